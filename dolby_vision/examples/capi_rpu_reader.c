@@ -0,0 +1,45 @@
+#include <stdio.h>
+#include <stdint.h>
+#include <inttypes.h>
+
+#include "helpers.h"
+
+int main(void) {
+    char *path = "../../assets/hevc_tests/regular_rpu_mel.bin";
+    size_t count = 0;
+    size_t invalid = 0;
+
+    DoviRpuReaderOpaque *reader = dovi_rpu_reader_open(path, false);
+    if (!reader) {
+        printf("Failed opening %s\n", path);
+        return 1;
+    }
+
+    DoviRpuOpaque *rpu;
+    while ((rpu = dovi_rpu_reader_next(reader))) {
+        const char *error = dovi_rpu_get_error(rpu);
+
+        if (error) {
+            // Invalid RPUs don't stop the stream
+            DoviErrorCode code = dovi_rpu_get_error_code(rpu);
+            printf("Invalid RPU %zu (error code %d): %s\n", count, code, error);
+            invalid++;
+        } else {
+            const DoviRpuDataHeader *header = dovi_rpu_get_header(rpu);
+
+            // Process the RPU..
+            if (count == 0) {
+                process_rpu_info(rpu, header);
+            }
+
+            dovi_rpu_free_header(header);
+        }
+
+        dovi_rpu_free(rpu);
+        count++;
+    }
+
+    printf("Read %zu RPUs, %zu invalid\n", count, invalid);
+
+    dovi_rpu_reader_close(reader);
+}
@@ -32,6 +32,14 @@ int main(void) {
         // Final video has letterboxing completely cropped
         ret = dovi_rpu_set_active_area_offsets(rpu, 0, 0, 0, 0);
 
+        // Mastering display and content light levels of the encode
+        ret = dovi_rpu_set_source_levels(rpu, 7, 3079);
+
+        DoviExtMetadataBlock level6 = {};
+        level6.tag = DoviExtMetadataBlock_Level6;
+        level6.level6 = { 1000, 1, 1000, 400 };
+        ret = dovi_rpu_replace_ext_block(rpu, &level6);
+
         const DoviData *rpu_payload = dovi_write_unspec62_nalu(rpu);
         
         // Do something with the edited payload
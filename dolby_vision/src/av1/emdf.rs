@@ -3,26 +3,55 @@ use bitvec_helpers::{
     bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
 };
 
+use crate::error::DoviError;
+
 /// Parse the expected EMDF container with fixed values according to spec
 /// Returns `emdf_payload_size`
 pub(crate) fn parse_emdf_container(reader: &mut BsIoSliceReader) -> Result<usize> {
     let emdf_version = reader.read::<2, u8>()?;
-    ensure!(emdf_version == 0);
+    ensure!(
+        emdf_version == 0,
+        DoviError::InvalidAv1Payload("emdf_version should be 0".into())
+    );
 
     let key_id = reader.read::<3, u8>()?;
-    ensure!(key_id == 6);
+    ensure!(
+        key_id == 6,
+        DoviError::InvalidAv1Payload("key_id should be 6".into())
+    );
 
     let emdf_payload_id = reader.read::<5, u8>()?;
-    ensure!(emdf_payload_id == 31);
+    ensure!(
+        emdf_payload_id == 31,
+        DoviError::InvalidAv1Payload("emdf_payload_id should be 31".into())
+    );
 
     let emdf_payload_id_ext = parse_variable_bits::<5>(reader)?;
-    ensure!(emdf_payload_id_ext == 225);
-
-    ensure!(!reader.read_bit()?); // smploffste = 0
-    ensure!(!reader.read_bit()?); // duratione = 0
-    ensure!(!reader.read_bit()?); // groupide = 0
-    ensure!(!reader.read_bit()?); // codecdatae = 0
-    ensure!(reader.read_bit()?); // discard_unknown_payload = 1
+    ensure!(
+        emdf_payload_id_ext == 225,
+        DoviError::InvalidAv1Payload("emdf_payload_id_ext should be 225".into())
+    );
+
+    ensure!(
+        !reader.read_bit()?,
+        DoviError::InvalidAv1Payload("smploffste should be 0".into())
+    );
+    ensure!(
+        !reader.read_bit()?,
+        DoviError::InvalidAv1Payload("duratione should be 0".into())
+    );
+    ensure!(
+        !reader.read_bit()?,
+        DoviError::InvalidAv1Payload("groupide should be 0".into())
+    );
+    ensure!(
+        !reader.read_bit()?,
+        DoviError::InvalidAv1Payload("codecdatae should be 0".into())
+    );
+    ensure!(
+        reader.read_bit()?,
+        DoviError::InvalidAv1Payload("discard_unknown_payload should be 1".into())
+    );
 
     let emdf_payload_size = parse_variable_bits::<8>(reader)? as usize;
     Ok(emdf_payload_size)
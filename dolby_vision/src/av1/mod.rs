@@ -5,6 +5,7 @@ use bitvec_helpers::{
 
 use crate::{
     av1::emdf::{parse_emdf_container, write_emdf_container_with_dovi_rpu_payload},
+    error::DoviError,
     rpu::dovi_rpu::{DoviRpu, FINAL_BYTE},
 };
 
@@ -26,7 +27,7 @@ pub fn parse_itu_t35_dovi_metadata_obu(data: &[u8]) -> Result<DoviRpu> {
 
 pub(crate) fn av1_validated_trimmed_data(data: &[u8]) -> Result<&[u8]> {
     if data.len() < 34 {
-        bail!("Invalid RPU length: {}", data.len());
+        bail!(DoviError::InvalidRpuLength(data.len()));
     }
 
     let data = if data[0] == 0xB5 {
@@ -39,10 +40,10 @@ pub(crate) fn av1_validated_trimmed_data(data: &[u8]) -> Result<&[u8]> {
 
     let trimmed_data = match &data[..ITU_T35_DOVI_RPU_PAYLOAD_HEADER_LEN] {
         ITU_T35_DOVI_RPU_PAYLOAD_HEADER => data,
-        _ => bail!(
-            "Invalid AV1 RPU payload header: {:?}",
+        _ => bail!(DoviError::InvalidAv1Payload(format!(
+            "Invalid payload header: {:?}",
             &data[..ITU_T35_DOVI_RPU_PAYLOAD_HEADER_LEN]
-        ),
+        ))),
     };
 
     Ok(trimmed_data)
@@ -53,16 +54,26 @@ pub(crate) fn convert_av1_rpu_payload_to_regular(data: &[u8]) -> Result<Vec<u8>>
     let mut reader = BsIoSliceReader::from_slice(data);
 
     let itu_t_t35_terminal_provider_code = reader.read::<16, u16>()?;
-    ensure!(itu_t_t35_terminal_provider_code == 0x3B);
+    ensure!(
+        itu_t_t35_terminal_provider_code == 0x3B,
+        DoviError::InvalidAv1Payload("itu_t_t35_terminal_provider_code should be 0x3B".into())
+    );
 
     let itu_t_t35_terminal_provider_oriented_code = reader.read::<32, u32>()?;
-    ensure!(itu_t_t35_terminal_provider_oriented_code == 0x800);
+    ensure!(
+        itu_t_t35_terminal_provider_oriented_code == 0x800,
+        DoviError::InvalidAv1Payload(
+            "itu_t_t35_terminal_provider_oriented_code should be 0x800".into()
+        )
+    );
 
     let emdf_payload_size = parse_emdf_container(&mut reader)?;
     let mut converted_buf = vec![0; emdf_payload_size + 1];
     converted_buf[0] = 0x19;
 
-    reader.read_bytes(&mut converted_buf[1..])?;
+    reader
+        .read_bytes(&mut converted_buf[1..])
+        .map_err(|e| DoviError::map_truncated(e.into()))?;
 
     Ok(converted_buf)
 }
@@ -72,7 +83,7 @@ pub(crate) fn convert_av1_rpu_payload_to_regular(data: &[u8]) -> Result<Vec<u8>>
 ///
 /// Returns payload for AV1 ITU T-T.35 metadata OBU
 pub fn convert_regular_rpu_to_av1_payload(data: &[u8]) -> Result<Vec<u8>> {
-    ensure!(data[0] == 0x19);
+    ensure!(data.first() == Some(&0x19), DoviError::InvalidStartBytes);
 
     // The EMDF payload must not include any trailing bytes after 0x80 terminator
     let trailing_zeroes = data.iter().rev().take_while(|b| **b == 0).count();
@@ -80,7 +91,7 @@ pub fn convert_regular_rpu_to_av1_payload(data: &[u8]) -> Result<Vec<u8>> {
     let last_byte = data[rpu_end - 1];
 
     if last_byte != FINAL_BYTE {
-        bail!("Invalid RPU last byte: {}", last_byte);
+        bail!(DoviError::InvalidFinalByte(last_byte));
     }
 
     // Exclude 0x19 prefix
@@ -0,0 +1,52 @@
+use crate::error::DoviError;
+
+/// Error code of the last failed operation, see `dovi_rpu_get_error_code`.
+///
+/// The codes map the library `DoviError` variants.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// No error
+    #[default]
+    None = 0,
+    /// Error without a specific code, see the error message
+    Other = 1,
+    /// The buffer is too short to contain an RPU
+    InvalidRpuLength = 2,
+    /// The buffer does not start with a known RPU prefix
+    InvalidStartBytes = 3,
+    /// The RPU does not end with the 0x80 terminator byte
+    InvalidFinalByte = 4,
+    /// The CRC32 does not match the RPU data
+    CrcMismatch = 5,
+    /// The RPU data is truncated
+    Truncated = 6,
+    /// Invalid RPU header values
+    InvalidHeader = 7,
+    /// Invalid values for the profile, or unsupported profile conversion
+    InvalidProfile = 8,
+    /// Invalid extension metadata block
+    BlockValidation = 9,
+    /// Invalid AV1 ITU-T T.35 metadata payload
+    InvalidAv1Payload = 10,
+    /// Invalid CM XML metadata
+    InvalidXml = 11,
+}
+
+impl From<&anyhow::Error> for ErrorCode {
+    fn from(err: &anyhow::Error) -> Self {
+        match DoviError::find(err) {
+            Some(DoviError::InvalidRpuLength(_)) => Self::InvalidRpuLength,
+            Some(DoviError::InvalidStartBytes) => Self::InvalidStartBytes,
+            Some(DoviError::InvalidFinalByte(_)) => Self::InvalidFinalByte,
+            Some(DoviError::CrcMismatch { .. }) => Self::CrcMismatch,
+            Some(DoviError::Truncated) => Self::Truncated,
+            Some(DoviError::InvalidHeader(_)) => Self::InvalidHeader,
+            Some(DoviError::InvalidProfile { .. }) => Self::InvalidProfile,
+            Some(DoviError::BlockValidation { .. }) => Self::BlockValidation,
+            Some(DoviError::InvalidAv1Payload(_)) => Self::InvalidAv1Payload,
+            Some(DoviError::InvalidXml(_)) => Self::InvalidXml,
+            None => Self::Other,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::rpu::extension_metadata::blocks::{
+    ExtMetadataBlock as RuExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel2,
+    ExtMetadataBlockLevel3, ExtMetadataBlockLevel4, ExtMetadataBlockLevel5, ExtMetadataBlockLevel6,
+    ExtMetadataBlockLevel8, ExtMetadataBlockLevel9, ExtMetadataBlockLevel10,
+    ExtMetadataBlockLevel11, ExtMetadataBlockLevel254, ExtMetadataBlockLevel255,
+};
+
+/// C tagged union of a single ext_metadata_block(), for editing
+#[repr(C, u8)]
+pub enum ExtMetadataBlock {
+    Level1(ExtMetadataBlockLevel1),
+    Level2(ExtMetadataBlockLevel2),
+    Level3(ExtMetadataBlockLevel3),
+    Level4(ExtMetadataBlockLevel4),
+    Level5(ExtMetadataBlockLevel5),
+    Level6(ExtMetadataBlockLevel6),
+    Level8(ExtMetadataBlockLevel8),
+    Level9(ExtMetadataBlockLevel9),
+    Level10(ExtMetadataBlockLevel10),
+    Level11(ExtMetadataBlockLevel11),
+    Level254(ExtMetadataBlockLevel254),
+    Level255(ExtMetadataBlockLevel255),
+}
+
+impl From<&ExtMetadataBlock> for RuExtMetadataBlock {
+    fn from(block: &ExtMetadataBlock) -> Self {
+        match block {
+            ExtMetadataBlock::Level1(b) => Self::Level1(b.clone()),
+            ExtMetadataBlock::Level2(b) => Self::Level2(b.clone()),
+            ExtMetadataBlock::Level3(b) => Self::Level3(b.clone()),
+            ExtMetadataBlock::Level4(b) => Self::Level4(b.clone()),
+            ExtMetadataBlock::Level5(b) => Self::Level5(b.clone()),
+            ExtMetadataBlock::Level6(b) => Self::Level6(b.clone()),
+            ExtMetadataBlock::Level8(b) => Self::Level8(b.clone()),
+            ExtMetadataBlock::Level9(b) => Self::Level9(b.clone()),
+            ExtMetadataBlock::Level10(b) => Self::Level10(b.clone()),
+            ExtMetadataBlock::Level11(b) => Self::Level11(b.clone()),
+            ExtMetadataBlock::Level254(b) => Self::Level254(b.clone()),
+            ExtMetadataBlock::Level255(b) => Self::Level255(b.clone()),
+        }
+    }
+}
@@ -1,17 +1,23 @@
 use crate::rpu::NUM_COMPONENTS;
 
 mod buffers;
+mod error;
+mod ext_metadata_block;
 mod extension_metadata;
 mod rpu;
 mod rpu_data_header;
 mod rpu_data_mapping;
 mod rpu_data_nlq;
+mod rpu_reader;
 mod vdr_dm_data;
 
 pub use buffers::*;
+pub use error::ErrorCode;
+pub use ext_metadata_block::ExtMetadataBlock;
 pub use extension_metadata::DmData;
 pub use rpu::{RpuOpaque, RpuOpaqueList};
 pub use rpu_data_header::RpuDataHeader;
 pub use rpu_data_mapping::RpuDataMapping;
 pub use rpu_data_nlq::RpuDataNlq;
+pub use rpu_reader::RpuReaderOpaque;
 pub use vdr_dm_data::VdrDmData;
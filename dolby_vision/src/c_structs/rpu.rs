@@ -1,10 +1,10 @@
-use std::ffi::CString;
+use std::{ffi::CString, ptr::null};
 
 use libc::{c_char, size_t};
 
 use crate::rpu::dovi_rpu::DoviRpu;
 
-use super::Freeable;
+use super::{ErrorCode, Freeable};
 
 /// Opaque Dolby Vision RPU.
 ///
@@ -15,6 +15,8 @@ pub struct RpuOpaque {
     pub rpu: Option<DoviRpu>,
     /// Error String of the parsing, in cases of failure.
     pub error: Option<CString>,
+    /// Error code of the last failure.
+    pub error_code: ErrorCode,
 }
 
 /// Heap allocated list of valid RPU pointers
@@ -24,17 +26,72 @@ pub struct RpuOpaqueList {
     pub len: size_t,
 
     pub error: *const c_char,
+    pub error_code: ErrorCode,
 }
 
 impl RpuOpaque {
     pub(crate) fn new(rpu: Option<DoviRpu>) -> Self {
-        Self { rpu, error: None }
+        Self {
+            rpu,
+            error: None,
+            error_code: ErrorCode::None,
+        }
     }
 
     pub(crate) fn invalid_with_error(msg: &str) -> Self {
         Self {
             rpu: None,
             error: CString::new(msg).ok(),
+            error_code: ErrorCode::Other,
+        }
+    }
+
+    pub(crate) fn set_error(&mut self, msg: String, err: &anyhow::Error) {
+        self.error = CString::new(msg).ok();
+        self.error_code = ErrorCode::from(err);
+    }
+}
+
+impl RpuOpaqueList {
+    pub(crate) fn new(rpus: Vec<DoviRpu>) -> Self {
+        let opaque_list: Vec<*mut RpuOpaque> = rpus
+            .into_iter()
+            .map(|rpu| Box::into_raw(Box::new(RpuOpaque::new(Some(rpu)))))
+            .collect();
+
+        Self {
+            len: opaque_list.len(),
+            list: Box::into_raw(opaque_list.into_boxed_slice()) as *const *mut RpuOpaque,
+            error: null(),
+            error_code: ErrorCode::None,
+        }
+    }
+
+    /// # Safety
+    /// The list pointer must be valid for `len` elements, or null.
+    pub(crate) unsafe fn as_slice(&self) -> &[*mut RpuOpaque] {
+        if self.list.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.list, self.len) }
+        }
+    }
+
+    pub(crate) fn with_error(msg: &str) -> Self {
+        let mut list = Self::new(Vec::new());
+
+        if let Ok(err) = CString::new(msg) {
+            list.error = err.into_raw();
+        }
+        list.error_code = ErrorCode::Other;
+
+        list
+    }
+
+    pub(crate) fn with_error_code(msg: &str, err: &anyhow::Error) -> Self {
+        Self {
+            error_code: ErrorCode::from(err),
+            ..Self::with_error(msg)
         }
     }
 }
@@ -43,7 +100,12 @@ impl From<Result<DoviRpu, anyhow::Error>> for RpuOpaque {
     fn from(res: Result<DoviRpu, anyhow::Error>) -> Self {
         match res {
             Ok(rpu) => Self::new(Some(rpu)),
-            Err(e) => Self::invalid_with_error(&format!("Failed parsing RPU: {e}")),
+            Err(e) => {
+                let mut opaque = Self::new(None);
+                opaque.set_error(format!("Failed parsing RPU: {e}"), &e);
+
+                opaque
+            }
         }
     }
 }
@@ -0,0 +1,24 @@
+use std::io::Read;
+
+use crate::rpu::utils::{RpuReader, RpuReaderFormat};
+
+/// Opaque streaming RPU reader, from a file or memory buffer.
+///
+/// Use dovi_rpu_reader_close to free.
+pub struct RpuReaderOpaque {
+    pub(crate) reader: RpuReader<Box<dyn Read>>,
+}
+
+impl RpuReaderOpaque {
+    pub(crate) fn new<R: Read + 'static>(reader: R, av1: bool) -> Self {
+        let format = if av1 {
+            RpuReaderFormat::Av1ItuT35
+        } else {
+            RpuReaderFormat::Hevc
+        };
+
+        Self {
+            reader: RpuReader::new(Box::new(reader), format),
+        }
+    }
+}
@@ -1,14 +1,21 @@
 #![deny(missing_docs)]
 
+use anyhow::Context;
 use libc::{c_char, size_t};
 use std::{
-    ffi::{CStr, CString},
+    ffi::CStr,
+    fs::File,
+    io::Cursor,
     path::PathBuf,
     ptr::{null, null_mut},
     slice,
 };
 
-use crate::rpu::{ConversionMode, dovi_rpu::DoviRpu, utils::parse_rpu_file};
+use crate::rpu::{
+    ConversionMode, dovi_rpu::DoviRpu, editor::EditConfig, generate::GenerateConfig,
+    utils::parse_rpu_file,
+};
+use crate::xml::{CmXmlParser, XmlParserOpts};
 
 use super::c_structs::*;
 
@@ -53,6 +60,31 @@ pub unsafe extern "C" fn dovi_parse_itu_t35_dovi_metadata_obu(
     Box::into_raw(Box::new(RpuOpaque::from(res)))
 }
 
+/// # Safety
+/// The pointer to the JSON string must be valid, and the string null terminated.
+///
+/// Parse a Dolby Vision RPU from its JSON representation, as written by `dovi_rpu_to_json`.
+/// Adds an error if the parsing fails, or if the RPU is invalid and could not be written.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_from_json(json: *const c_char) -> *mut RpuOpaque {
+    if json.is_null() {
+        return Box::into_raw(Box::new(RpuOpaque::invalid_with_error(
+            "dovi_rpu_from_json: null JSON pointer",
+        )));
+    }
+
+    let res = unsafe { CStr::from_ptr(json) }
+        .to_str()
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(serde_json::from_str::<DoviRpu>(json)?))
+        .and_then(|rpu| {
+            rpu.validate()?;
+            Ok(rpu)
+        });
+
+    Box::into_raw(Box::new(RpuOpaque::from(res)))
+}
+
 /// # Safety
 /// The pointer to the data must be valid.
 ///
@@ -105,6 +137,23 @@ pub unsafe extern "C" fn dovi_rpu_get_error(ptr: *const RpuOpaque) -> *const c_c
     }
 }
 
+/// # Safety
+/// The pointer to the opaque struct must be valid.
+///
+/// Get the error code of the last failed RpuOpaque operation.
+///
+/// Returns `DoviErrorCode_None` if no error occurred.
+/// The codes allow deciding whether to skip or fail on an invalid RPU,
+/// without matching the error message from `dovi_rpu_get_error`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_get_error_code(ptr: *const RpuOpaque) -> ErrorCode {
+    if ptr.is_null() {
+        return ErrorCode::Other;
+    }
+
+    unsafe { &*ptr }.error_code
+}
+
 /// # Safety
 /// The data pointer should exist, and be allocated by Rust.
 ///
@@ -136,7 +185,7 @@ pub unsafe extern "C" fn dovi_write_rpu(ptr: *mut RpuOpaque) -> *const Data {
         match rpu.write_rpu() {
             Ok(buf) => Box::into_raw(Box::new(Data::from(buf))),
             Err(e) => {
-                opaque.error = CString::new(format!("Failed writing byte buffer: {e}")).ok();
+                opaque.set_error(format!("Failed writing byte buffer: {e}"), &e);
                 null_mut()
             }
         }
@@ -162,7 +211,33 @@ pub unsafe extern "C" fn dovi_write_unspec62_nalu(ptr: *mut RpuOpaque) -> *const
         match rpu.write_hevc_unspec62_nalu() {
             Ok(buf) => Box::into_raw(Box::new(Data::from(buf))),
             Err(e) => {
-                opaque.error = CString::new(format!("Failed writing byte buffer: {e}")).ok();
+                opaque.set_error(format!("Failed writing byte buffer: {e}"), &e);
+                null_mut()
+            }
+        }
+    } else {
+        null_mut()
+    }
+}
+
+/// # Safety
+/// The struct pointer must be valid.
+///
+/// Serializes the RPU to JSON, as UTF-8 bytes without a null terminator.
+/// If an error occurs in the serialization, it is logged to RpuOpaque.error
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_to_json(ptr: *mut RpuOpaque) -> *const Data {
+    if ptr.is_null() {
+        return null_mut();
+    }
+
+    let opaque = unsafe { &mut *ptr };
+
+    if let Some(rpu) = &opaque.rpu {
+        match serde_json::to_vec(rpu) {
+            Ok(buf) => Box::into_raw(Box::new(Data::from(buf))),
+            Err(e) => {
+                opaque.set_error(format!("Failed serializing to JSON: {e}"), &e.into());
                 null_mut()
             }
         }
@@ -200,8 +275,7 @@ pub unsafe extern "C" fn dovi_convert_rpu_with_mode(ptr: *mut RpuOpaque, mode: u
         match rpu.convert_with_mode(mode) {
             Ok(_) => 0,
             Err(e) => {
-                opaque.error =
-                    CString::new(format!("Failed converting with mode {mode}: {e}")).ok();
+                opaque.set_error(format!("Failed converting with mode {mode}: {e}"), &e);
                 -1
             }
         }
@@ -330,44 +404,25 @@ pub unsafe extern "C" fn dovi_rpu_free_vdr_dm_data(ptr: *const VdrDmData) {
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn dovi_parse_rpu_bin_file(path: *const c_char) -> *const RpuOpaqueList {
     if !path.is_null() {
-        let mut rpu_list = RpuOpaqueList {
-            list: null(),
-            len: 0,
-            error: null(),
-        };
-        let mut error = None;
-
-        if let Ok(str) = unsafe { CStr::from_ptr(path) }.to_str() {
+        let rpu_list = if let Ok(str) = unsafe { CStr::from_ptr(path) }.to_str() {
             let path = PathBuf::from(str);
 
             if path.is_file() {
                 match parse_rpu_file(path) {
-                    Ok(rpus) => {
-                        rpu_list.len = rpus.len();
-
-                        let opaque_list: Vec<*mut RpuOpaque> = rpus
-                            .into_iter()
-                            .map(|rpu| Box::into_raw(Box::new(RpuOpaque::new(Some(rpu)))))
-                            .collect();
-
-                        rpu_list.list =
-                            Box::into_raw(opaque_list.into_boxed_slice()) as *const *mut RpuOpaque;
-                    }
-                    Err(e) => {
-                        error = Some(format!("parse_rpu_bin_file: Errored while parsing: {e}"))
-                    }
+                    Ok(rpus) => RpuOpaqueList::new(rpus),
+                    Err(e) => RpuOpaqueList::with_error_code(
+                        &format!("parse_rpu_bin_file: Errored while parsing: {e}"),
+                        &e,
+                    ),
                 }
             } else {
-                error = Some("parse_rpu_bin_file: Input file does not exist".to_string());
+                RpuOpaqueList::with_error("parse_rpu_bin_file: Input file does not exist")
             }
         } else {
-            error =
-                Some("parse_rpu_bin_file: Failed parsing the input path as a string".to_string());
-        }
-
-        if let Some(err) = error.and_then(|err| CString::new(err).ok()) {
-            rpu_list.error = err.into_raw();
-        }
+            RpuOpaqueList::with_error(
+                "parse_rpu_bin_file: Failed parsing the input path as a string",
+            )
+        };
 
         return Box::into_raw(Box::new(rpu_list));
     }
@@ -389,6 +444,290 @@ pub unsafe extern "C" fn dovi_rpu_list_free(ptr: *const RpuOpaqueList) {
     }
 }
 
+/// # Safety
+/// The pointer to the path must be valid, and the string null terminated.
+///
+/// Opens a streaming RPU reader for a file, to parse one RPU at a time with `dovi_rpu_reader_next`.
+///   - `av1`: false for HEVC UNSPEC62 NALUs with start codes, as written by `dovi_tool extract-rpu`.
+///     true for AV1 ITU-T T.35 payloads, each prefixed by its size as a 32-bit big-endian integer.
+///
+/// Returns null if the file could not be opened.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_reader_open(
+    path: *const c_char,
+    av1: bool,
+) -> *mut RpuReaderOpaque {
+    if path.is_null() {
+        return null_mut();
+    }
+
+    let Ok(path) = unsafe { CStr::from_ptr(path) }.to_str() else {
+        return null_mut();
+    };
+
+    match File::open(path) {
+        Ok(file) => Box::into_raw(Box::new(RpuReaderOpaque::new(file, av1))),
+        Err(_) => null_mut(),
+    }
+}
+
+/// # Safety
+/// The pointer to the data must be valid.
+///
+/// Opens a streaming RPU reader for a memory buffer. The data is copied.
+/// See `dovi_rpu_reader_open` for the `av1` option.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_reader_open_buffer(
+    buf: *const u8,
+    len: size_t,
+    av1: bool,
+) -> *mut RpuReaderOpaque {
+    if buf.is_null() {
+        return null_mut();
+    }
+
+    let data = unsafe { slice::from_raw_parts(buf, len) }.to_vec();
+
+    Box::into_raw(Box::new(RpuReaderOpaque::new(Cursor::new(data), av1)))
+}
+
+/// # Safety
+/// The reader pointer must be valid.
+///
+/// Parses the next RPU of the stream.
+/// Invalid RPUs are returned with an error, and the following RPUs can still be read.
+///
+/// Returns null at the end of the stream.
+/// The returned `DoviRpuOpaque` must be freed with `dovi_rpu_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_reader_next(ptr: *mut RpuReaderOpaque) -> *mut RpuOpaque {
+    if ptr.is_null() {
+        return null_mut();
+    }
+
+    let opaque = unsafe { &mut *ptr };
+
+    match opaque.reader.next() {
+        Some(res) => Box::into_raw(Box::new(RpuOpaque::from(res))),
+        None => null_mut(),
+    }
+}
+
+/// # Safety
+/// The reader pointer must be valid.
+///
+/// Closes the reader and frees its memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_reader_close(ptr: *mut RpuReaderOpaque) {
+    if !ptr.is_null() {
+        unsafe {
+            drop(Box::from_raw(ptr));
+        }
+    }
+}
+
+/// # Safety
+/// The pointer to the struct must be valid.
+///
+/// Serializes the RPU list to a JSON array, as UTF-8 bytes without a null terminator.
+/// Invalid RPUs are serialized as `null`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_list_to_json(ptr: *const RpuOpaqueList) -> *const Data {
+    if ptr.is_null() {
+        return null();
+    }
+
+    let opaque_list = unsafe { (*ptr).as_slice() };
+
+    let rpus: Vec<Option<&DoviRpu>> = opaque_list
+        .iter()
+        .map(|opaque| unsafe { opaque.as_ref() }.and_then(|opaque| opaque.rpu.as_ref()))
+        .collect();
+
+    match serde_json::to_vec(&rpus) {
+        Ok(buf) => Box::into_raw(Box::new(Data::from(buf))),
+        Err(_) => null(),
+    }
+}
+
+/// # Safety
+/// The pointer to the JSON string must be valid, and the string null terminated.
+///
+/// Parses a list of RPUs from a JSON array, as written by `dovi_rpu_list_to_json`.
+/// Every RPU is validated, the list is empty with an error if any is invalid.
+///
+/// Returns the heap allocated `DoviRpuList` as a pointer.
+/// The returned pointer may be null, or the list could be empty if an error occurred.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_list_from_json(json: *const c_char) -> *const RpuOpaqueList {
+    if json.is_null() {
+        return null();
+    }
+
+    let res = unsafe { CStr::from_ptr(json) }
+        .to_str()
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(serde_json::from_str::<Vec<DoviRpu>>(json)?))
+        .and_then(|rpus| {
+            for (i, rpu) in rpus.iter().enumerate() {
+                rpu.validate().with_context(|| format!("RPU {i}"))?;
+            }
+
+            Ok(rpus)
+        });
+
+    let rpu_list = match res {
+        Ok(rpus) => RpuOpaqueList::new(rpus),
+        Err(e) => RpuOpaqueList::with_error_code(
+            &format!("rpu_list_from_json: Failed parsing: {e:#}"),
+            &e,
+        ),
+    };
+
+    Box::into_raw(Box::new(rpu_list))
+}
+
+/// # Safety
+/// The pointer to the JSON string must be valid, and the string null terminated.
+///
+/// Generates RPUs from a `GenerateConfig` JSON, as used by `dovi_tool generate --json`.
+///
+/// Returns the heap allocated `DoviRpuList` as a pointer.
+/// The returned pointer may be null, or the list could be empty if an error occurred.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_generate_rpus_from_json(json: *const c_char) -> *const RpuOpaqueList {
+    if json.is_null() {
+        return null();
+    }
+
+    let rpu_list = match unsafe { CStr::from_ptr(json) }.to_str() {
+        Ok(json) => {
+            let res = serde_json::from_str::<GenerateConfig>(json)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut config| {
+                    config.fill_defaults()?;
+                    config.generate_rpu_list()
+                });
+
+            match res {
+                Ok(rpus) => RpuOpaqueList::new(rpus),
+                Err(e) => RpuOpaqueList::with_error_code(
+                    &format!("generate_rpus_from_json: Failed generating RPUs: {e}"),
+                    &e,
+                ),
+            }
+        }
+        Err(_) => RpuOpaqueList::with_error(
+            "generate_rpus_from_json: Failed parsing the JSON as an UTF-8 string",
+        ),
+    };
+
+    Box::into_raw(Box::new(rpu_list))
+}
+
+/// # Safety
+/// The pointer to the XML string must be valid, and the string null terminated.
+/// The output and track pointers must be valid or null.
+///
+/// Generates RPUs from a Dolby Vision CM XML metadata file content.
+///   - `canvas_width` and `canvas_height` are required for L5 metadata, 0 when unknown.
+///   - `output` and `track` select the `Output` and video track, by name or index.
+///     The first ones are used when null.
+///
+/// Returns the heap allocated `DoviRpuList` as a pointer.
+/// The returned pointer may be null, or the list could be empty if an error occurred.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_generate_rpus_from_xml(
+    xml: *const c_char,
+    canvas_width: u16,
+    canvas_height: u16,
+    output: *const c_char,
+    track: *const c_char,
+) -> *const RpuOpaqueList {
+    if xml.is_null() {
+        return null();
+    }
+
+    let optional_str = |ptr: *const c_char| {
+        (!ptr.is_null()).then(|| {
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+
+    let rpu_list = match unsafe { CStr::from_ptr(xml) }.to_str() {
+        Ok(xml) => {
+            let opts = XmlParserOpts {
+                canvas_width: (canvas_width > 0).then_some(canvas_width),
+                canvas_height: (canvas_height > 0).then_some(canvas_height),
+                output: optional_str(output),
+                track: optional_str(track),
+            };
+
+            let res = CmXmlParser::new(xml.to_string(), opts)
+                .and_then(|parser| parser.config.generate_rpu_list());
+
+            match res {
+                Ok(rpus) => RpuOpaqueList::new(rpus),
+                Err(e) => RpuOpaqueList::with_error_code(
+                    &format!("generate_rpus_from_xml: Failed generating RPUs: {e}"),
+                    &e,
+                ),
+            }
+        }
+        Err(_) => RpuOpaqueList::with_error(
+            "generate_rpus_from_xml: Failed parsing the XML as an UTF-8 string",
+        ),
+    };
+
+    Box::into_raw(Box::new(rpu_list))
+}
+
+/// # Safety
+/// The pointer to the RPU list must be valid.
+/// The pointer to the JSON string must be valid, and the string null terminated.
+///
+/// Applies an editor config JSON, as used by `dovi_tool editor`, to a list of RPUs.
+/// The input list is left untouched.
+///
+/// Returns a new heap allocated `DoviRpuList`, with the removed and duplicated frames applied.
+/// The returned pointer may be null, or the list could be empty if an error occurred.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_list_apply_edit_config(
+    ptr: *const RpuOpaqueList,
+    json: *const c_char,
+) -> *const RpuOpaqueList {
+    if ptr.is_null() || json.is_null() {
+        return null();
+    }
+
+    let opaque_list = unsafe { (*ptr).as_slice() };
+
+    let rpus = opaque_list
+        .iter()
+        .enumerate()
+        .map(|(i, opaque)| {
+            unsafe { opaque.as_ref() }
+                .and_then(|opaque| opaque.rpu.clone())
+                .ok_or_else(|| anyhow::anyhow!("Invalid RPU at index {i}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>();
+
+    let res = unsafe { CStr::from_ptr(json) }
+        .to_str()
+        .map_err(|_| anyhow::anyhow!("Failed parsing the JSON as an UTF-8 string"))
+        .and_then(EditConfig::from_json)
+        .and_then(|config| config.apply(rpus?));
+
+    let rpu_list = match res {
+        Ok(rpus) => RpuOpaqueList::new(rpus),
+        Err(e) => RpuOpaqueList::with_error_code(&format!("rpu_list_apply_edit_config: {e:#}"), &e),
+    };
+
+    Box::into_raw(Box::new(rpu_list))
+}
+
 /// # Safety
 /// The struct pointer must be valid.
 ///
@@ -412,8 +751,7 @@ pub unsafe extern "C" fn dovi_rpu_set_active_area_offsets(
         match rpu.set_active_area_offsets(left, right, top, bottom) {
             Ok(_) => 0,
             Err(e) => {
-                opaque.error =
-                    CString::new(format!("Failed editing active area offsets: {e}")).ok();
+                opaque.set_error(format!("Failed editing active area offsets: {e}"), &e);
                 -1
             }
         }
@@ -443,6 +781,160 @@ pub unsafe extern "C" fn dovi_rpu_remove_mapping(ptr: *mut RpuOpaque) -> i32 {
     }
 }
 
+/// # Safety
+/// The struct pointer must be valid.
+/// The block pointer must be valid.
+///
+/// Adds an extension metadata block.
+/// If an error occurs, it is logged to RpuOpaque.error.
+/// Returns 0 if successful, -1 otherwise.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_add_ext_block(
+    ptr: *mut RpuOpaque,
+    block: *const ExtMetadataBlock,
+) -> i32 {
+    if ptr.is_null() || block.is_null() {
+        return -1;
+    }
+
+    let opaque = unsafe { &mut *ptr };
+    let block = unsafe { &*block };
+
+    if let Some(rpu) = &mut opaque.rpu {
+        match rpu.add_metadata_block(block.into()) {
+            Ok(_) => 0,
+            Err(e) => {
+                opaque.set_error(format!("Failed adding metadata block: {e}"), &e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// # Safety
+/// The struct pointer must be valid.
+/// The block pointer must be valid.
+///
+/// Replaces the extension metadata block of the same level, or adds it if missing.
+/// L2, L8 and L10 blocks are replaced for the same target display index.
+///
+/// If an error occurs, it is logged to RpuOpaque.error.
+/// Returns 0 if successful, -1 otherwise.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_replace_ext_block(
+    ptr: *mut RpuOpaque,
+    block: *const ExtMetadataBlock,
+) -> i32 {
+    if ptr.is_null() || block.is_null() {
+        return -1;
+    }
+
+    let opaque = unsafe { &mut *ptr };
+    let block = unsafe { &*block };
+
+    if let Some(rpu) = &mut opaque.rpu {
+        match rpu.replace_metadata_block(block.into()) {
+            Ok(_) => 0,
+            Err(e) => {
+                opaque.set_error(format!("Failed replacing metadata block: {e}"), &e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// # Safety
+/// The struct pointer must be valid.
+///
+/// Removes every extension metadata block of the specified level.
+/// If an error occurs, it is logged to RpuOpaque.error.
+/// Returns 0 if successful, -1 otherwise.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_remove_ext_blocks(ptr: *mut RpuOpaque, level: u8) -> i32 {
+    if ptr.is_null() {
+        return -1;
+    }
+
+    let opaque = unsafe { &mut *ptr };
+
+    if let Some(rpu) = &mut opaque.rpu {
+        match rpu.remove_metadata_level(level) {
+            Ok(_) => 0,
+            Err(e) => {
+                opaque.set_error(format!("Failed removing L{level} metadata blocks: {e}"), &e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// # Safety
+/// The struct pointer must be valid.
+///
+/// Sets the `scene_refresh_flag`, marking the RPU as the first frame of a scene.
+/// If an error occurs, it is logged to RpuOpaque.error.
+/// Returns 0 if successful, -1 otherwise.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_set_scene_refresh_flag(
+    ptr: *mut RpuOpaque,
+    scene_refresh_flag: bool,
+) -> i32 {
+    if ptr.is_null() {
+        return -1;
+    }
+
+    let opaque = unsafe { &mut *ptr };
+
+    if let Some(rpu) = &mut opaque.rpu {
+        match rpu.set_scene_refresh_flag(scene_refresh_flag) {
+            Ok(_) => 0,
+            Err(e) => {
+                opaque.set_error(format!("Failed setting scene_refresh_flag: {e}"), &e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// # Safety
+/// The struct pointer must be valid.
+///
+/// Sets the source (mastering display) min and max PQ values, as 12 bit codes.
+/// If an error occurs, it is logged to RpuOpaque.error.
+/// Returns 0 if successful, -1 otherwise.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dovi_rpu_set_source_levels(
+    ptr: *mut RpuOpaque,
+    source_min_pq: u16,
+    source_max_pq: u16,
+) -> i32 {
+    if ptr.is_null() {
+        return -1;
+    }
+
+    let opaque = unsafe { &mut *ptr };
+
+    if let Some(rpu) = &mut opaque.rpu {
+        match rpu.set_source_levels(source_min_pq, source_max_pq) {
+            Ok(_) => 0,
+            Err(e) => {
+                opaque.set_error(format!("Failed setting source levels: {e}"), &e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
 /// # Safety
 /// The struct pointer must be valid.
 ///
@@ -462,7 +954,7 @@ pub unsafe extern "C" fn dovi_write_av1_rpu_metadata_obu_t35_payload(
         match rpu.write_av1_rpu_metadata_obu_t35_payload() {
             Ok(buf) => Box::into_raw(Box::new(Data::from(buf))),
             Err(e) => {
-                opaque.error = CString::new(format!("Failed writing byte buffer: {e}")).ok();
+                opaque.set_error(format!("Failed writing byte buffer: {e}"), &e);
                 null_mut()
             }
         }
@@ -490,7 +982,7 @@ pub unsafe extern "C" fn dovi_write_av1_rpu_metadata_obu_t35_complete(
         match rpu.write_av1_rpu_metadata_obu_t35_complete() {
             Ok(buf) => Box::into_raw(Box::new(Data::from(buf))),
             Err(e) => {
-                opaque.error = CString::new(format!("Failed writing byte buffer: {e}")).ok();
+                opaque.set_error(format!("Failed writing byte buffer: {e}"), &e);
                 null_mut()
             }
         }
@@ -0,0 +1,160 @@
+use std::{fmt, io};
+
+/// Typed errors returned by the library.
+///
+/// Functions still return `anyhow::Result`, the typed error can be recovered
+/// from the returned error with `DoviError::find`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DoviError {
+    /// The buffer is too short to contain an RPU
+    InvalidRpuLength(usize),
+    /// The buffer does not start with a known RPU prefix
+    InvalidStartBytes,
+    /// The RPU does not end with the 0x80 terminator byte
+    InvalidFinalByte(u8),
+    /// The `rpu_data_crc32` does not match the CRC32 computed from the data
+    CrcMismatch { expected: u32, got: u32 },
+    /// The RPU data ended before all the syntax elements could be read
+    Truncated,
+    /// Invalid `rpu_data_header` values
+    InvalidHeader(String),
+    /// Header values that are invalid for the profile, or unsupported profile conversion
+    InvalidProfile { profile: u8, reason: String },
+    /// Invalid extension metadata block, or invalid block count for a level
+    BlockValidation { level: u8, reason: String },
+    /// Invalid AV1 ITU-T T.35 metadata payload
+    InvalidAv1Payload(String),
+    /// Invalid or unsupported CM XML metadata
+    InvalidXml(String),
+}
+
+impl DoviError {
+    /// Finds the typed error in the chain of an `anyhow::Error`, if any.
+    pub fn find(err: &anyhow::Error) -> Option<&DoviError> {
+        err.downcast_ref::<DoviError>()
+            .or_else(|| err.chain().find_map(|e| e.downcast_ref::<DoviError>()))
+    }
+
+    pub(crate) fn block(level: u8, reason: impl Into<String>) -> Self {
+        Self::BlockValidation {
+            level,
+            reason: reason.into(),
+        }
+    }
+
+    /// Marks errors caused by reading past the end of the data as `Truncated`
+    pub(crate) fn map_truncated(err: anyhow::Error) -> anyhow::Error {
+        let eof = err.chain().any(|e| {
+            e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::UnexpectedEof)
+        });
+
+        if eof && Self::find(&err).is_none() {
+            err.context(DoviError::Truncated)
+        } else {
+            err
+        }
+    }
+}
+
+impl fmt::Display for DoviError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoviError::InvalidRpuLength(len) => write!(f, "Invalid RPU length: {len}"),
+            DoviError::InvalidStartBytes => write!(f, "Invalid RPU data start bytes"),
+            DoviError::InvalidFinalByte(byte) => write!(f, "Invalid RPU last byte: {byte}"),
+            DoviError::CrcMismatch { expected, got } => write!(
+                f,
+                "RPU CRC32 does not match the data. Received: {got}, expected {expected}"
+            ),
+            DoviError::Truncated => write!(f, "RPU data is truncated"),
+            DoviError::InvalidHeader(reason) => write!(f, "Invalid RPU header: {reason}"),
+            DoviError::InvalidProfile { profile, reason } => {
+                write!(f, "profile {profile}: {reason}")
+            }
+            DoviError::BlockValidation { reason, .. } => write!(f, "{reason}"),
+            DoviError::InvalidAv1Payload(reason) => {
+                write!(f, "Invalid AV1 RPU payload: {reason}")
+            }
+            DoviError::InvalidXml(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DoviError {}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::DoviError;
+    use crate::rpu::dovi_rpu::DoviRpu;
+    use crate::rpu::extension_metadata::blocks::{ExtMetadataBlock, ExtMetadataBlockLevel1};
+    use crate::rpu::generate::GenerateConfig;
+
+    #[test]
+    fn typed_parse_errors() -> Result<()> {
+        let data = DoviRpu::profile81_config(&GenerateConfig::default())?.write_rpu()?;
+        let rpu = DoviRpu::parse_rpu(&data)?;
+        let len = data.len();
+
+        let err = DoviRpu::parse_rpu(&data[..10]).unwrap_err();
+        assert_eq!(
+            DoviError::find(&err),
+            Some(&DoviError::InvalidRpuLength(10))
+        );
+
+        let err = DoviRpu::parse_rpu(&[0xFF; 30]).unwrap_err();
+        assert_eq!(DoviError::find(&err), Some(&DoviError::InvalidStartBytes));
+
+        let mut invalid = data.clone();
+        invalid[len - 1] = 0x81;
+        let err = DoviRpu::parse_rpu(&invalid).unwrap_err();
+        assert_eq!(
+            DoviError::find(&err),
+            Some(&DoviError::InvalidFinalByte(0x81))
+        );
+
+        let mut invalid = data.clone();
+        invalid[len - 2] ^= 1;
+        let err = DoviRpu::parse_rpu(&invalid).unwrap_err();
+        assert_eq!(
+            DoviError::find(&err),
+            Some(&DoviError::CrcMismatch {
+                expected: rpu.rpu_data_crc32 ^ 1,
+                got: rpu.rpu_data_crc32,
+            })
+        );
+
+        let mut truncated = data[..len / 2].to_vec();
+        truncated.push(0x80);
+        let err = DoviRpu::parse_rpu(&truncated).unwrap_err();
+        assert_eq!(DoviError::find(&err), Some(&DoviError::Truncated));
+
+        let mut payload = rpu.write_av1_rpu_metadata_obu_t35_payload()?;
+        payload[1] = 0x3C;
+        let err = DoviRpu::parse_itu_t35_dovi_metadata_obu(&payload).unwrap_err();
+        assert!(matches!(
+            DoviError::find(&err),
+            Some(DoviError::InvalidAv1Payload(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn typed_block_errors() -> Result<()> {
+        let mut rpu = DoviRpu::profile81_config(&GenerateConfig::default())?;
+        rpu.replace_metadata_block(ExtMetadataBlock::Level1(ExtMetadataBlockLevel1::new(
+            0, 4096, 0,
+        )))?;
+
+        let err = rpu.write_rpu().unwrap_err();
+        assert!(matches!(
+            DoviError::find(&err),
+            Some(DoviError::BlockValidation { level: 1, .. })
+        ));
+
+        Ok(())
+    }
+}
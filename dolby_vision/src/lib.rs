@@ -7,10 +7,18 @@ pub mod av1;
 /// SMPTE ST2094-10 metadata module
 pub mod st2094_10;
 
+/// Typed library errors
+/// cbindgen:ignore
+pub mod error;
+
 /// Various utils
 /// cbindgen:ignore
 pub mod utils;
 
+/// Frame rate and timecode utils
+/// cbindgen:ignore
+pub mod timecode;
+
 /// Dolby Vision XML metadata module
 #[cfg(feature = "xml")]
 pub mod xml;
@@ -1,19 +1,20 @@
-use anyhow::{Result, anyhow, bail, ensure};
+use anyhow::{Context, Result, anyhow, bail, ensure};
 use bitvec::prelude::{BitVec, Msb0};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use super::extension_metadata::blocks::{ExtMetadataBlock, ExtMetadataBlockLevel5};
+use super::extension_metadata::blocks::{
+    ExtMetadataBlock, ExtMetadataBlockLevel5, ExtMetadataBlockLevel9, ExtMetadataBlockLevel11,
+};
 use super::generate::GenerateConfig;
 use super::profiles::profile81::Profile81;
 use super::profiles::profile84::Profile84;
 use super::rpu_data_header::RpuDataHeader;
 use super::rpu_data_mapping::{DoviNlqMethod, RpuDataMapping};
 use super::rpu_data_nlq::{DoviELType, RpuDataNlq};
+use super::trace::{RpuBitReader, RpuTrace};
 use super::vdr_dm_data::{VdrDmData, vdr_dm_data_payload};
 use super::{ConversionMode, compute_crc32};
 
@@ -21,6 +22,7 @@ use crate::av1::{
     av1_validated_trimmed_data, convert_av1_rpu_payload_to_regular,
     convert_regular_rpu_to_av1_payload,
 };
+use crate::error::DoviError;
 use crate::rpu::extension_metadata::{CmV40DmData, DmData};
 use crate::utils::{
     add_start_code_emulation_prevention_3_byte, clear_start_code_emulation_prevention_3_byte,
@@ -34,7 +36,7 @@ const CRC32_TERMINATOR_BITS: u64 = 40;
 pub(crate) const RPU_WRITE_ALLOC_CAPACITY: usize = 512;
 
 #[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct DoviRpu {
     pub dovi_profile: u8,
 
@@ -53,23 +55,84 @@ pub struct DoviRpu {
         feature = "serde",
         serde(
             serialize_with = "crate::utils::opt_bitvec_ser_bits",
-            skip_serializing_if = "Option::is_none"
+            deserialize_with = "crate::utils::opt_bitvec_de_bits",
+            skip_serializing_if = "Option::is_none",
+            default
         )
     )]
     pub remaining: Option<BitVec<u8, Msb0>>,
     pub rpu_data_crc32: u32,
 
-    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    /// Deserialized RPUs may have been edited, so the CRC32 is always recomputed
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing, default = "deserialized_modified")
+    )]
     pub modified: bool,
 
-    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    #[cfg_attr(feature = "serde", serde(skip))]
     trailing_zeroes: usize,
+
+    /// Issues fixed when parsing in repair mode, see `RpuParseOpts::repair`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub repairs: Vec<RpuRepair>,
+}
+
+/// Options for the `_with_opts` parsing functions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RpuParseOpts {
+    /// Repair mode, for RPUs damaged by broken muxers or bad rips.
+    ///
+    /// CRC32 mismatches, invalid final byte, trailing data and non-zero alignment bits
+    /// do not fail the parsing, they are listed in `DoviRpu::repairs` instead.
+    /// Repaired RPUs are marked as modified, so the CRC32 is recomputed when writing.
+    pub repair: bool,
+}
+
+impl RpuParseOpts {
+    /// Sets the repair mode, see `RpuParseOpts::repair`
+    pub fn with_repair(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+}
+
+/// Issue fixed when parsing an RPU in repair mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpuRepair {
+    /// The stored CRC32 did not match the data
+    CrcMismatch { expected: u32, got: u32 },
+    /// The RPU did not end with the 0x80 terminator byte
+    InvalidFinalByte(u8),
+    /// Bytes found after the 0x80 terminator were dropped
+    TrailingData(usize),
+    /// `rpu_alignment_zero_bit` values were not zero
+    AlignmentBits,
+}
+
+impl std::fmt::Display for RpuRepair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpuRepair::CrcMismatch { expected, got } => {
+                write!(f, "CRC32 mismatch: stored {expected}, computed {got}")
+            }
+            RpuRepair::InvalidFinalByte(byte) => write!(f, "Invalid final byte: {byte}"),
+            RpuRepair::TrailingData(len) => write!(f, "Dropped {len} bytes of trailing data"),
+            RpuRepair::AlignmentBits => write!(f, "Non-zero rpu_alignment_zero_bit"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialized_modified() -> bool {
+    true
 }
 
 impl DoviRpu {
     pub fn validated_trimmed_data(data: &[u8]) -> Result<&[u8]> {
         if data.len() < 25 {
-            bail!("Invalid RPU length: {}", &data.len());
+            bail!(DoviError::InvalidRpuLength(data.len()));
         }
 
         // Including 0x7C01 prepended
@@ -79,7 +142,7 @@ impl DoviRpu {
             [0, 1, 25, 8, 9] | [124, 1, 25, 8, 9] => &data[2..],
             [1, 25, 8, 9, _] => &data[1..],
             [25, 8, 9, _, _] => data,
-            _ => bail!("Invalid RPU data start bytes\n{:?}", &data[..5]),
+            _ => bail!(DoviError::InvalidStartBytes),
         };
 
         Ok(trimmed_data)
@@ -87,80 +150,168 @@ impl DoviRpu {
 
     /// HEVC UNSPEC62 NALU, clears start code emulation prevention 3 bytes
     pub fn parse_unspec62_nalu(data: &[u8]) -> Result<DoviRpu> {
+        DoviRpu::parse_unspec62_nalu_with_opts(data, RpuParseOpts::default())
+    }
+
+    pub fn parse_unspec62_nalu_with_opts(data: &[u8], opts: RpuParseOpts) -> Result<DoviRpu> {
         let trimmed_data = DoviRpu::validated_trimmed_data(data)?;
 
         // Clear start code emulation prevention 3 byte
         let bytes: Vec<u8> = clear_start_code_emulation_prevention_3_byte(trimmed_data);
 
-        DoviRpu::parse(&bytes)
+        DoviRpu::parse(&bytes, opts)
     }
 
     /// Parse AV1 ITU-T T.35 metadata OBU into a `DoviRpu`
     /// The payload is extracted out of the EMDF wrapper
     pub fn parse_itu_t35_dovi_metadata_obu(data: &[u8]) -> Result<Self> {
+        DoviRpu::parse_itu_t35_dovi_metadata_obu_with_opts(data, RpuParseOpts::default())
+    }
+
+    pub fn parse_itu_t35_dovi_metadata_obu_with_opts(
+        data: &[u8],
+        opts: RpuParseOpts,
+    ) -> Result<Self> {
         let data = av1_validated_trimmed_data(data)?;
         let converted_buf = convert_av1_rpu_payload_to_regular(data)?;
 
-        DoviRpu::parse(&converted_buf)
+        DoviRpu::parse(&converted_buf, opts)
     }
 
     pub fn parse_rpu(data: &[u8]) -> Result<DoviRpu> {
+        DoviRpu::parse_rpu_with_opts(data, RpuParseOpts::default())
+    }
+
+    pub fn parse_rpu_with_opts(data: &[u8], opts: RpuParseOpts) -> Result<DoviRpu> {
         let trimmed_data = DoviRpu::validated_trimmed_data(data)?;
 
-        DoviRpu::parse(trimmed_data)
+        DoviRpu::parse(trimmed_data, opts)
+    }
+
+    /// HEVC UNSPEC62 NALU, recording the bit offset, width and value of every syntax element.
+    ///
+    /// The trace is returned even when parsing fails, up to the failing element.
+    pub fn trace_unspec62_nalu(data: &[u8], opts: RpuParseOpts) -> (Result<DoviRpu>, RpuTrace) {
+        match DoviRpu::validated_trimmed_data(data) {
+            Ok(trimmed_data) => {
+                let bytes = clear_start_code_emulation_prevention_3_byte(trimmed_data);
+                DoviRpu::trace(&bytes, opts)
+            }
+            Err(e) => (Err(e), RpuTrace::default()),
+        }
+    }
+
+    /// AV1 ITU-T T.35 metadata OBU, see `trace_unspec62_nalu`
+    pub fn trace_itu_t35_dovi_metadata_obu(
+        data: &[u8],
+        opts: RpuParseOpts,
+    ) -> (Result<DoviRpu>, RpuTrace) {
+        let converted_buf =
+            av1_validated_trimmed_data(data).and_then(convert_av1_rpu_payload_to_regular);
+
+        match converted_buf {
+            Ok(converted_buf) => DoviRpu::trace(&converted_buf, opts),
+            Err(e) => (Err(e), RpuTrace::default()),
+        }
+    }
+
+    fn trace(data: &[u8], opts: RpuParseOpts) -> (Result<DoviRpu>, RpuTrace) {
+        let mut trace = RpuTrace::default();
+        let res = DoviRpu::parse_data(data, opts, Some(&mut trace));
+
+        (res, trace)
+    }
+
+    #[inline(always)]
+    pub(crate) fn parse(data: &[u8], opts: RpuParseOpts) -> Result<DoviRpu> {
+        DoviRpu::parse_data(data, opts, None)
     }
 
     #[inline(always)]
-    pub(crate) fn parse(data: &[u8]) -> Result<DoviRpu> {
-        let trailing_zeroes = data.iter().rev().take_while(|b| **b == 0).count();
+    fn parse_data(
+        data: &[u8],
+        opts: RpuParseOpts,
+        trace: Option<&mut RpuTrace>,
+    ) -> Result<DoviRpu> {
+        let mut trailing_zeroes = data.iter().rev().take_while(|b| **b == 0).count();
 
         // Ignore trailing bytes
-        let rpu_end = data.len() - trailing_zeroes;
+        let mut rpu_end = data.len() - trailing_zeroes;
 
         // Minimum: 1 prefix byte + at least 1 byte payload + 4 CRC32 bytes + 1 final byte = 7
-        ensure!(
-            rpu_end >= 7,
-            "RPU data too short: {rpu_end} bytes after trimming trailing zeroes"
-        );
+        ensure!(rpu_end >= 7, DoviError::InvalidRpuLength(rpu_end));
+
+        let mut repairs = Vec::new();
 
         let last_byte = data[rpu_end - 1];
 
+        if last_byte != FINAL_BYTE {
+            if !opts.repair {
+                bail!(DoviError::InvalidFinalByte(last_byte));
+            }
+
+            // Either garbage was appended after the terminator, or the final byte is damaged
+            if let Some(end) = Self::find_valid_rpu_end(&data[..rpu_end]) {
+                repairs.push(RpuRepair::TrailingData(data.len() - end));
+                rpu_end = end;
+                trailing_zeroes = 0;
+            } else {
+                repairs.push(RpuRepair::InvalidFinalByte(last_byte));
+            }
+        }
+
         // Minus 4 bytes for the CRC32, 1 for the 0x80 ending byte
         let crc32_start = rpu_end - 5;
 
         // Ignoring the prefix byte
         let received_crc32 = compute_crc32(&data[1..crc32_start]);
 
-        if last_byte != FINAL_BYTE {
-            bail!("Invalid RPU last byte: {}", last_byte);
+        let mut reader = RpuBitReader::new(&data[..rpu_end], trace.is_some());
+        let res = DoviRpu::read_rpu_data(&mut reader, opts);
+
+        if let Some(trace) = trace {
+            *trace = reader.into_trace();
         }
 
-        let mut dovi_rpu = DoviRpu::read_rpu_data(&data[..rpu_end])?;
+        let mut dovi_rpu = res.map_err(DoviError::map_truncated)?;
 
-        if received_crc32 != dovi_rpu.rpu_data_crc32 {
-            bail!(
-                "RPU CRC32 does not match the data. Received: {}, expected {}",
-                received_crc32,
-                dovi_rpu.rpu_data_crc32
-            );
+        let (expected, got) = (dovi_rpu.rpu_data_crc32, received_crc32);
+        if expected != got {
+            ensure!(opts.repair, DoviError::CrcMismatch { expected, got });
+            repairs.push(RpuRepair::CrcMismatch { expected, got });
         }
 
         dovi_rpu.trailing_zeroes = trailing_zeroes;
 
+        repairs.append(&mut dovi_rpu.repairs);
+        dovi_rpu.modified = !repairs.is_empty();
+        dovi_rpu.repairs = repairs;
+
         // Validate
         dovi_rpu.validate()?;
 
         Ok(dovi_rpu)
     }
 
-    #[inline(always)]
-    fn read_rpu_data(bytes: &[u8]) -> Result<DoviRpu> {
-        let mut reader = BsIoSliceReader::from_slice(bytes);
+    /// End of the RPU data, when followed by trailing data.
+    /// Looks for the last 0x80 byte preceded by a matching CRC32.
+    fn find_valid_rpu_end(data: &[u8]) -> Option<usize> {
+        (7..data.len()).rev().find(|&end| {
+            let crc32_start = end - 5;
+            let stored_crc32 = u32::from_be_bytes(data[crc32_start..end - 1].try_into().unwrap());
+
+            data[end - 1] == FINAL_BYTE && compute_crc32(&data[1..crc32_start]) == stored_crc32
+        })
+    }
 
-        let rpu_prefix = reader.read::<8, u8>()?;
-        ensure!(rpu_prefix == 25, "rpu_nal_prefix should be 25");
+    #[inline(always)]
+    fn read_rpu_data(reader: &mut RpuBitReader, opts: RpuParseOpts) -> Result<DoviRpu> {
+        let rpu_prefix = reader.read::<8, u8>("rpu_nal_prefix")?;
+        ensure!(rpu_prefix == 25, DoviError::InvalidStartBytes);
 
-        let mut header = RpuDataHeader::parse(&mut reader)?;
+        reader.begin("rpu_data_header");
+        let mut header = RpuDataHeader::parse(reader)?;
+        reader.end();
 
         // FIXME: rpu_nal_prefix deprecation
         #[allow(deprecated)]
@@ -173,7 +324,11 @@ impl DoviRpu {
         header.validate(dovi_profile)?;
 
         let rpu_data_mapping = if !header.use_prev_vdr_rpu_flag {
-            Some(RpuDataMapping::parse(&mut reader, &header)?)
+            reader.begin("rpu_data_mapping");
+            let mapping = RpuDataMapping::parse(reader, &header)?;
+            reader.end();
+
+            Some(mapping)
         } else {
             None
         };
@@ -184,23 +339,36 @@ impl DoviRpu {
             .unwrap_or(None);
 
         let vdr_dm_data = if header.vdr_dm_metadata_present_flag {
-            Some(vdr_dm_data_payload(&mut reader, &header)?)
+            reader.begin("vdr_dm_data_payload");
+            let vdr_dm_data = vdr_dm_data_payload(reader, &header)?;
+            reader.end();
+
+            Some(vdr_dm_data)
         } else {
             None
         };
 
         // rpu_alignment_zero_bit
+        let mut repairs = Vec::new();
         while !reader.byte_aligned() {
-            ensure!(!reader.read_bit()?, "rpu_alignment_zero_bit != 0");
+            if reader.read_bit("rpu_alignment_zero_bit")? {
+                ensure!(opts.repair, "rpu_alignment_zero_bit != 0");
+
+                if repairs.is_empty() {
+                    repairs.push(RpuRepair::AlignmentBits);
+                }
+            }
         }
 
         // CRC32 is at the end, there can be more data in between
         let remaining = if reader.available()? > CRC32_TERMINATOR_BITS {
             let mut remaining: BitVec<u8, Msb0> = BitVec::new();
 
+            reader.begin("remaining");
             while reader.available()? != CRC32_TERMINATOR_BITS {
-                remaining.push(reader.read_bit()?);
+                remaining.push(reader.read_bit("remaining_bit")?);
             }
+            reader.end();
 
             Some(remaining)
         } else {
@@ -209,12 +377,15 @@ impl DoviRpu {
 
         let avail = reader.available()?;
         if avail != CRC32_TERMINATOR_BITS {
-            bail!("expected {CRC32_TERMINATOR_BITS} remaining bits but have {avail} bits");
+            bail!(DoviError::Truncated);
         }
 
-        let rpu_data_crc32 = reader.read::<32, u32>()?;
-        let last_byte = reader.read::<8, u8>()?;
-        ensure!(last_byte == FINAL_BYTE, "last byte should be 0x80");
+        let rpu_data_crc32 = reader.read::<32, u32>("rpu_data_crc32")?;
+        let last_byte = reader.read::<8, u8>("rbsp_trailing_bits")?;
+        ensure!(
+            opts.repair || last_byte == FINAL_BYTE,
+            DoviError::InvalidFinalByte(last_byte)
+        );
 
         Ok(DoviRpu {
             dovi_profile: header.get_dovi_profile(),
@@ -224,6 +395,7 @@ impl DoviRpu {
             vdr_dm_data,
             remaining,
             rpu_data_crc32,
+            repairs,
             ..Default::default()
         })
     }
@@ -319,11 +491,16 @@ impl DoviRpu {
         Ok(writer.into_inner())
     }
 
-    fn validate(&self) -> Result<()> {
+    /// Validates the RPU against its profile and header, as done before writing.
+    /// Useful to report errors early for RPUs that were not parsed, e.g. deserialized ones.
+    pub fn validate(&self) -> Result<()> {
         self.header.validate(self.dovi_profile)?;
 
         if let Some(mapping) = self.rpu_data_mapping.as_ref() {
             mapping.validate(self.dovi_profile)?;
+            mapping
+                .validate_structure(&self.header)
+                .context("Invalid rpu_data_mapping")?;
         }
 
         if let Some(vdr_dm_data) = &self.vdr_dm_data {
@@ -356,6 +533,7 @@ impl DoviRpu {
 
         if mode != ConversionMode::Lossless {
             self.modified = true;
+            self.detach_prev_vdr_rpu();
         }
 
         let valid_conversion = match mode {
@@ -394,7 +572,10 @@ impl DoviRpu {
         };
 
         if !valid_conversion {
-            bail!("Invalid profile for mode {} conversion!", mode);
+            bail!(DoviError::InvalidProfile {
+                profile: self.dovi_profile,
+                reason: format!("Invalid profile for mode {mode} conversion!"),
+            });
         }
 
         // Update profile value
@@ -422,7 +603,10 @@ impl DoviRpu {
             } else if self.dovi_profile == 8 {
                 mapping.nlq = Some(RpuDataNlq::mel_default());
             } else {
-                bail!("Not profile 7 or 8, cannot convert to MEL!");
+                bail!(DoviError::InvalidProfile {
+                    profile: self.dovi_profile,
+                    reason: "Not profile 7 or 8, cannot convert to MEL!".into(),
+                });
             }
         }
 
@@ -482,7 +666,10 @@ impl DoviRpu {
                 vdr_dm_data.set_p81_coeffs();
             }
         } else {
-            bail!("Attempt to convert profile 5: RPU is not profile 5!");
+            bail!(DoviError::InvalidProfile {
+                profile: self.dovi_profile,
+                reason: "Attempt to convert profile 5: RPU is not profile 5!".into(),
+            });
         }
 
         Ok(())
@@ -544,12 +731,80 @@ impl DoviRpu {
 
     pub fn remove_mapping(&mut self) {
         self.modified = true;
+        self.detach_prev_vdr_rpu();
 
         if let Some(rpu_data_mapping) = self.rpu_data_mapping.as_mut() {
             rpu_data_mapping.set_empty_p81_mapping();
         }
     }
 
+    /// Writes the resolved mapping instead of referencing the previous one, before editing it.
+    /// See `PrevVdrRpuResolver` for resolving the mapping of RPUs using `use_prev_vdr_rpu_flag`.
+    fn detach_prev_vdr_rpu(&mut self) {
+        if self.header.use_prev_vdr_rpu_flag && self.rpu_data_mapping.is_some() {
+            self.header.use_prev_vdr_rpu_flag = false;
+            self.header.prev_vdr_rpu_id = 0;
+        }
+    }
+
+    fn vdr_dm_data_for_edit(&mut self) -> Result<&mut VdrDmData> {
+        self.modified = true;
+
+        self.vdr_dm_data
+            .as_mut()
+            .ok_or_else(|| anyhow!("No vdr_dm_data to edit"))
+    }
+
+    /// Adds an extension metadata block to the DM data of its CM version
+    pub fn add_metadata_block(&mut self, block: ExtMetadataBlock) -> Result<()> {
+        let level = block.level();
+        let vdr_dm_data = self.vdr_dm_data_for_edit()?;
+
+        ensure!(
+            vdr_dm_data.extension_metadata_for_level(level).is_some(),
+            "Cannot add L{level} metadata, no matching CM version DM data"
+        );
+
+        vdr_dm_data.add_metadata_block(block)
+    }
+
+    /// Replaces the extension metadata block of the same level.
+    /// L2, L8 and L10 blocks are replaced for the same target display.
+    ///
+    /// The block is added if there was none to replace.
+    pub fn replace_metadata_block(&mut self, block: ExtMetadataBlock) -> Result<()> {
+        let level = block.level();
+        let vdr_dm_data = self.vdr_dm_data_for_edit()?;
+
+        ensure!(
+            vdr_dm_data.extension_metadata_for_level(level).is_some(),
+            "Cannot replace L{level} metadata, no matching CM version DM data"
+        );
+
+        vdr_dm_data.replace_metadata_block(block)
+    }
+
+    /// Removes every extension metadata block of the level
+    pub fn remove_metadata_level(&mut self, level: u8) -> Result<()> {
+        self.vdr_dm_data_for_edit()?.remove_metadata_level(level);
+
+        Ok(())
+    }
+
+    pub fn set_scene_refresh_flag(&mut self, is_scene_cut: bool) -> Result<()> {
+        self.vdr_dm_data_for_edit()?.set_scene_cut(is_scene_cut);
+
+        Ok(())
+    }
+
+    /// Sets the source (mastering display) min and max PQ values
+    pub fn set_source_levels(&mut self, min_pq: u16, max_pq: u16) -> Result<()> {
+        self.vdr_dm_data_for_edit()?
+            .change_source_levels(Some(min_pq), Some(max_pq));
+
+        Ok(())
+    }
+
     pub fn parse_list_of_unspec62_nalus(data: &[Vec<u8>]) -> Vec<DoviRpu> {
         data.iter()
             .map(|rpu| DoviRpu::parse_unspec62_nalu(rpu))
@@ -587,6 +842,36 @@ impl DoviRpu {
         Ok(())
     }
 
+    /// Converts the CM v4.0 metadata to CM v2.9, preserving the L8 trims as L2 metadata.
+    pub fn convert_cmv40_to_cmv29(&mut self) -> Result<()> {
+        if let Some(vdr_dm_data) = self.vdr_dm_data.as_mut() {
+            if vdr_dm_data.cmv40_metadata.is_some() {
+                self.modified = true;
+
+                vdr_dm_data.downgrade_to_cmv29()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts the CM v2.9 metadata to CM v4.0, preserving the L2 trims as L8 metadata.
+    pub fn convert_cmv29_to_cmv40(
+        &mut self,
+        level9: ExtMetadataBlockLevel9,
+        level11: ExtMetadataBlockLevel11,
+    ) -> Result<()> {
+        if let Some(vdr_dm_data) = self.vdr_dm_data.as_mut() {
+            if vdr_dm_data.cmv40_metadata.is_none() {
+                self.modified = true;
+
+                vdr_dm_data.upgrade_to_cmv40(level9, level11)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Replaces metadata levels from `src_rpu`.
     /// If the RPU doesn't have `cmv40_metadata`, the CM v4.0 levels are ignored and an error may be returned.
     pub fn replace_levels_from_rpu(&mut self, src_rpu: &Self, levels: &Vec<u8>) -> Result<()> {
@@ -631,3 +916,199 @@ impl DoviRpu {
         self.replace_levels_from_rpu(src_rpu, levels)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::{DoviRpu, RpuParseOpts, RpuRepair};
+    use crate::rpu::extension_metadata::blocks::{
+        ExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel8, ExtMetadataBlockLevel9,
+    };
+    use crate::rpu::generate::GenerateConfig;
+
+    #[test]
+    fn edit_metadata_blocks() -> Result<()> {
+        let mut rpu = DoviRpu::profile81_config(&GenerateConfig::default())?;
+
+        rpu.replace_metadata_block(ExtMetadataBlock::Level1(ExtMetadataBlockLevel1::new(
+            0, 2800, 1500,
+        )))?;
+
+        let level8 = ExtMetadataBlockLevel8 {
+            target_display_index: 1,
+            ..Default::default()
+        };
+        rpu.add_metadata_block(ExtMetadataBlock::Level8(level8.clone()))?;
+        rpu.replace_metadata_block(ExtMetadataBlock::Level8(ExtMetadataBlockLevel8 {
+            trim_slope: 2100,
+            ..level8
+        }))?;
+
+        rpu.set_scene_refresh_flag(true)?;
+        rpu.set_source_levels(7, 3079)?;
+
+        let mut rpu = DoviRpu::parse_rpu(&rpu.write_rpu()?)?;
+        let vdr_dm_data = rpu.vdr_dm_data.as_ref().unwrap();
+
+        assert_eq!(vdr_dm_data.scene_refresh_flag, 1);
+        assert_eq!(vdr_dm_data.source_min_pq, 7);
+        assert_eq!(vdr_dm_data.source_max_pq, 3079);
+
+        if let Some(ExtMetadataBlock::Level1(level1)) = vdr_dm_data.get_block(1) {
+            assert_eq!(level1.max_pq, 2800);
+        } else {
+            panic!("No L1 block");
+        }
+
+        let level8_blocks: Vec<_> = vdr_dm_data.level_blocks_iter(8).collect();
+        assert_eq!(level8_blocks.len(), 1);
+
+        let ExtMetadataBlock::Level8(level8) = level8_blocks[0] else {
+            panic!("No L8 block");
+        };
+        assert_eq!(level8.trim_slope, 2100);
+
+        rpu.remove_metadata_level(8)?;
+        assert!(rpu.vdr_dm_data.as_ref().unwrap().get_block(8).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn edit_without_vdr_dm_data() {
+        let mut rpu = DoviRpu::default();
+
+        assert!(rpu.set_scene_refresh_flag(true).is_err());
+        assert!(
+            rpu.add_metadata_block(ExtMetadataBlock::Level1(ExtMetadataBlockLevel1::default()))
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip() -> Result<()> {
+        use std::path::PathBuf;
+
+        use crate::rpu::utils::parse_rpu_file;
+
+        let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let assets_path = lib_path.parent().unwrap().join("assets/tests");
+
+        for file in [
+            "fel_orig.bin",
+            "mel_orig.bin",
+            "profile5.bin",
+            "profile8.bin",
+            "profile84.bin",
+            "cmv40_full_rpu.bin",
+            "mel_variable_l8_length13.bin",
+        ] {
+            for rpu in parse_rpu_file(assets_path.join(file))? {
+                let json = serde_json::to_string(&rpu)?;
+                let deserialized: DoviRpu = serde_json::from_str(&json)?;
+
+                assert!(deserialized.modified);
+                assert_eq!(deserialized.rpu_data_crc32, rpu.rpu_data_crc32, "{file}");
+                assert_eq!(deserialized.write_rpu()?, rpu.write_rpu()?, "{file}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_inconsistent_structure() -> Result<()> {
+        use std::path::PathBuf;
+
+        use crate::rpu::utils::parse_rpu_file;
+
+        let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let assets_path = lib_path.parent().unwrap().join("assets/tests");
+
+        let rpu = &parse_rpu_file(assets_path.join("fel_orig.bin"))?[0];
+        let json = serde_json::to_value(rpu)?;
+
+        let invalid_rpus = [
+            ("/rpu_data_mapping/curves/0/num_pivots_minus2", 3.into()),
+            (
+                "/rpu_data_mapping/curves/0/poly_coef",
+                serde_json::json!([]),
+            ),
+            (
+                "/rpu_data_mapping/curves/1/mmr_coef/0",
+                serde_json::json!([[0]]),
+            ),
+            (
+                "/rpu_data_mapping/curves/2/mapping_idc",
+                "Polynomial".into(),
+            ),
+            ("/rpu_data_mapping/nlq_method_idc", serde_json::Value::Null),
+            ("/vdr_dm_data/cmv29_metadata/num_ext_blocks", 0.into()),
+        ];
+
+        for (pointer, value) in invalid_rpus {
+            let mut json = json.clone();
+            *json.pointer_mut(pointer).unwrap() = value;
+
+            let deserialized: DoviRpu = serde_json::from_value(json)?;
+            assert!(deserialized.validate().is_err(), "{pointer}");
+            assert!(deserialized.write_rpu().is_err(), "{pointer}");
+        }
+
+        // Block lengths are also checked before writing
+        let mut rpu = parse_rpu_file(assets_path.join("cmv40_full_rpu.bin"))?.remove(0);
+        rpu.modified = true;
+        let vdr_dm_data = rpu.vdr_dm_data.as_mut().unwrap();
+        vdr_dm_data.replace_metadata_block(ExtMetadataBlock::Level9(ExtMetadataBlockLevel9 {
+            length: 5,
+            ..Default::default()
+        }))?;
+        assert!(rpu.write_rpu().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn repair_mode() -> Result<()> {
+        let data = DoviRpu::profile81_config(&GenerateConfig::default())?.write_rpu()?;
+        let len = data.len();
+        let opts = RpuParseOpts::default().with_repair(true);
+
+        let mut crc_mismatch = data.clone();
+        crc_mismatch[len - 2] ^= 1;
+
+        let mut final_byte = data.clone();
+        final_byte[len - 1] = 0x81;
+
+        let mut trailing_data = data.clone();
+        trailing_data.extend_from_slice(&[0x12, 0x34, 0]);
+
+        for (damaged, repair) in [
+            (
+                &crc_mismatch,
+                RpuRepair::CrcMismatch {
+                    expected: u32::from_be_bytes(crc_mismatch[len - 5..len - 1].try_into()?),
+                    got: u32::from_be_bytes(data[len - 5..len - 1].try_into()?),
+                },
+            ),
+            (&final_byte, RpuRepair::InvalidFinalByte(0x81)),
+            (&trailing_data, RpuRepair::TrailingData(3)),
+        ] {
+            assert!(DoviRpu::parse_rpu(damaged).is_err());
+
+            let rpu = DoviRpu::parse_rpu_with_opts(damaged, opts)?;
+            assert_eq!(rpu.repairs, vec![repair]);
+            assert!(rpu.modified);
+            assert_eq!(rpu.write_rpu()?, data);
+        }
+
+        let rpu = DoviRpu::parse_rpu_with_opts(&data, opts)?;
+        assert!(rpu.repairs.is_empty());
+        assert!(!rpu.modified);
+
+        Ok(())
+    }
+}
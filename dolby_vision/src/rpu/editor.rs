@@ -0,0 +1,1090 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use serde::{Deserialize, Serialize};
+
+use super::dovi_rpu::DoviRpu;
+use super::extension_metadata::blocks::{
+    ExtMetadataBlock, ExtMetadataBlockLevel5, ExtMetadataBlockLevel6, ExtMetadataBlockLevel9,
+    ExtMetadataBlockLevel11, ExtMetadataBlockLevel255, MAX_12_BIT_VALUE,
+};
+use super::extension_metadata::{CmV40DmData, MasteringDisplayPrimaries, WithExtMetadataBlocks};
+use super::utils::parse_rpu_file;
+use super::vdr_dm_data::CmVersion;
+use crate::timecode::{FramePosition, FrameRate};
+use crate::utils::nits_to_pq_12_bit;
+
+/// RPU list editing config, as used by `dovi_tool editor`.
+///
+/// Frame ranges are inclusive `start-end` strings, or `all`.
+/// With a `frame_rate`, positions can also be timecodes or seconds.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct EditConfig {
+    /// Conversion mode applied to every RPU, see `DoviRpu::convert_with_mode`
+    #[serde(default)]
+    pub mode: u8,
+
+    /// Frame rate used to parse timecode and seconds positions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_rate: Option<FrameRate>,
+
+    /// Removes the CM v4.0 extension metadata
+    #[serde(default)]
+    pub remove_cmv4: bool,
+
+    /// Converts the CM v4.0 metadata to CM v2.9
+    #[serde(default)]
+    pub downgrade_cmv4: bool,
+
+    /// Converts the CM v2.9 metadata to CM v4.0
+    #[serde(default)]
+    pub upgrade_cmv29: bool,
+
+    /// Removes the polynomial/MMR mapping
+    #[serde(default)]
+    pub remove_mapping: bool,
+
+    /// Source min PQ override
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_pq: Option<u16>,
+    /// Source max PQ override
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_pq: Option<u16>,
+
+    /// L5 active area edits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_area: Option<ActiveArea>,
+
+    /// Frame positions or ranges to remove
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove: Option<Vec<String>>,
+
+    /// Frames to duplicate, applied after removal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate: Option<Vec<DuplicateMetadata>>,
+
+    /// Scene cut flags to set, by range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_cuts: Option<HashMap<String, bool>>,
+
+    /// L6 metadata to set on every RPU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level6: Option<ExtMetadataBlockLevel6>,
+    /// L9 source primaries to set on every RPU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level9: Option<MasteringDisplayPrimaries>,
+    /// L11 metadata to set on every RPU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level11: Option<ExtMetadataBlockLevel11>,
+    /// L255 metadata to set on every RPU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level255: Option<ExtMetadataBlockLevel255>,
+
+    /// Metadata block edits, by range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level_edits: Option<HashMap<String, LevelEdit>>,
+
+    /// Relative metadata value changes, by range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level_adjustments: Option<HashMap<String, Vec<LevelAdjustment>>>,
+
+    /// Frame cadence conversion, applied last
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_rate_conversion: Option<FrameRateConversion>,
+
+    /// RPU file to copy the `rpu_levels` metadata from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_rpu: Option<PathBuf>,
+    /// Metadata levels copied from `source_rpu`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpu_levels: Option<Vec<u8>>,
+    /// Allow copying CM v4.0 levels to CM v2.9 RPUs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_cmv4_transfer: Option<bool>,
+}
+
+/// L5 active area edits
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ActiveArea {
+    /// Sets the active area offsets to 0
+    #[serde(default)]
+    pub crop: bool,
+
+    /// Removes the L5 metadata: `all`, or only `zeroes` offsets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_l5: Option<String>,
+
+    /// Offset presets, referenced by ID in `edits`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presets: Option<Vec<ActiveAreaOffsets>>,
+
+    /// Preset ID to apply, by range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edits: Option<HashMap<String, u16>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ActiveAreaOffsets {
+    pub id: u16,
+    pub left: u16,
+    pub right: u16,
+    pub top: u16,
+    pub bottom: u16,
+}
+
+/// Metadata block edits for a range of frames.
+/// Applied in order: remove, insert, then replace.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LevelEdit {
+    /// Metadata levels to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<u8>,
+
+    /// Blocks added to the existing ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub insert: Vec<ExtMetadataBlock>,
+
+    /// Blocks replacing the existing ones of the same level (and target display for L2/L8/L10)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replace: Vec<ExtMetadataBlock>,
+}
+
+/// Relative change to a L1, L2, L3 or L8 metadata value.
+/// The new value is `value * scale + offset`, clamped to the valid range.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LevelAdjustment {
+    pub level: u8,
+    pub field: AdjustmentField,
+
+    /// Only adjusts the L2/L8 trims for this target display, all targets otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_nits: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u16>,
+}
+
+/// Metadata value changed by a `LevelAdjustment`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentField {
+    MinPq,
+    MaxPq,
+    AvgPq,
+    MinPqOffset,
+    MaxPqOffset,
+    AvgPqOffset,
+    TrimSlope,
+    TrimOffset,
+    TrimPower,
+    TrimChromaWeight,
+    TrimSaturationGain,
+    MsWeight,
+    TargetMidContrast,
+    ClipTrim,
+}
+
+/// Remaps the RPU list to a different frame cadence
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FrameRateConversion {
+    pub mode: FrameRateConversionMode,
+
+    /// Frame rate to resample to, the source is `EditConfig.frame_rate`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_frame_rate: Option<FrameRate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameRateConversionMode {
+    /// 2:3 pulldown, every 4 frames become 5 frames
+    Pulldown,
+    /// Removes the 2:3 pulldown, every 5 frames become 4 frames
+    InverseTelecine,
+    /// Picks the nearest source frame for every frame at the target frame rate
+    Resample,
+}
+
+/// Repeats the `source` frame metadata `length` times, inserted at `offset`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DuplicateMetadata {
+    pub source: FramePosition,
+    pub offset: FramePosition,
+    pub length: usize,
+}
+
+impl EditConfig {
+    /// Parses and validates a JSON config file
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json_file = File::open(path)?;
+        let config: EditConfig = serde_json::from_reader(&json_file)?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Parses and validates a JSON config string
+    pub fn from_json(json: &str) -> Result<Self> {
+        let config: EditConfig = serde_json::from_str(json)?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        // CM v2.9 does not carry the mastering display primaries
+        ensure!(
+            !self.upgrade_cmv29 || self.level9.is_some(),
+            "level9 is required to upgrade CM v2.9 metadata"
+        );
+
+        if let Some(adjustments) = &self.level_adjustments {
+            adjustments
+                .values()
+                .flatten()
+                .try_for_each(LevelAdjustment::validate)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the config to a RPU list, returning the edited list.
+    ///
+    /// Removed frames are dropped, and the `duplicate` frames are inserted.
+    pub fn apply(&self, rpus: Vec<DoviRpu>) -> Result<Vec<DoviRpu>> {
+        let mut rpus: Vec<Option<DoviRpu>> = rpus.into_iter().map(Some).collect();
+        self.execute(&mut rpus)?;
+
+        let mut rpus: Vec<DoviRpu> = rpus.into_iter().flatten().collect();
+
+        if let Some(to_duplicate) = &self.duplicate {
+            self.duplicate_metadata(to_duplicate, &mut rpus)?;
+        }
+
+        Ok(rpus)
+    }
+
+    /// Edits the RPU list in place. Removed frames are set to `None`.
+    ///
+    /// Duplication is not done here, see `duplicate_metadata`.
+    pub fn execute(&self, rpus: &mut Vec<Option<DoviRpu>>) -> Result<()> {
+        // Drop metadata frames
+        if let Some(ranges) = &self.remove {
+            self.remove_frames(ranges, rpus)?;
+        }
+
+        for rpu in rpus.iter_mut().filter_map(|e| e.as_mut()) {
+            self.execute_single_rpu(rpu)?;
+        }
+
+        if let Some(edits) = &self.scene_cuts {
+            self.set_scene_cuts(rpus, edits)?;
+        }
+
+        // Specific ranges only, requires complete list
+        if let Some(active_area) = &self.active_area {
+            active_area.execute(rpus, self.frame_rate.as_ref())?;
+        }
+
+        if let Some(edits) = &self.level_edits {
+            self.edit_levels(rpus, edits)?;
+        }
+
+        if let Some(adjustments) = &self.level_adjustments {
+            self.adjust_levels(rpus, adjustments)?;
+        }
+
+        if let Some(source_rpu_path) = &self.source_rpu {
+            let source_rpus = parse_rpu_file(source_rpu_path).with_context(|| {
+                format!("Failed parsing source_rpu {}", source_rpu_path.display())
+            })?;
+            self.replace_from_rpus(rpus, &source_rpus)?;
+        }
+
+        // Changes the list length, done last
+        if let Some(conversion) = &self.frame_rate_conversion {
+            *rpus = conversion.execute(rpus, self.frame_rate.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the edits that do not depend on the frame position
+    pub fn execute_single_rpu(&self, rpu: &mut DoviRpu) -> Result<()> {
+        if self.downgrade_cmv4 {
+            rpu.convert_cmv40_to_cmv29()?;
+        }
+
+        if self.remove_cmv4 {
+            rpu.remove_cmv40_extension_metadata()?;
+        }
+
+        // L11 is replaced afterwards if configured
+        if self.upgrade_cmv29 {
+            let primaries = self
+                .level9
+                .ok_or_else(|| anyhow!("level9 is required to upgrade CM v2.9 metadata"))?;
+
+            rpu.convert_cmv29_to_cmv40(
+                Self::level9_block(primaries),
+                ExtMetadataBlockLevel11::default_reference_cinema(),
+            )?;
+        }
+
+        if self.mode > 0 {
+            rpu.convert_with_mode(self.mode)?;
+        }
+
+        if self.min_pq.is_some() || self.max_pq.is_some() {
+            self.change_source_levels(rpu);
+        }
+
+        if self.remove_mapping {
+            rpu.remove_mapping();
+        }
+
+        if let Some(l6) = &self.level6 {
+            self.set_level6_metadata(rpu, l6)?;
+        }
+
+        if let Some(l9) = &self.level9 {
+            self.set_level9_metadata(rpu, l9)?;
+        }
+
+        if let Some(l11) = &self.level11 {
+            self.set_level11_metadata(rpu, l11)?;
+        }
+
+        if let Some(l255) = &self.level255 {
+            self.set_level255_metadata(rpu, l255)?;
+        }
+
+        if let Some(edits) = &self.level_edits {
+            self.edit_levels_single_rpu(rpu, edits)?;
+        }
+
+        if let Some(adjustments) = &self.level_adjustments {
+            self.adjust_levels_single_rpu(rpu, adjustments)?;
+        }
+
+        if let Some(edits) = &self.scene_cuts {
+            self.set_scene_cuts_single_rpu(rpu, edits)?;
+        }
+
+        if let Some(active_area) = &self.active_area {
+            active_area.execute_single_rpu(rpu)?;
+        }
+
+        Ok(())
+    }
+
+    fn range_string_to_tuple(&self, range: &str) -> Result<(usize, usize)> {
+        FrameRate::parse_range(self.frame_rate.as_ref(), range)
+    }
+
+    fn remove_frames(&self, ranges: &[String], rpus: &mut [Option<DoviRpu>]) -> Result<()> {
+        for range in ranges {
+            if range.contains('-') {
+                let (start, end) = self.range_string_to_tuple(range)?;
+                ensure!(end < rpus.len(), "invalid end range {}", end);
+
+                rpus[start..=end].iter_mut().for_each(|e| *e = None);
+            } else {
+                let index = FrameRate::parse_position(self.frame_rate.as_ref(), range)?;
+                ensure!(
+                    index < rpus.len(),
+                    "invalid frame index to remove {}",
+                    index
+                );
+
+                rpus[index] = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the duplicated frames into a list of RPUs, or of encoded RPUs
+    pub fn duplicate_metadata<T: Clone>(
+        &self,
+        to_duplicate: &[DuplicateMetadata],
+        data: &mut Vec<T>,
+    ) -> Result<()> {
+        let mut to_duplicate = to_duplicate
+            .iter()
+            .map(|meta| {
+                let source = meta.source.to_frame(self.frame_rate.as_ref())?;
+                let offset = meta.offset.to_frame(self.frame_rate.as_ref())?;
+
+                ensure!(
+                    source < data.len() && offset <= data.len(),
+                    "invalid duplicate: {:?}",
+                    meta
+                );
+
+                Ok((source, offset, meta.length))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        to_duplicate.sort_by_key(|(_, offset, _)| *offset);
+        to_duplicate.reverse();
+
+        for (source, offset, length) in to_duplicate {
+            let source = data[source].clone();
+            data.splice(offset..offset, std::iter::repeat_n(source, length));
+        }
+
+        Ok(())
+    }
+
+    fn change_source_levels(&self, rpu: &mut DoviRpu) {
+        rpu.modified = true;
+
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            vdr_dm_data.change_source_levels(self.min_pq, self.max_pq)
+        }
+    }
+
+    fn set_level6_metadata(
+        &self,
+        rpu: &mut DoviRpu,
+        level6: &ExtMetadataBlockLevel6,
+    ) -> Result<()> {
+        rpu.modified = true;
+
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            vdr_dm_data.replace_metadata_block(ExtMetadataBlock::Level6(level6.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn set_level9_metadata(
+        &self,
+        rpu: &mut DoviRpu,
+        primaries: &MasteringDisplayPrimaries,
+    ) -> Result<()> {
+        let level9 = Self::level9_block(*primaries);
+
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            rpu.modified = true;
+
+            vdr_dm_data.replace_metadata_block(ExtMetadataBlock::Level9(level9))?;
+        }
+
+        Ok(())
+    }
+
+    fn level9_block(primaries: MasteringDisplayPrimaries) -> ExtMetadataBlockLevel9 {
+        ExtMetadataBlockLevel9 {
+            length: 1,
+            source_primary_index: primaries as u8,
+            ..Default::default()
+        }
+    }
+
+    fn set_level11_metadata(
+        &self,
+        rpu: &mut DoviRpu,
+        level11: &ExtMetadataBlockLevel11,
+    ) -> Result<()> {
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            rpu.modified = true;
+            vdr_dm_data.replace_metadata_block(ExtMetadataBlock::Level11(level11.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn set_scene_cuts_single_rpu(
+        &self,
+        rpu: &mut DoviRpu,
+        edits: &HashMap<String, bool>,
+    ) -> Result<()> {
+        // Allow passing "all" instead of a range
+        // Do "all" presets before specific ranges
+        for edit in edits {
+            if edit.0.to_lowercase() == "all"
+                && let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut()
+            {
+                rpu.modified = true;
+                vdr_dm_data.set_scene_cut(*edit.1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_scene_cuts(
+        &self,
+        rpus: &mut [Option<DoviRpu>],
+        edits: &HashMap<String, bool>,
+    ) -> Result<()> {
+        let edits = edits.iter().filter(|e| e.0.to_lowercase() != "all");
+
+        for edit in edits {
+            let (start, end) = self.range_string_to_tuple(edit.0)?;
+
+            if end > rpus.len() {
+                bail!("Invalid range: {} > {} available RPUs", end, rpus.len());
+            }
+
+            for rpu in rpus[start..=end].iter_mut().filter_map(|e| e.as_mut()) {
+                if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+                    rpu.modified = true;
+                    vdr_dm_data.set_scene_cut(*edit.1)
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_level255_metadata(
+        &self,
+        rpu: &mut DoviRpu,
+        level255: &ExtMetadataBlockLevel255,
+    ) -> Result<()> {
+        rpu.modified = true;
+
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            vdr_dm_data.replace_metadata_block(ExtMetadataBlock::Level255(level255.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    fn edit_levels_single_rpu(
+        &self,
+        rpu: &mut DoviRpu,
+        edits: &HashMap<String, LevelEdit>,
+    ) -> Result<()> {
+        // Allow passing "all" instead of a range
+        // Do "all" edits before specific ranges
+        for edit in edits {
+            if edit.0.to_lowercase() == "all" {
+                edit.1.execute(rpu)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn edit_levels(
+        &self,
+        rpus: &mut [Option<DoviRpu>],
+        edits: &HashMap<String, LevelEdit>,
+    ) -> Result<()> {
+        for ((start, end), edit) in self.specific_ranges(edits, rpus.len())? {
+            for rpu in rpus[start..=end].iter_mut().filter_map(|e| e.as_mut()) {
+                edit.execute(rpu)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn adjust_levels_single_rpu(
+        &self,
+        rpu: &mut DoviRpu,
+        adjustments: &HashMap<String, Vec<LevelAdjustment>>,
+    ) -> Result<()> {
+        // Allow passing "all" instead of a range
+        // Do "all" adjustments before specific ranges
+        for adjustment in adjustments {
+            if adjustment.0.to_lowercase() == "all" {
+                LevelAdjustment::execute_all(adjustment.1, rpu);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn adjust_levels(
+        &self,
+        rpus: &mut [Option<DoviRpu>],
+        adjustments: &HashMap<String, Vec<LevelAdjustment>>,
+    ) -> Result<()> {
+        for ((start, end), list) in self.specific_ranges(adjustments, rpus.len())? {
+            for rpu in rpus[start..=end].iter_mut().filter_map(|e| e.as_mut()) {
+                LevelAdjustment::execute_all(list, rpu);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses the non-"all" range keys, sorted so that overlapping ranges are applied in order
+    fn specific_ranges<'a, T>(
+        &self,
+        edits: &'a HashMap<String, T>,
+        rpus_count: usize,
+    ) -> Result<Vec<((usize, usize), &'a T)>> {
+        let mut specific_edits = edits
+            .iter()
+            .filter(|e| e.0.to_lowercase() != "all")
+            .map(|(range, edit)| Ok((self.range_string_to_tuple(range)?, edit)))
+            .collect::<Result<Vec<_>>>()?;
+
+        specific_edits.sort_by_key(|(range, _)| *range);
+
+        if let Some(((_, end), _)) = specific_edits
+            .iter()
+            .find(|((_, end), _)| *end >= rpus_count)
+        {
+            bail!("Invalid range: {} >= {} available RPUs", end, rpus_count);
+        }
+
+        Ok(specific_edits)
+    }
+
+    fn replace_from_rpus(
+        &self,
+        rpus: &mut [Option<DoviRpu>],
+        source_rpus: &Vec<DoviRpu>,
+    ) -> Result<()> {
+        ensure!(
+            rpus.len() == source_rpus.len(),
+            "source_rpu length mismatch: {} RPUs, expected {}",
+            source_rpus.len(),
+            rpus.len()
+        );
+
+        let zip_iter = rpus.iter_mut().filter_map(|e| e.as_mut()).zip(source_rpus);
+
+        let Some(levels) = self.rpu_levels.as_ref() else {
+            bail!("rpu_levels are required to replace metadata from source_rpu");
+        };
+
+        let allow_cmv4_transfer = self.allow_cmv4_transfer.unwrap_or(false)
+            && levels
+                .iter()
+                .any(|l| CmV40DmData::ALLOWED_BLOCK_LEVELS.contains(l));
+
+        for (dst_rpu, src_rpu) in zip_iter {
+            dst_rpu.replace_levels_from_rpu_cmv40(src_rpu, levels, allow_cmv4_transfer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LevelEdit {
+    fn execute(&self, rpu: &mut DoviRpu) -> Result<()> {
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            rpu.modified = true;
+
+            for level in &self.remove {
+                vdr_dm_data.remove_metadata_level(*level);
+            }
+
+            for block in &self.insert {
+                vdr_dm_data.add_metadata_block(block.clone())?;
+            }
+
+            vdr_dm_data.replace_metadata_blocks(self.replace.iter())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LevelAdjustment {
+    fn execute_all(adjustments: &[LevelAdjustment], rpu: &mut DoviRpu) {
+        let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() else {
+            return;
+        };
+
+        let cm_version = if vdr_dm_data.cmv40_metadata.is_some() {
+            CmVersion::V40
+        } else {
+            CmVersion::V29
+        };
+
+        for adjustment in adjustments {
+            for block in vdr_dm_data.level_blocks_iter_mut(adjustment.level) {
+                adjustment.adjust_block(block, cm_version);
+            }
+        }
+
+        rpu.modified = true;
+    }
+
+    fn adjust_block(&self, block: &mut ExtMetadataBlock, cm_version: CmVersion) {
+        use AdjustmentField::*;
+
+        match block {
+            ExtMetadataBlock::Level1(b) => {
+                let value = match self.field {
+                    MinPq => &mut b.min_pq,
+                    MaxPq => &mut b.max_pq,
+                    AvgPq => &mut b.avg_pq,
+                    _ => return,
+                };
+                *value = self.adjusted_value(*value as i32, 0) as u16;
+
+                b.clamp_values_cm_version(cm_version);
+            }
+            ExtMetadataBlock::Level2(b) => {
+                if self
+                    .target_nits
+                    .is_some_and(|nits| nits_to_pq_12_bit(nits) != b.target_max_pq)
+                {
+                    return;
+                }
+
+                let value = match self.field {
+                    TrimSlope => &mut b.trim_slope,
+                    TrimOffset => &mut b.trim_offset,
+                    TrimPower => &mut b.trim_power,
+                    TrimChromaWeight => &mut b.trim_chroma_weight,
+                    TrimSaturationGain => &mut b.trim_saturation_gain,
+                    MsWeight => {
+                        b.ms_weight = self.adjusted_value(b.ms_weight as i32, -1) as i16;
+                        return;
+                    }
+                    _ => return,
+                };
+                *value = self.adjusted_value(*value as i32, 0) as u16;
+            }
+            ExtMetadataBlock::Level3(b) => {
+                let value = match self.field {
+                    MinPqOffset => &mut b.min_pq_offset,
+                    MaxPqOffset => &mut b.max_pq_offset,
+                    AvgPqOffset => &mut b.avg_pq_offset,
+                    _ => return,
+                };
+                *value = self.adjusted_value(*value as i32, 0) as u16;
+            }
+            ExtMetadataBlock::Level8(b) => {
+                if self
+                    .target_nits
+                    .is_some_and(|nits| nits != b.trim_target_nits())
+                {
+                    return;
+                }
+
+                let value = match self.field {
+                    TrimSlope => &mut b.trim_slope,
+                    TrimOffset => &mut b.trim_offset,
+                    TrimPower => &mut b.trim_power,
+                    TrimChromaWeight => &mut b.trim_chroma_weight,
+                    TrimSaturationGain => &mut b.trim_saturation_gain,
+                    MsWeight => &mut b.ms_weight,
+                    TargetMidContrast => &mut b.target_mid_contrast,
+                    ClipTrim => &mut b.clip_trim,
+                    _ => return,
+                };
+                *value = self.adjusted_value(*value as i32, 0) as u16;
+            }
+            _ => {}
+        }
+    }
+
+    fn adjusted_value(&self, value: i32, min_valid_value: i32) -> i32 {
+        let new_value = value as f64 * self.scale.unwrap_or(1.0) + self.offset.unwrap_or(0.0);
+        let mut new_value = new_value.round() as i32;
+
+        if let Some(min) = self.min {
+            new_value = new_value.max(min as i32);
+        }
+        if let Some(max) = self.max {
+            new_value = new_value.min(max as i32);
+        }
+
+        new_value.clamp(min_valid_value, MAX_12_BIT_VALUE as i32)
+    }
+
+    fn validate(&self) -> Result<()> {
+        use AdjustmentField::*;
+
+        let valid = match self.level {
+            1 => matches!(self.field, MinPq | MaxPq | AvgPq),
+            2 => matches!(
+                self.field,
+                TrimSlope
+                    | TrimOffset
+                    | TrimPower
+                    | TrimChromaWeight
+                    | TrimSaturationGain
+                    | MsWeight
+            ),
+            3 => matches!(self.field, MinPqOffset | MaxPqOffset | AvgPqOffset),
+            8 => !matches!(
+                self.field,
+                MinPq | MaxPq | AvgPq | MinPqOffset | MaxPqOffset | AvgPqOffset
+            ),
+            _ => bail!(
+                "Invalid level adjustment: level {} is not supported, only L1, L2, L3 and L8",
+                self.level
+            ),
+        };
+
+        ensure!(
+            valid,
+            "Invalid level adjustment: L{} has no {:?} field",
+            self.level,
+            self.field
+        );
+        ensure!(
+            self.target_nits.is_none() || matches!(self.level, 2 | 8),
+            "Invalid level adjustment: target_nits is only valid for L2 and L8"
+        );
+
+        Ok(())
+    }
+}
+
+impl FrameRateConversion {
+    /// Source frames for each group of 5 pulldown frames: A B B C D
+    const PULLDOWN_CADENCE: [usize; 5] = [0, 1, 1, 2, 3];
+    /// Index of the repeated frame in each group of 5 telecined frames
+    const TELECINE_REPEATED_FRAME: usize = 2;
+
+    fn execute(
+        &self,
+        rpus: &[Option<DoviRpu>],
+        frame_rate: Option<&FrameRate>,
+    ) -> Result<Vec<Option<DoviRpu>>> {
+        let rpus: Vec<&DoviRpu> = rpus.iter().filter_map(|e| e.as_ref()).collect();
+        let source_indices = self.source_indices(rpus.len(), frame_rate)?;
+
+        let mut prev_source_idx = None;
+        let converted_rpus = source_indices
+            .into_iter()
+            .map(|source_idx| {
+                let mut rpu = rpus[source_idx].clone();
+
+                // Scene cuts are kept on the first frame of the shot, even if the source frame was dropped
+                let first_source_idx = prev_source_idx.map_or(source_idx, |prev| prev + 1);
+                let scene_cut = source_idx != prev_source_idx.unwrap_or(usize::MAX)
+                    && rpus[first_source_idx..=source_idx].iter().any(|rpu| {
+                        rpu.vdr_dm_data
+                            .as_ref()
+                            .is_some_and(|e| e.scene_refresh_flag == 1)
+                    });
+                prev_source_idx = Some(source_idx);
+
+                if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut()
+                    && (vdr_dm_data.scene_refresh_flag == 1) != scene_cut
+                {
+                    rpu.modified = true;
+                    vdr_dm_data.set_scene_cut(scene_cut);
+                }
+
+                Some(rpu)
+            })
+            .collect();
+
+        Ok(converted_rpus)
+    }
+
+    fn source_indices(&self, count: usize, frame_rate: Option<&FrameRate>) -> Result<Vec<usize>> {
+        let indices = match self.mode {
+            FrameRateConversionMode::Pulldown => (0..count.div_ceil(4))
+                .flat_map(|group| Self::PULLDOWN_CADENCE.map(|i| group * 4 + i))
+                .filter(|i| *i < count)
+                .collect(),
+            FrameRateConversionMode::InverseTelecine => (0..count)
+                .filter(|i| i % 5 != Self::TELECINE_REPEATED_FRAME)
+                .collect(),
+            FrameRateConversionMode::Resample => {
+                let (Some(source), Some(target)) = (frame_rate, self.target_frame_rate.as_ref())
+                else {
+                    bail!("Resampling requires both frame_rate and target_frame_rate");
+                };
+
+                let ratio = source.as_f64() / target.as_f64();
+                let new_count = (count as f64 / ratio).round() as usize;
+
+                (0..new_count)
+                    .map(|i| ((i as f64 * ratio).round() as usize).min(count - 1))
+                    .collect()
+            }
+        };
+
+        Ok(indices)
+    }
+}
+
+impl ActiveArea {
+    fn execute(&self, rpus: &mut [Option<DoviRpu>], frame_rate: Option<&FrameRate>) -> Result<()> {
+        if let Some(edits) = &self.edits
+            && !edits.is_empty()
+        {
+            self.do_edits(edits, rpus, frame_rate)?;
+        }
+
+        Ok(())
+    }
+
+    fn execute_single_rpu(&self, rpu: &mut DoviRpu) -> Result<()> {
+        if self.crop {
+            rpu.crop()?;
+        }
+
+        if let Some(drop_opt) = &self.drop_l5 {
+            self.drop_specific_l5(&drop_opt.to_lowercase(), rpu)?;
+        }
+
+        // Allow passing "all" instead of a range
+        // Do "all" presets before specific ranges
+        if let (Some(presets), Some(edits)) = (&self.presets, &self.edits) {
+            for edit in edits {
+                let preset_id = *edit.1;
+
+                if edit.0.to_lowercase() == "all" {
+                    if let Some(active_area_offsets) = presets.iter().find(|e| e.id == preset_id) {
+                        self.set_offsets(rpu, active_area_offsets)?;
+                    } else {
+                        bail!("Invalid preset ID: {}", preset_id);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn do_edits(
+        &self,
+        edits: &HashMap<String, u16>,
+        rpus: &mut [Option<DoviRpu>],
+        frame_rate: Option<&FrameRate>,
+    ) -> Result<()> {
+        if let Some(presets) = &self.presets {
+            let specific_edits = edits.iter().filter(|e| e.0.to_lowercase() != "all");
+
+            for edit in specific_edits {
+                let (start, end) = FrameRate::parse_range(frame_rate, edit.0)?;
+                let preset_id = *edit.1;
+
+                if end > rpus.len() {
+                    bail!("Invalid range: {} > {} available RPUs", end, rpus.len());
+                }
+
+                if let Some(active_area_offsets) = presets.iter().find(|e| e.id == preset_id) {
+                    for rpu in rpus[start..=end].iter_mut().filter_map(|e| e.as_mut()) {
+                        self.set_offsets(rpu, active_area_offsets)?;
+                    }
+                } else {
+                    bail!("Invalid preset ID: {}", preset_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_offsets(
+        &self,
+        rpu: &mut DoviRpu,
+        active_area_offsets: &ActiveAreaOffsets,
+    ) -> Result<()> {
+        rpu.modified = true;
+
+        let (left, right, top, bottom) = (
+            active_area_offsets.left,
+            active_area_offsets.right,
+            active_area_offsets.top,
+            active_area_offsets.bottom,
+        );
+
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            vdr_dm_data.replace_metadata_block(ExtMetadataBlock::Level5(
+                ExtMetadataBlockLevel5::from_offsets(left, right, top, bottom),
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    fn drop_specific_l5(&self, param: &str, rpu: &mut DoviRpu) -> Result<()> {
+        if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+            let drop_it = if param == "zeroes" {
+                let level5_block = vdr_dm_data.get_block(5);
+
+                if let Some(ExtMetadataBlock::Level5(m)) = level5_block {
+                    m.active_area_left_offset == 0
+                        && m.active_area_right_offset == 0
+                        && m.active_area_top_offset == 0
+                        && m.active_area_bottom_offset == 0
+                } else {
+                    false
+                }
+            } else {
+                param == "all"
+            };
+
+            if drop_it {
+                rpu.modified = true;
+
+                vdr_dm_data.remove_metadata_level(5);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::EditConfig;
+    use crate::rpu::generate::GenerateConfig;
+
+    #[test]
+    fn apply_to_list() -> Result<()> {
+        let mut generate_config: GenerateConfig = serde_json::from_str(r#"{ "length": 10 }"#)?;
+        generate_config.fill_defaults()?;
+        let rpus = generate_config.generate_rpu_list()?;
+
+        let config = EditConfig::from_json(
+            r#"{
+                "remove": ["0-1", "9"],
+                "duplicate": [{ "source": 0, "offset": 0, "length": 3 }],
+                "scene_cuts": { "all": false, "4-4": true },
+                "level_adjustments": {
+                    "all": [{ "level": 1, "field": "max_pq", "offset": -100 }]
+                }
+            }"#,
+        )?;
+        let edited = config.apply(rpus)?;
+
+        assert_eq!(edited.len(), 10);
+
+        let scene_cuts: Vec<usize> = edited
+            .iter()
+            .enumerate()
+            .filter(|(_, rpu)| rpu.vdr_dm_data.as_ref().unwrap().scene_refresh_flag == 1)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(scene_cuts, vec![5]);
+
+        let invalid = EditConfig::from_json(
+            r#"{ "level_adjustments": { "all": [{ "level": 5, "field": "max_pq" }] } }"#,
+        );
+        assert!(invalid.is_err());
+
+        Ok(())
+    }
+}
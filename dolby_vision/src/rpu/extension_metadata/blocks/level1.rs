@@ -1,11 +1,10 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
 use crate::rpu::vdr_dm_data::CmVersion;
 
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
@@ -32,11 +31,11 @@ pub struct ExtMetadataBlockLevel1 {
 }
 
 impl ExtMetadataBlockLevel1 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level1(Self {
-            min_pq: reader.read::<12, u16>()?,
-            max_pq: reader.read::<12, u16>()?,
-            avg_pq: reader.read::<12, u16>()?,
+            min_pq: reader.read::<12, u16>("min_pq")?,
+            max_pq: reader.read::<12, u16>("max_pq")?,
+            avg_pq: reader.read::<12, u16>("avg_pq")?,
         }))
     }
 
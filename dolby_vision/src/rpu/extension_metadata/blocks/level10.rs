@@ -1,14 +1,14 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ColorPrimaries, ExtMetadataBlock, ExtMetadataBlockInfo, level6::MAX_PQ_LUMINANCE};
 
-pub const PRESET_TARGET_DISPLAYS: &[u8] = &[1, 16, 18, 21, 27, 28, 37, 38, 42, 48, 49];
+pub const PRESET_TARGET_DISPLAYS: &[u8] = &[1, 16, 18, 21, 24, 25, 27, 28, 37, 38, 42, 48, 49];
 
 /// Custom target display information
 ///
@@ -39,25 +39,25 @@ pub struct ExtMetadataBlockLevel10 {
 }
 
 impl ExtMetadataBlockLevel10 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader, length: u64) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader, length: u64) -> Result<ExtMetadataBlock> {
         let mut block = Self {
             length,
-            target_display_index: reader.read::<8, u8>()?,
-            target_max_pq: reader.read::<12, u16>()?,
-            target_min_pq: reader.read::<12, u16>()?,
-            target_primary_index: reader.read::<8, u8>()?,
+            target_display_index: reader.read::<8, u8>("target_display_index")?,
+            target_max_pq: reader.read::<12, u16>("target_max_pq")?,
+            target_min_pq: reader.read::<12, u16>("target_min_pq")?,
+            target_primary_index: reader.read::<8, u8>("target_primary_index")?,
             ..Default::default()
         };
 
         if length > 5 {
-            block.target_primary_red_x = reader.read::<16, u16>()?;
-            block.target_primary_red_y = reader.read::<16, u16>()?;
-            block.target_primary_green_x = reader.read::<16, u16>()?;
-            block.target_primary_green_y = reader.read::<16, u16>()?;
-            block.target_primary_blue_x = reader.read::<16, u16>()?;
-            block.target_primary_blue_y = reader.read::<16, u16>()?;
-            block.target_primary_white_x = reader.read::<16, u16>()?;
-            block.target_primary_white_y = reader.read::<16, u16>()?;
+            block.target_primary_red_x = reader.read::<16, u16>("target_primary_red_x")?;
+            block.target_primary_red_y = reader.read::<16, u16>("target_primary_red_y")?;
+            block.target_primary_green_x = reader.read::<16, u16>("target_primary_green_x")?;
+            block.target_primary_green_y = reader.read::<16, u16>("target_primary_green_y")?;
+            block.target_primary_blue_x = reader.read::<16, u16>("target_primary_blue_x")?;
+            block.target_primary_blue_y = reader.read::<16, u16>("target_primary_blue_y")?;
+            block.target_primary_white_x = reader.read::<16, u16>("target_primary_white_x")?;
+            block.target_primary_white_y = reader.read::<16, u16>("target_primary_white_y")?;
         }
 
         Ok(ExtMetadataBlock::Level10(block))
@@ -86,6 +86,12 @@ impl ExtMetadataBlockLevel10 {
     }
 
     pub fn validate(&self) -> Result<()> {
+        ensure!(
+            matches!(self.length, 5 | 21),
+            "invalid length {}",
+            self.length
+        );
+
         ensure!(!PRESET_TARGET_DISPLAYS.contains(&self.target_display_index));
         ensure!(self.target_max_pq <= MAX_PQ_LUMINANCE);
         ensure!(self.target_min_pq <= MAX_PQ_LUMINANCE);
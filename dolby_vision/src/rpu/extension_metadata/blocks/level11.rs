@@ -1,11 +1,11 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
 
 /// Content type metadata level
@@ -24,15 +24,15 @@ pub struct ExtMetadataBlockLevel11 {
 }
 
 impl ExtMetadataBlockLevel11 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         let mut l11 = Self {
-            content_type: reader.read::<8, u8>()?,
+            content_type: reader.read::<8, u8>("content_type")?,
             ..Default::default()
         };
 
-        l11.decode_byte1(reader.read::<8, u8>()?);
-        l11.reserved_byte2 = reader.read::<8, u8>()?;
-        l11.reserved_byte3 = reader.read::<8, u8>()?;
+        l11.decode_byte1(reader.read::<8, u8>("byte1")?);
+        l11.reserved_byte2 = reader.read::<8, u8>("reserved_byte2")?;
+        l11.reserved_byte3 = reader.read::<8, u8>("reserved_byte3")?;
 
         Ok(ExtMetadataBlock::Level11(l11))
     }
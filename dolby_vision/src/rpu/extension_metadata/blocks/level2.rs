@@ -1,11 +1,10 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
 use crate::utils::nits_to_pq_12_bit;
 
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo, MAX_12_BIT_VALUE};
@@ -27,15 +26,15 @@ pub struct ExtMetadataBlockLevel2 {
 }
 
 impl ExtMetadataBlockLevel2 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         let mut level2 = Self {
-            target_max_pq: reader.read::<12, u16>()?,
-            trim_slope: reader.read::<12, u16>()?,
-            trim_offset: reader.read::<12, u16>()?,
-            trim_power: reader.read::<12, u16>()?,
-            trim_chroma_weight: reader.read::<12, u16>()?,
-            trim_saturation_gain: reader.read::<12, u16>()?,
-            ms_weight: reader.read::<13, i16>()?,
+            target_max_pq: reader.read::<12, u16>("target_max_pq")?,
+            trim_slope: reader.read::<12, u16>("trim_slope")?,
+            trim_offset: reader.read::<12, u16>("trim_offset")?,
+            trim_power: reader.read::<12, u16>("trim_power")?,
+            trim_chroma_weight: reader.read::<12, u16>("trim_chroma_weight")?,
+            trim_saturation_gain: reader.read::<12, u16>("trim_saturation_gain")?,
+            ms_weight: reader.read::<13, i16>("ms_weight")?,
         };
 
         if level2.ms_weight > MAX_12_BIT_VALUE as i16 {
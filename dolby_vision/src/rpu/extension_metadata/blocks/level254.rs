@@ -1,11 +1,11 @@
 use anyhow::Result;
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
 
 /// Metadata level present in CM v4.0
@@ -19,10 +19,10 @@ pub struct ExtMetadataBlockLevel254 {
 }
 
 impl ExtMetadataBlockLevel254 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level254(Self {
-            dm_mode: reader.read::<8, u8>()?,
-            dm_version_index: reader.read::<8, u8>()?,
+            dm_mode: reader.read::<8, u8>("dm_mode")?,
+            dm_version_index: reader.read::<8, u8>("dm_version_index")?,
         }))
     }
 
@@ -1,11 +1,11 @@
 use anyhow::Result;
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
 
 /// Metadata level optionally present in CM v2.9.
@@ -24,14 +24,14 @@ pub struct ExtMetadataBlockLevel255 {
 }
 
 impl ExtMetadataBlockLevel255 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level255(Self {
-            dm_run_mode: reader.read::<8, u8>()?,
-            dm_run_version: reader.read::<8, u8>()?,
-            dm_debug0: reader.read::<8, u8>()?,
-            dm_debug1: reader.read::<8, u8>()?,
-            dm_debug2: reader.read::<8, u8>()?,
-            dm_debug3: reader.read::<8, u8>()?,
+            dm_run_mode: reader.read::<8, u8>("dm_run_mode")?,
+            dm_run_version: reader.read::<8, u8>("dm_run_version")?,
+            dm_debug0: reader.read::<8, u8>("dm_debug0")?,
+            dm_debug1: reader.read::<8, u8>("dm_debug1")?,
+            dm_debug2: reader.read::<8, u8>("dm_debug2")?,
+            dm_debug3: reader.read::<8, u8>("dm_debug3")?,
         }))
     }
 
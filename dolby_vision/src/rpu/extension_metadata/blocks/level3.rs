@@ -1,11 +1,11 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo, MAX_12_BIT_VALUE};
 
 /// Level 1 offsets.
@@ -20,11 +20,11 @@ pub struct ExtMetadataBlockLevel3 {
 }
 
 impl ExtMetadataBlockLevel3 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level3(Self {
-            min_pq_offset: reader.read::<12, u16>()?,
-            max_pq_offset: reader.read::<12, u16>()?,
-            avg_pq_offset: reader.read::<12, u16>()?,
+            min_pq_offset: reader.read::<12, u16>("min_pq_offset")?,
+            max_pq_offset: reader.read::<12, u16>("max_pq_offset")?,
+            avg_pq_offset: reader.read::<12, u16>("avg_pq_offset")?,
         }))
     }
 
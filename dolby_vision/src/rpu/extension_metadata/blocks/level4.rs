@@ -1,11 +1,11 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo, MAX_12_BIT_VALUE};
 
 /// Something about temporal stability
@@ -18,10 +18,10 @@ pub struct ExtMetadataBlockLevel4 {
 }
 
 impl ExtMetadataBlockLevel4 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level4(Self {
-            anchor_pq: reader.read::<12, u16>()?,
-            anchor_power: reader.read::<12, u16>()?,
+            anchor_pq: reader.read::<12, u16>("anchor_pq")?,
+            anchor_power: reader.read::<12, u16>("anchor_power")?,
         }))
     }
 
@@ -1,11 +1,11 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
 
 const MAX_RESOLUTION_13_BITS: u16 = 8191;
@@ -22,12 +22,12 @@ pub struct ExtMetadataBlockLevel5 {
 }
 
 impl ExtMetadataBlockLevel5 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level5(Self {
-            active_area_left_offset: reader.read::<13, u16>()?,
-            active_area_right_offset: reader.read::<13, u16>()?,
-            active_area_top_offset: reader.read::<13, u16>()?,
-            active_area_bottom_offset: reader.read::<13, u16>()?,
+            active_area_left_offset: reader.read::<13, u16>("active_area_left_offset")?,
+            active_area_right_offset: reader.read::<13, u16>("active_area_right_offset")?,
+            active_area_top_offset: reader.read::<13, u16>("active_area_top_offset")?,
+            active_area_bottom_offset: reader.read::<13, u16>("active_area_bottom_offset")?,
         }))
     }
 
@@ -91,6 +91,42 @@ impl ExtMetadataBlockLevel5 {
             active_area_bottom_offset: bottom,
         }
     }
+
+    /// Centered active area offsets of an image with aspect ratio `image_ar`,
+    /// within a canvas of the specified dimensions and aspect ratio
+    pub fn from_aspect_ratios(
+        canvas_width: u16,
+        canvas_height: u16,
+        canvas_ar: f32,
+        image_ar: f32,
+    ) -> Self {
+        let cw = canvas_width as f32;
+        let ch = canvas_height as f32;
+
+        let mut level5 = Self::default();
+
+        if (canvas_ar - image_ar).abs() < f32::EPSILON {
+            // No AR difference, zero offsets
+        } else if image_ar > canvas_ar {
+            let image_h = (ch * (canvas_ar / image_ar)).round();
+            let diff = ch - image_h;
+            let offset_top = (diff / 2.0).trunc();
+            let offset_bottom = diff - offset_top;
+
+            level5.active_area_top_offset = offset_top as u16;
+            level5.active_area_bottom_offset = offset_bottom as u16;
+        } else {
+            let image_w = (cw * (image_ar / canvas_ar)).round();
+            let diff = cw - image_w;
+            let offset_left = (diff / 2.0).trunc();
+            let offset_right = diff - offset_left;
+
+            level5.active_area_left_offset = offset_left as u16;
+            level5.active_area_right_offset = offset_right as u16;
+        }
+
+        level5
+    }
 }
 
 impl ExtMetadataBlockInfo for ExtMetadataBlockLevel5 {
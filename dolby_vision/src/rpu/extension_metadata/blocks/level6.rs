@@ -1,11 +1,11 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
 
 /// cbindgen:ignore
@@ -23,12 +23,15 @@ pub struct ExtMetadataBlockLevel6 {
 }
 
 impl ExtMetadataBlockLevel6 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<ExtMetadataBlock> {
         Ok(ExtMetadataBlock::Level6(Self {
-            max_display_mastering_luminance: reader.read::<16, u16>()?,
-            min_display_mastering_luminance: reader.read::<16, u16>()?,
-            max_content_light_level: reader.read::<16, u16>()?,
-            max_frame_average_light_level: reader.read::<16, u16>()?,
+            max_display_mastering_luminance: reader
+                .read::<16, u16>("max_display_mastering_luminance")?,
+            min_display_mastering_luminance: reader
+                .read::<16, u16>("min_display_mastering_luminance")?,
+            max_content_light_level: reader.read::<16, u16>("max_content_light_level")?,
+            max_frame_average_light_level: reader
+                .read::<16, u16>("max_frame_average_light_level")?,
         }))
     }
 
@@ -1,13 +1,30 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo, MAX_12_BIT_VALUE};
 
+/// Preset target display indexes and their peak brightness in nits
+pub const PRESET_TARGET_DISPLAY_NITS: &[(u8, u16)] = &[
+    (1, 100),
+    (16, 48),
+    (18, 48),
+    (21, 48),
+    (24, 300),
+    (25, 300),
+    (27, 600),
+    (28, 600),
+    (37, 2000),
+    (38, 2000),
+    (42, 108),
+    (48, 1000),
+    (49, 1000),
+];
+
 /// Creative intent trim passes per target display peak brightness
 /// For CM v4.0, L8 metadata only is present and used to compute L2
 ///
@@ -53,43 +70,43 @@ pub struct ExtMetadataBlockLevel8 {
 }
 
 impl ExtMetadataBlockLevel8 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader, length: u64) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader, length: u64) -> Result<ExtMetadataBlock> {
         let mut block = Self {
             length,
-            target_display_index: reader.read::<8, u8>()?,
-            trim_slope: reader.read::<12, u16>()?,
-            trim_offset: reader.read::<12, u16>()?,
-            trim_power: reader.read::<12, u16>()?,
-            trim_chroma_weight: reader.read::<12, u16>()?,
-            trim_saturation_gain: reader.read::<12, u16>()?,
-            ms_weight: reader.read::<12, u16>()?,
+            target_display_index: reader.read::<8, u8>("target_display_index")?,
+            trim_slope: reader.read::<12, u16>("trim_slope")?,
+            trim_offset: reader.read::<12, u16>("trim_offset")?,
+            trim_power: reader.read::<12, u16>("trim_power")?,
+            trim_chroma_weight: reader.read::<12, u16>("trim_chroma_weight")?,
+            trim_saturation_gain: reader.read::<12, u16>("trim_saturation_gain")?,
+            ms_weight: reader.read::<12, u16>("ms_weight")?,
             ..Default::default()
         };
 
         if length > 10 {
-            block.target_mid_contrast = reader.read::<12, u16>()?;
+            block.target_mid_contrast = reader.read::<12, u16>("target_mid_contrast")?;
         }
 
         if length > 12 {
-            block.clip_trim = reader.read::<12, u16>()?;
+            block.clip_trim = reader.read::<12, u16>("clip_trim")?;
         }
 
         if length > 13 {
-            block.saturation_vector_field0 = reader.read::<8, u8>()?;
-            block.saturation_vector_field1 = reader.read::<8, u8>()?;
-            block.saturation_vector_field2 = reader.read::<8, u8>()?;
-            block.saturation_vector_field3 = reader.read::<8, u8>()?;
-            block.saturation_vector_field4 = reader.read::<8, u8>()?;
-            block.saturation_vector_field5 = reader.read::<8, u8>()?;
+            block.saturation_vector_field0 = reader.read::<8, u8>("saturation_vector_field0")?;
+            block.saturation_vector_field1 = reader.read::<8, u8>("saturation_vector_field1")?;
+            block.saturation_vector_field2 = reader.read::<8, u8>("saturation_vector_field2")?;
+            block.saturation_vector_field3 = reader.read::<8, u8>("saturation_vector_field3")?;
+            block.saturation_vector_field4 = reader.read::<8, u8>("saturation_vector_field4")?;
+            block.saturation_vector_field5 = reader.read::<8, u8>("saturation_vector_field5")?;
         }
 
         if length > 19 {
-            block.hue_vector_field0 = reader.read::<8, u8>()?;
-            block.hue_vector_field1 = reader.read::<8, u8>()?;
-            block.hue_vector_field2 = reader.read::<8, u8>()?;
-            block.hue_vector_field3 = reader.read::<8, u8>()?;
-            block.hue_vector_field4 = reader.read::<8, u8>()?;
-            block.hue_vector_field5 = reader.read::<8, u8>()?;
+            block.hue_vector_field0 = reader.read::<8, u8>("hue_vector_field0")?;
+            block.hue_vector_field1 = reader.read::<8, u8>("hue_vector_field1")?;
+            block.hue_vector_field2 = reader.read::<8, u8>("hue_vector_field2")?;
+            block.hue_vector_field3 = reader.read::<8, u8>("hue_vector_field3")?;
+            block.hue_vector_field4 = reader.read::<8, u8>("hue_vector_field4")?;
+            block.hue_vector_field5 = reader.read::<8, u8>("hue_vector_field5")?;
         }
 
         Ok(ExtMetadataBlock::Level8(block))
@@ -137,6 +154,12 @@ impl ExtMetadataBlockLevel8 {
     }
 
     pub fn validate(&self) -> Result<()> {
+        ensure!(
+            matches!(self.length, 10 | 12 | 13 | 19 | 25),
+            "invalid length {}",
+            self.length
+        );
+
         ensure!(self.trim_slope <= MAX_12_BIT_VALUE);
         ensure!(self.trim_offset <= MAX_12_BIT_VALUE);
         ensure!(self.trim_power <= MAX_12_BIT_VALUE);
@@ -150,15 +173,10 @@ impl ExtMetadataBlockLevel8 {
     }
 
     pub fn trim_target_nits(&self) -> u16 {
-        match self.target_display_index {
-            16 | 18 | 21 => 48,
-            42 => 108,
-            24 | 25 => 300,
-            27 | 28 => 600,
-            48 | 49 => 1000,
-            37 | 38 => 2000,
-            _ => 100,
-        }
+        PRESET_TARGET_DISPLAY_NITS
+            .iter()
+            .find(|(index, _)| *index == self.target_display_index)
+            .map_or(100, |(_, nits)| *nits)
     }
 }
 
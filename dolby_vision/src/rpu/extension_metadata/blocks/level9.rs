@@ -1,12 +1,11 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
 
 use crate::rpu::extension_metadata::MasteringDisplayPrimaries;
+use crate::rpu::trace::RpuBitReader;
 
 use super::{ColorPrimaries, ExtMetadataBlock, ExtMetadataBlockInfo};
 
@@ -57,22 +56,22 @@ pub struct ExtMetadataBlockLevel9 {
 }
 
 impl ExtMetadataBlockLevel9 {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader, length: u64) -> Result<ExtMetadataBlock> {
+    pub(crate) fn parse(reader: &mut RpuBitReader, length: u64) -> Result<ExtMetadataBlock> {
         let mut block = Self {
             length,
-            source_primary_index: reader.read::<8, u8>()?,
+            source_primary_index: reader.read::<8, u8>("source_primary_index")?,
             ..Default::default()
         };
 
         if length > 1 {
-            block.source_primary_red_x = reader.read::<16, u16>()?;
-            block.source_primary_red_y = reader.read::<16, u16>()?;
-            block.source_primary_green_x = reader.read::<16, u16>()?;
-            block.source_primary_green_y = reader.read::<16, u16>()?;
-            block.source_primary_blue_x = reader.read::<16, u16>()?;
-            block.source_primary_blue_y = reader.read::<16, u16>()?;
-            block.source_primary_white_x = reader.read::<16, u16>()?;
-            block.source_primary_white_y = reader.read::<16, u16>()?;
+            block.source_primary_red_x = reader.read::<16, u16>("source_primary_red_x")?;
+            block.source_primary_red_y = reader.read::<16, u16>("source_primary_red_y")?;
+            block.source_primary_green_x = reader.read::<16, u16>("source_primary_green_x")?;
+            block.source_primary_green_y = reader.read::<16, u16>("source_primary_green_y")?;
+            block.source_primary_blue_x = reader.read::<16, u16>("source_primary_blue_x")?;
+            block.source_primary_blue_y = reader.read::<16, u16>("source_primary_blue_y")?;
+            block.source_primary_white_x = reader.read::<16, u16>("source_primary_white_x")?;
+            block.source_primary_white_y = reader.read::<16, u16>("source_primary_white_y")?;
         }
 
         Ok(ExtMetadataBlock::Level9(block))
@@ -98,6 +97,12 @@ impl ExtMetadataBlockLevel9 {
     }
 
     pub fn validate(&self) -> Result<()> {
+        ensure!(
+            matches!(self.length, 1 | 17),
+            "invalid length {}",
+            self.length
+        );
+
         if self.length > 1 {
             // Custom primaries required
             ensure!(self.source_primary_index == 255);
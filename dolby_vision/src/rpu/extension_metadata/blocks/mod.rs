@@ -1,11 +1,12 @@
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::error::DoviError;
+use crate::rpu::trace::RpuBitReader;
+
 pub mod level1;
 pub mod level10;
 pub mod level11;
@@ -163,6 +164,8 @@ impl ExtMetadataBlock {
     }
 
     pub fn write(&self, writer: &mut BitstreamIoWriter) -> Result<()> {
+        self.validate()?;
+
         match self {
             ExtMetadataBlock::Level1(b) => b.write(writer),
             ExtMetadataBlock::Level2(b) => b.write(writer),
@@ -180,13 +183,39 @@ impl ExtMetadataBlock {
         }
     }
 
+    /// Validates the block values, failing with `DoviError::BlockValidation`
+    pub fn validate(&self) -> Result<()> {
+        let res = match self {
+            ExtMetadataBlock::Level1(b) => b.validate(),
+            ExtMetadataBlock::Level2(b) => b.validate(),
+            ExtMetadataBlock::Level3(b) => b.validate(),
+            ExtMetadataBlock::Level4(b) => b.validate(),
+            ExtMetadataBlock::Level5(b) => b.validate(),
+            ExtMetadataBlock::Level6(b) => b.validate(),
+            ExtMetadataBlock::Level8(b) => b.validate(),
+            ExtMetadataBlock::Level9(b) => b.validate(),
+            ExtMetadataBlock::Level10(b) => b.validate(),
+            ExtMetadataBlock::Level11(b) => b.validate(),
+            ExtMetadataBlock::Level254(_)
+            | ExtMetadataBlock::Level255(_)
+            | ExtMetadataBlock::Reserved(_) => Ok(()),
+        };
+
+        res.map_err(|e| {
+            let level = self.level();
+            DoviError::block(level, format!("Invalid L{level} metadata block: {e}")).into()
+        })
+    }
+
     pub fn validate_correct_dm_data<T: WithExtMetadataBlocks>(&self) -> Result<()> {
         let level = self.level();
 
         ensure!(
             T::ALLOWED_BLOCK_LEVELS.contains(&level),
-            "Metadata block level {} is not allowed",
-            &level
+            DoviError::block(
+                level,
+                format!("Metadata block level {level} is not allowed")
+            )
         );
 
         Ok(())
@@ -194,17 +223,20 @@ impl ExtMetadataBlock {
 
     pub(crate) fn validate_and_read_remaining<T: WithExtMetadataBlocks>(
         &self,
-        reader: &mut BsIoSliceReader,
+        reader: &mut RpuBitReader,
         block_length: u64,
     ) -> Result<()> {
         let level = self.level();
 
         ensure!(
             block_length == self.length_bytes(),
-            format!(
-                "Invalid metadata block. Block level {} should have length {}",
+            DoviError::block(
                 level,
-                self.length_bytes()
+                format!(
+                    "Invalid metadata block. Block level {} should have length {}",
+                    level,
+                    self.length_bytes()
+                )
             )
         );
 
@@ -214,8 +246,8 @@ impl ExtMetadataBlock {
 
         for _ in 0..ext_block_use_bits {
             ensure!(
-                !reader.read_bit()?,
-                format!("ext_dm_alignment_zero_bit != 0")
+                !reader.read_bit("ext_dm_alignment_zero_bit")?,
+                DoviError::block(level, "ext_dm_alignment_zero_bit != 0")
             );
         }
 
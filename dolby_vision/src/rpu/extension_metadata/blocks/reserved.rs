@@ -1,12 +1,12 @@
 use anyhow::{Result, bail};
 
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::rpu::trace::RpuBitReader;
+
 use super::{ExtMetadataBlock, ExtMetadataBlockInfo};
 
 #[derive(Debug, Default, Clone)]
@@ -23,10 +23,10 @@ impl ReservedExtMetadataBlock {
     pub(crate) fn parse(
         ext_block_length: u64,
         ext_block_level: u8,
-        reader: &mut BsIoSliceReader,
+        reader: &mut RpuBitReader,
     ) -> Result<ExtMetadataBlock> {
         let mut data = vec![0; ext_block_length as usize];
-        reader.read_bytes(&mut data)?;
+        reader.read_bytes(&mut data, "ext_block_data")?;
 
         Ok(ExtMetadataBlock::Reserved(Self {
             ext_block_length,
@@ -1,11 +1,12 @@
 use anyhow::{Result, bail, ensure};
-use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use super::WithExtMetadataBlocks;
+use crate::error::DoviError;
 use crate::rpu::extension_metadata::blocks::*;
+use crate::rpu::trace::RpuBitReader;
 
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -41,9 +42,9 @@ impl WithExtMetadataBlocks for CmV29DmData {
         self.ext_metadata_blocks.as_mut()
     }
 
-    fn parse_block(&mut self, reader: &mut BsIoSliceReader) -> Result<()> {
-        let ext_block_length = reader.read_ue()?;
-        let ext_block_level = reader.read::<8, u8>()?;
+    fn parse_block(&mut self, reader: &mut RpuBitReader) -> Result<()> {
+        let ext_block_length = reader.read_ue("ext_block_length")?;
+        let ext_block_level = reader.read::<8, u8>("ext_block_level")?;
 
         let ext_metadata_block = match ext_block_level {
             1 => level1::ExtMetadataBlockLevel1::parse(reader)?,
@@ -52,14 +53,21 @@ impl WithExtMetadataBlocks for CmV29DmData {
             5 => level5::ExtMetadataBlockLevel5::parse(reader)?,
             6 => level6::ExtMetadataBlockLevel6::parse(reader)?,
             255 => level255::ExtMetadataBlockLevel255::parse(reader)?,
-            3 | 8 | 9 | 10 | 11 | 254 => bail!("Disallowed block level {}", ext_block_level),
+            3 | 8 | 9 | 10 | 11 | 254 => {
+                bail!(DoviError::block(
+                    ext_block_level,
+                    format!("Disallowed block level {ext_block_level}")
+                ))
+            }
             _ => {
                 // FIXME: This returns early so the parsing doesn't actually take place
                 ensure!(
                     false,
-                    format!(
-                        "Unknown metadata block found: Level {}, length {}, please open an issue.",
-                        ext_block_level, ext_block_length
+                    DoviError::block(
+                        ext_block_level,
+                        format!(
+                            "Unknown metadata block found: Level {ext_block_level}, length {ext_block_length}, please open an issue."
+                        )
                     )
                 );
 
@@ -104,10 +112,14 @@ impl CmV29DmData {
     pub fn validate(&self) -> Result<()> {
         let blocks = self.blocks_ref();
 
-        let invalid_blocks_count = blocks
+        ensure!(
+            self.num_ext_blocks == blocks.len() as u64,
+            "num_ext_blocks should match the number of blocks"
+        );
+
+        let invalid_block = blocks
             .iter()
-            .filter(|b| !Self::ALLOWED_BLOCK_LEVELS.contains(&b.level()))
-            .count();
+            .find(|b| !Self::ALLOWED_BLOCK_LEVELS.contains(&b.level()));
 
         let level1_count = blocks.iter().filter(|b| b.level() == 1).count();
 
@@ -121,34 +133,36 @@ impl CmV29DmData {
 
         let level6_count = blocks.iter().filter(|b| b.level() == 6).count();
 
-        ensure!(
-            invalid_blocks_count == 0,
-            "Only allowed blocks level 1, 2, 4, 5, 6, and 255"
-        );
+        if let Some(block) = invalid_block {
+            bail!(DoviError::block(
+                block.level(),
+                "Only allowed blocks level 1, 2, 4, 5, 6, and 255"
+            ));
+        }
 
         ensure!(
             level1_count <= 1,
-            "There must be at most one L1 metadata block"
+            DoviError::block(1, "There must be at most one L1 metadata block")
         );
         ensure!(
             level2_count <= 8,
-            "There must be at most 8 L2 metadata blocks"
+            DoviError::block(2, "There must be at most 8 L2 metadata blocks")
         );
         ensure!(
             level255_count <= 1,
-            "There must be at most one L255 metadata block"
+            DoviError::block(255, "There must be at most one L255 metadata block")
         );
         ensure!(
             level4_count <= 1,
-            "There must be at most one L4 metadata block"
+            DoviError::block(4, "There must be at most one L4 metadata block")
         );
         ensure!(
             level5_count <= 1,
-            "There must be at most one L5 metadata block"
+            DoviError::block(5, "There must be at most one L5 metadata block")
         );
         ensure!(
             level6_count <= 1,
-            "There must be at most one L6 metadata block"
+            DoviError::block(6, "There must be at most one L6 metadata block")
         );
 
         Ok(())
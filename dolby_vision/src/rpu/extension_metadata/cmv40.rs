@@ -1,11 +1,12 @@
 use anyhow::{Result, bail, ensure};
-use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use super::WithExtMetadataBlocks;
+use crate::error::DoviError;
 use crate::rpu::extension_metadata::blocks::*;
+use crate::rpu::trace::RpuBitReader;
 
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -41,9 +42,9 @@ impl WithExtMetadataBlocks for CmV40DmData {
         self.ext_metadata_blocks.as_mut()
     }
 
-    fn parse_block(&mut self, reader: &mut BsIoSliceReader) -> Result<()> {
-        let ext_block_length = reader.read_ue()?;
-        let ext_block_level: u8 = reader.read::<8, u8>()?;
+    fn parse_block(&mut self, reader: &mut RpuBitReader) -> Result<()> {
+        let ext_block_length = reader.read_ue("ext_block_length")?;
+        let ext_block_level: u8 = reader.read::<8, u8>("ext_block_level")?;
 
         let ext_metadata_block = match ext_block_level {
             3 => level3::ExtMetadataBlockLevel3::parse(reader)?,
@@ -52,14 +53,21 @@ impl WithExtMetadataBlocks for CmV40DmData {
             10 => level10::ExtMetadataBlockLevel10::parse(reader, ext_block_length)?,
             11 => level11::ExtMetadataBlockLevel11::parse(reader)?,
             254 => level254::ExtMetadataBlockLevel254::parse(reader)?,
-            1 | 2 | 4 | 5 | 6 | 255 => bail!("Disallowed block level {}", ext_block_level),
+            1 | 2 | 4 | 5 | 6 | 255 => {
+                bail!(DoviError::block(
+                    ext_block_level,
+                    format!("Disallowed block level {ext_block_level}")
+                ))
+            }
             _ => {
                 // FIXME: This returns early so the parsing doesn't actually take place
                 ensure!(
                     false,
-                    format!(
-                        "Unknown metadata block found: Level {}, length {}, please open an issue.",
-                        ext_block_level, ext_block_length
+                    DoviError::block(
+                        ext_block_level,
+                        format!(
+                            "Unknown metadata block found: Level {ext_block_level}, length {ext_block_length}, please open an issue."
+                        )
                     )
                 );
 
@@ -121,10 +129,14 @@ impl CmV40DmData {
     pub fn validate(&self) -> Result<()> {
         let blocks = self.blocks_ref();
 
-        let invalid_blocks_count = blocks
+        ensure!(
+            self.num_ext_blocks == blocks.len() as u64,
+            "num_ext_blocks should match the number of blocks"
+        );
+
+        let invalid_block = blocks
             .iter()
-            .filter(|b| !Self::ALLOWED_BLOCK_LEVELS.contains(&b.level()))
-            .count();
+            .find(|b| !Self::ALLOWED_BLOCK_LEVELS.contains(&b.level()));
 
         let level254_count = blocks.iter().filter(|b| b.level() == 254).count();
 
@@ -138,32 +150,37 @@ impl CmV40DmData {
 
         let level11_count = blocks.iter().filter(|b| b.level() == 11).count();
 
+        if let Some(block) = invalid_block {
+            bail!(DoviError::block(
+                block.level(),
+                "Only allowed blocks level 3, 8, 9, 10, 11 and 254"
+            ));
+        }
+
         ensure!(
-            invalid_blocks_count == 0,
-            "Only allowed blocks level 3, 8, 9, 10, 11 and 254"
+            level254_count == 1,
+            DoviError::block(254, "There must be one L254 metadata block")
         );
 
-        ensure!(level254_count == 1, "There must be one L254 metadata block");
-
         ensure!(
             level3_count <= 1,
-            "There must be at most one L3 metadata block"
+            DoviError::block(3, "There must be at most one L3 metadata block")
         );
         ensure!(
             level8_count <= 5,
-            "There must be at most 5 L8 metadata blocks"
+            DoviError::block(8, "There must be at most 5 L8 metadata blocks")
         );
         ensure!(
             level9_count <= 1,
-            "There must be at most one L9 metadata block"
+            DoviError::block(9, "There must be at most one L9 metadata block")
         );
         ensure!(
             level10_count <= 4,
-            "There must be at most 4 L10 metadata blocks"
+            DoviError::block(10, "There must be at most 4 L10 metadata blocks")
         );
         ensure!(
             level11_count <= 1,
-            "There must be at most one L11 metadata block"
+            DoviError::block(11, "There must be at most one L11 metadata block")
         );
 
         Ok(())
@@ -1,7 +1,5 @@
 use anyhow::{Context, Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -18,6 +16,9 @@ pub use cmv40::CmV40DmData;
 
 use blocks::ExtMetadataBlock;
 
+use crate::error::DoviError;
+use crate::rpu::trace::RpuBitReader;
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[cfg_attr(feature = "serde", serde(untagged))]
@@ -27,7 +28,7 @@ pub enum DmData {
 }
 
 pub trait ExtMetadata {
-    fn parse(&mut self, reader: &mut BsIoSliceReader) -> Result<()>;
+    fn parse(&mut self, reader: &mut RpuBitReader) -> Result<()>;
     fn write(&self, writer: &mut BitstreamIoWriter);
 }
 
@@ -40,7 +41,7 @@ pub trait WithExtMetadataBlocks {
     fn set_num_ext_blocks(&mut self, num_ext_blocks: u64);
     fn num_ext_blocks(&self) -> u64;
 
-    fn parse_block(&mut self, reader: &mut BsIoSliceReader) -> Result<()>;
+    fn parse_block(&mut self, reader: &mut RpuBitReader) -> Result<()>;
     fn blocks_ref(&self) -> &Vec<ExtMetadataBlock>;
     fn blocks_mut(&mut self) -> &mut Vec<ExtMetadataBlock>;
 
@@ -59,7 +60,10 @@ pub trait WithExtMetadataBlocks {
 
         ensure!(
             Self::ALLOWED_BLOCK_LEVELS.contains(&level),
-            "Metadata block level {level} is not allowed"
+            DoviError::block(
+                level,
+                format!("Metadata block level {level} is not allowed")
+            )
         );
 
         let blocks = self.blocks_mut();
@@ -79,16 +83,24 @@ pub trait WithExtMetadataBlocks {
 
     fn write(&self, writer: &mut BitstreamIoWriter) -> Result<()> {
         let num_ext_blocks = self.num_ext_blocks();
+        let ext_metadata_blocks = self.blocks_ref();
+
+        ensure!(
+            num_ext_blocks == ext_metadata_blocks.len() as u64,
+            "num_ext_blocks {num_ext_blocks} does not match the {} blocks",
+            ext_metadata_blocks.len()
+        );
 
         writer.write_ue(num_ext_blocks)?;
 
         // dm_alignment_zero_bit
         writer.byte_align()?;
 
-        let ext_metadata_blocks = self.blocks_ref();
-
         for ext_metadata_block in ext_metadata_blocks {
             let level = ext_metadata_block.level();
+
+            // The block length must be valid to compute the alignment bits
+            ext_metadata_block.validate()?;
             let remaining_bits =
                 (ext_metadata_block.length_bits() - ext_metadata_block.required_bits()) as u32;
 
@@ -109,19 +121,24 @@ pub trait WithExtMetadataBlocks {
 
 impl DmData {
     pub(crate) fn parse<T: WithExtMetadataBlocks + Default>(
-        reader: &mut BsIoSliceReader,
+        reader: &mut RpuBitReader,
     ) -> Result<Option<T>> {
-        let num_ext_blocks = reader.read_ue()?;
+        let num_ext_blocks = reader.read_ue("num_ext_blocks")?;
         let mut meta = T::with_blocks_allocation(num_ext_blocks);
 
         meta.set_num_ext_blocks(num_ext_blocks);
 
         while !reader.byte_aligned() {
-            ensure!(!reader.read_bit()?, "dm_alignment_zero_bit != 0");
+            ensure!(
+                !reader.read_bit("dm_alignment_zero_bit")?,
+                "dm_alignment_zero_bit != 0"
+            );
         }
 
-        for _ in 0..num_ext_blocks {
+        for i in 0..num_ext_blocks {
+            reader.begin_with(|| format!("ext_metadata_block[{i}]"));
             meta.parse_block(reader)?;
+            reader.end();
         }
 
         Ok(Some(meta))
@@ -222,10 +222,45 @@ impl GenerateConfig {
         Ok(())
     }
 
+    /// Fills in the defaults of a deserialized config, like `dovi_tool generate` does:
+    ///   - `l1_avg_pq_cm_version` defaults to `cm_version`.
+    ///   - `length` is derived from the shots, or a single shot is created for `length` frames.
+    ///   - L1 metadata is clamped to valid values for the CM version.
+    pub fn fill_defaults(&mut self) -> Result<()> {
+        self.l1_avg_pq_cm_version = Some(self.l1_cm_version());
+
+        if self.length == 0 {
+            self.length = self.shots.iter().map(|s| s.duration).sum();
+        }
+
+        ensure!(
+            self.length > 0,
+            "Missing number of RPUs to generate, and no shots to derive it from"
+        );
+
+        if self.shots.is_empty() {
+            self.shots.push(VideoShot {
+                start: 0,
+                duration: self.length,
+                ..Default::default()
+            });
+        }
+
+        self.fixup_l1();
+
+        Ok(())
+    }
+
+    /// CM version used to compute the L1 average PQ, defaults to `cm_version`
+    pub fn l1_cm_version(&self) -> CmVersion {
+        self.l1_avg_pq_cm_version.unwrap_or(self.cm_version)
+    }
+
     pub fn fixup_l1(&mut self) {
+        let cm_version = self.l1_cm_version();
         let clamp_l1 = |block: &mut ExtMetadataBlock| {
             if let ExtMetadataBlock::Level1(l1) = block {
-                l1.clamp_values_cm_version(self.l1_avg_pq_cm_version.unwrap_or(self.cm_version));
+                l1.clamp_values_cm_version(cm_version);
             }
         };
 
@@ -342,6 +377,44 @@ mod tests {
         xml::{CmXmlParser, XmlParserOpts},
     };
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn fill_defaults_from_json() -> Result<()> {
+        use super::GenerateConfig;
+
+        let mut config: GenerateConfig = serde_json::from_str(
+            r#"{
+                "cm_version": "V29",
+                "length": 10,
+                "level6": {
+                    "max_display_mastering_luminance": 1000,
+                    "min_display_mastering_luminance": 1,
+                    "max_content_light_level": 1000,
+                    "max_frame_average_light_level": 400
+                },
+                "default_metadata_blocks": [
+                    { "Level1": { "min_pq": 0, "max_pq": 4095, "avg_pq": 0 } }
+                ]
+            }"#,
+        )?;
+        config.fill_defaults()?;
+
+        assert_eq!(config.l1_avg_pq_cm_version, Some(CmVersion::V29));
+        assert_eq!(config.shots.len(), 1);
+        assert_eq!(config.shots[0].duration, 10);
+
+        if let ExtMetadataBlock::Level1(level1) = &config.default_metadata_blocks[0] {
+            assert_eq!(level1.avg_pq, 819);
+        }
+
+        assert_eq!(config.generate_rpu_list()?.len(), 10);
+
+        let mut config: GenerateConfig = serde_json::from_str(r#"{ "level6": null }"#)?;
+        assert!(config.fill_defaults().is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn config_with_frame_edits() -> Result<()> {
         let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -350,6 +423,7 @@ mod tests {
         let opts = XmlParserOpts {
             canvas_width: Some(3840),
             canvas_height: Some(2160),
+            ..Default::default()
         };
         let parser = CmXmlParser::parse_file(assets_path.join("assets/tests/cmv4_0_2.xml"), opts)?;
 
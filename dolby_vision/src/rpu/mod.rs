@@ -1,12 +1,16 @@
 use crc::{CRC_32_MPEG_2, Crc, Table};
 
 pub mod dovi_rpu;
+#[cfg(feature = "serde")]
+pub mod editor;
 pub mod extension_metadata;
 pub mod generate;
+pub mod prev_vdr_rpu;
 pub mod profiles;
 pub mod rpu_data_header;
 pub mod rpu_data_mapping;
 pub mod rpu_data_nlq;
+pub mod trace;
 pub mod vdr_dm_data;
 
 pub mod utils;
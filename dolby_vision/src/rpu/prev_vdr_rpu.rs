@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result, ensure};
+
+use super::dovi_rpu::DoviRpu;
+use super::rpu_data_mapping::RpuDataMapping;
+
+/// Resolves the `rpu_data_mapping` of RPUs using `use_prev_vdr_rpu_flag`.
+///
+/// Such RPUs do not contain a mapping, they reference the last mapping parsed with `prev_vdr_rpu_id`.
+/// The resolver keeps track of the mappings by `vdr_rpu_id`, and must see the RPUs in decoding order.
+///
+/// RPU files, such as the ones from `parse_rpu_file` or `RpuReader`, are in presentation order.
+/// Resolving them is only correct for streams without frame reordering, so it is never done implicitly.
+#[derive(Debug, Default, Clone)]
+pub struct PrevVdrRpuResolver {
+    mappings: HashMap<u64, RpuDataMapping>,
+}
+
+impl PrevVdrRpuResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the mapping of the RPU, or sets the referenced mapping when `use_prev_vdr_rpu_flag` is set.
+    ///
+    /// The header is left untouched, so the RPU is still written without its mapping.
+    /// Returns false when the referenced mapping is unknown, leaving `rpu_data_mapping` empty.
+    pub fn resolve(&mut self, rpu: &mut DoviRpu) -> bool {
+        let header = &rpu.header;
+
+        if !header.use_prev_vdr_rpu_flag {
+            self.record(rpu);
+            return true;
+        }
+
+        if rpu.rpu_data_mapping.is_some() {
+            return true;
+        }
+
+        match self.mappings.get(&header.prev_vdr_rpu_id) {
+            Some(mapping) => {
+                rpu.rpu_data_mapping = Some(mapping.clone());
+                rpu.el_type = rpu.get_enhancement_layer_type();
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn record(&mut self, rpu: &DoviRpu) {
+        if !rpu.header.use_prev_vdr_rpu_flag
+            && let Some(mapping) = &rpu.rpu_data_mapping
+        {
+            self.mappings.insert(mapping.vdr_rpu_id, mapping.clone());
+        }
+    }
+
+    /// Mapping last recorded for the `vdr_rpu_id`
+    pub fn mapping(&self, vdr_rpu_id: u64) -> Option<&RpuDataMapping> {
+        self.mappings.get(&vdr_rpu_id)
+    }
+
+    /// Resolves a list of RPUs, which must be in decoding order, see `resolve`.
+    ///
+    /// Returns the indices of the RPUs referencing an unknown mapping.
+    pub fn resolve_all(rpus: &mut [DoviRpu]) -> Vec<usize> {
+        let mut resolver = Self::new();
+
+        rpus.iter_mut()
+            .enumerate()
+            .filter_map(|(i, rpu)| (!resolver.resolve(rpu)).then_some(i))
+            .collect()
+    }
+
+    /// Resolves the RPU from the RPUs decoded before it, then writes its mapping explicitly.
+    ///
+    /// Used for RPUs starting a new list, e.g. when splitting or concatenating, as the referenced mapping is not part of it.
+    /// Fails when the referenced mapping is not found in `previous`.
+    pub fn detach(previous: &[DoviRpu], rpu: &mut DoviRpu) -> Result<()> {
+        if !rpu.header.use_prev_vdr_rpu_flag {
+            return Ok(());
+        }
+
+        if rpu.rpu_data_mapping.is_none() {
+            let mut resolver = Self::new();
+            previous.iter().for_each(|e| resolver.record(e));
+
+            ensure!(
+                resolver.resolve(rpu),
+                "The RPU references the unknown mapping {}",
+                rpu.header.prev_vdr_rpu_id
+            );
+        }
+
+        rpu.header.use_prev_vdr_rpu_flag = false;
+        rpu.header.prev_vdr_rpu_id = 0;
+        rpu.modified = true;
+
+        Ok(())
+    }
+
+    /// Sets `use_prev_vdr_rpu_flag` on every RPU after the first, to avoid writing the same mapping for every frame.
+    ///
+    /// The references are resolved in decoding order, while RPU lists are usually in presentation order.
+    /// Sharing is only independent of the order when every mapping is identical, so it fails otherwise.
+    /// Runs of consecutive identical mappings are not shared, as they could reference the wrong mapping once reordered.
+    /// The first RPU keeps its mapping, and must also be the first decoded, e.g. an IDR frame.
+    pub fn share_identical_mappings<'a>(
+        rpus: impl IntoIterator<Item = &'a mut DoviRpu>,
+    ) -> Result<()> {
+        // Frame indices, before skipping the RPUs without mapping
+        let mut rpus: Vec<(usize, &mut DoviRpu)> = rpus
+            .into_iter()
+            .enumerate()
+            .filter(|(_, rpu)| rpu.header.rpu_type == 2)
+            .collect();
+
+        let Some((_, first)) = rpus.first() else {
+            return Ok(());
+        };
+        let mapping = first
+            .rpu_data_mapping
+            .clone()
+            .context("The first RPU has no mapping to share")?;
+
+        for (i, rpu) in &rpus {
+            ensure!(
+                rpu.rpu_data_mapping.as_ref() == Some(&mapping),
+                "The mapping of RPU {i} differs from the first RPU's, mappings can only be shared when they are all identical"
+            );
+        }
+
+        for (i, (_, rpu)) in rpus.iter_mut().enumerate() {
+            let use_prev = i > 0;
+            let prev_vdr_rpu_id = if use_prev { mapping.vdr_rpu_id } else { 0 };
+
+            let header = &mut rpu.header;
+            if header.use_prev_vdr_rpu_flag != use_prev || header.prev_vdr_rpu_id != prev_vdr_rpu_id
+            {
+                header.use_prev_vdr_rpu_flag = use_prev;
+                header.prev_vdr_rpu_id = prev_vdr_rpu_id;
+
+                rpu.modified = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use anyhow::Result;
+
+    use super::PrevVdrRpuResolver;
+    use crate::rpu::ConversionMode;
+    use crate::rpu::dovi_rpu::DoviRpu;
+    use crate::rpu::utils::parse_rpu_file;
+
+    fn assets_path() -> PathBuf {
+        let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        lib_path.parent().unwrap().join("assets/hevc_tests")
+    }
+
+    #[test]
+    fn share_and_resolve_mappings() -> Result<()> {
+        let mut rpus = parse_rpu_file(assets_path().join("regular_rpu_mel.bin"))?;
+        let original_mappings: Vec<_> = rpus.iter().map(|e| e.rpu_data_mapping.clone()).collect();
+        let original_size: usize = rpus
+            .iter()
+            .map(|e| e.write_hevc_unspec62_nalu().map(|data| data.len()))
+            .sum::<Result<_>>()?;
+
+        PrevVdrRpuResolver::share_identical_mappings(rpus.iter_mut())?;
+        assert!(!rpus[0].header.use_prev_vdr_rpu_flag);
+        assert!(rpus.iter().skip(1).all(|e| e.header.use_prev_vdr_rpu_flag));
+
+        let encoded: Vec<Vec<u8>> = rpus
+            .iter()
+            .map(|e| e.write_hevc_unspec62_nalu())
+            .collect::<Result<_>>()?;
+        assert!(encoded.iter().map(Vec::len).sum::<usize>() < original_size);
+
+        // Unresolved when parsed alone
+        let mut parsed: Vec<DoviRpu> = encoded
+            .iter()
+            .map(|data| DoviRpu::parse_unspec62_nalu(data))
+            .collect::<Result<_>>()?;
+        assert!(parsed[1].rpu_data_mapping.is_none());
+        assert!(parsed[1].el_type.is_none());
+
+        assert!(PrevVdrRpuResolver::resolve_all(&mut parsed).is_empty());
+
+        let resolved_mappings: Vec<_> = parsed.iter().map(|e| e.rpu_data_mapping.clone()).collect();
+        assert_eq!(resolved_mappings, original_mappings);
+        assert_eq!(parsed[1].el_type, rpus[1].el_type);
+
+        // Resolved RPUs are written without their mapping
+        assert_eq!(parsed[1].write_hevc_unspec62_nalu()?, encoded[1]);
+
+        // Editing the mapping of a referencing RPU writes it explicitly
+        parsed[2].convert_with_mode(ConversionMode::To81MappingPreserved)?;
+        assert!(!parsed[2].header.use_prev_vdr_rpu_flag);
+
+        // The mappings are not all identical anymore
+        assert!(PrevVdrRpuResolver::share_identical_mappings(parsed.iter_mut()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_reference() -> Result<()> {
+        let mut rpus = parse_rpu_file(assets_path().join("regular_rpu_mel.bin"))?;
+        PrevVdrRpuResolver::share_identical_mappings(rpus.iter_mut())?;
+
+        let mut parsed = rpus[1..3]
+            .iter()
+            .map(|e| DoviRpu::parse_unspec62_nalu(&e.write_hevc_unspec62_nalu()?))
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(PrevVdrRpuResolver::resolve_all(&mut parsed), vec![0, 1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reordered_gop() -> Result<()> {
+        let rpus = parse_rpu_file(assets_path().join("regular_rpu_mel.bin"))?;
+
+        // Decoding order: I, P, then a B frame referencing the P frame's mapping
+        let i_frame = rpus[0].clone();
+        let mut p_frame = rpus[1].clone();
+        p_frame.convert_with_mode(ConversionMode::To81MappingPreserved)?;
+
+        let mut b_frame = p_frame.clone();
+        b_frame.header.use_prev_vdr_rpu_flag = true;
+        b_frame.header.prev_vdr_rpu_id = p_frame.rpu_data_mapping.as_ref().unwrap().vdr_rpu_id;
+        let b_frame = DoviRpu::parse_unspec62_nalu(&b_frame.write_hevc_unspec62_nalu()?)?;
+        assert!(b_frame.rpu_data_mapping.is_none());
+
+        let mut decoding_order = vec![i_frame.clone(), p_frame.clone(), b_frame.clone()];
+        assert!(PrevVdrRpuResolver::resolve_all(&mut decoding_order).is_empty());
+        assert_eq!(decoding_order[2].rpu_data_mapping, p_frame.rpu_data_mapping);
+
+        // Resolving in presentation order picks the I frame's mapping instead
+        let mut presentation_order = vec![i_frame.clone(), b_frame, p_frame];
+        assert!(PrevVdrRpuResolver::resolve_all(&mut presentation_order).is_empty());
+        assert_eq!(
+            presentation_order[1].rpu_data_mapping,
+            i_frame.rpu_data_mapping
+        );
+
+        // So different mappings are never shared, whatever the order
+        assert!(PrevVdrRpuResolver::share_identical_mappings(decoding_order.iter_mut()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn detach() -> Result<()> {
+        let mut rpus = parse_rpu_file(assets_path().join("regular_rpu_mel.bin"))?;
+        PrevVdrRpuResolver::share_identical_mappings(rpus.iter_mut())?;
+
+        let parsed = rpus
+            .iter()
+            .map(|e| DoviRpu::parse_unspec62_nalu(&e.write_hevc_unspec62_nalu()?))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut rpu = parsed[2].clone();
+        PrevVdrRpuResolver::detach(&parsed[..2], &mut rpu)?;
+        assert!(!rpu.header.use_prev_vdr_rpu_flag);
+
+        // The mapping is written, so the RPU can be parsed alone
+        let rpu = DoviRpu::parse_unspec62_nalu(&rpu.write_hevc_unspec62_nalu()?)?;
+        assert_eq!(rpu.rpu_data_mapping, rpus[2].rpu_data_mapping);
+
+        let mut rpu = parsed[2].clone();
+        assert!(PrevVdrRpuResolver::detach(&parsed[1..2], &mut rpu).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn share_error_frame_index() -> Result<()> {
+        let mut rpus = parse_rpu_file(assets_path().join("regular_rpu_mel.bin"))?;
+
+        // Skipped, as it has no mapping
+        rpus[0].header.rpu_type = 0;
+        rpus[5].convert_with_mode(ConversionMode::To81MappingPreserved)?;
+
+        let err = PrevVdrRpuResolver::share_identical_mappings(rpus.iter_mut()).unwrap_err();
+        assert!(err.to_string().starts_with("The mapping of RPU 5 differs"));
+
+        Ok(())
+    }
+}
@@ -1,13 +1,14 @@
 use anyhow::{Result, bail, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::error::DoviError;
+use crate::rpu::trace::RpuBitReader;
 
 #[derive(Default, Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RpuDataHeader {
     /// Must be 25
     #[deprecated(
@@ -54,16 +55,19 @@ pub struct RpuDataHeader {
 }
 
 impl RpuDataHeader {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<RpuDataHeader> {
-        let rpu_type = reader.read::<6, u8>()?;
-        ensure!(rpu_type == 2);
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<RpuDataHeader> {
+        let rpu_type = reader.read::<6, u8>("rpu_type")?;
+        ensure!(
+            rpu_type == 2,
+            DoviError::InvalidHeader(format!("rpu_type should be 2, got {rpu_type}"))
+        );
 
-        let rpu_format = reader.read::<11, u16>()?;
+        let rpu_format = reader.read::<11, u16>("rpu_format")?;
 
-        let vdr_rpu_profile = reader.read::<4, u8>()?;
-        let vdr_rpu_level = reader.read::<4, u8>()?;
+        let vdr_rpu_profile = reader.read::<4, u8>("vdr_rpu_profile")?;
+        let vdr_rpu_level = reader.read::<4, u8>("vdr_rpu_level")?;
 
-        let vdr_seq_info_present_flag = reader.read_bit()?;
+        let vdr_seq_info_present_flag = reader.read_bit("vdr_seq_info_present_flag")?;
 
         let mut header = RpuDataHeader {
             rpu_type,
@@ -75,20 +79,21 @@ impl RpuDataHeader {
         };
 
         if vdr_seq_info_present_flag {
-            header.chroma_resampling_explicit_filter_flag = reader.read_bit()?;
-            header.coefficient_data_type = reader.read::<2, u8>()?;
+            header.chroma_resampling_explicit_filter_flag =
+                reader.read_bit("chroma_resampling_explicit_filter_flag")?;
+            header.coefficient_data_type = reader.read::<2, u8>("coefficient_data_type")?;
 
             if header.coefficient_data_type == 0 {
-                header.coefficient_log2_denom = reader.read_ue()?;
+                header.coefficient_log2_denom = reader.read_ue("coefficient_log2_denom")?;
             }
 
-            header.vdr_rpu_normalized_idc = reader.read::<2, u8>()?;
-            header.bl_video_full_range_flag = reader.read_bit()?;
+            header.vdr_rpu_normalized_idc = reader.read::<2, u8>("vdr_rpu_normalized_idc")?;
+            header.bl_video_full_range_flag = reader.read_bit("bl_video_full_range_flag")?;
 
             if header.rpu_format & 0x700 == 0 {
-                header.bl_bit_depth_minus8 = reader.read_ue()?;
+                header.bl_bit_depth_minus8 = reader.read_ue("bl_bit_depth_minus8")?;
 
-                let el_bit_depth_minus8 = reader.read_ue()?;
+                let el_bit_depth_minus8 = reader.read_ue("el_bit_depth_minus8")?;
                 // 8 lowest bits
                 header.el_bit_depth_minus8 = el_bit_depth_minus8 & 0xFF;
 
@@ -98,11 +103,13 @@ impl RpuDataHeader {
                 header.ext_mapping_idc_0_4 = ext_mapping_idc & 0x1F;
                 header.ext_mapping_idc_5_7 = ext_mapping_idc >> 5;
 
-                header.vdr_bit_depth_minus8 = reader.read_ue()?;
-                header.spatial_resampling_filter_flag = reader.read_bit()?;
-                header.reserved_zero_3bits = reader.read::<3, u8>()?;
-                header.el_spatial_resampling_filter_flag = reader.read_bit()?;
-                header.disable_residual_flag = reader.read_bit()?;
+                header.vdr_bit_depth_minus8 = reader.read_ue("vdr_bit_depth_minus8")?;
+                header.spatial_resampling_filter_flag =
+                    reader.read_bit("spatial_resampling_filter_flag")?;
+                header.reserved_zero_3bits = reader.read::<3, u8>("reserved_zero_3bits")?;
+                header.el_spatial_resampling_filter_flag =
+                    reader.read_bit("el_spatial_resampling_filter_flag")?;
+                header.disable_residual_flag = reader.read_bit("disable_residual_flag")?;
             }
 
             header.coefficient_log2_denom_length = if header.coefficient_data_type == 0 {
@@ -110,18 +117,18 @@ impl RpuDataHeader {
             } else if header.coefficient_data_type == 1 {
                 32
             } else {
-                bail!(
+                bail!(DoviError::InvalidHeader(format!(
                     "Invalid coefficient_data_type value: {}",
                     header.coefficient_data_type
-                );
+                )));
             };
         }
 
-        header.vdr_dm_metadata_present_flag = reader.read_bit()?;
+        header.vdr_dm_metadata_present_flag = reader.read_bit("vdr_dm_metadata_present_flag")?;
 
-        header.use_prev_vdr_rpu_flag = reader.read_bit()?;
+        header.use_prev_vdr_rpu_flag = reader.read_bit("use_prev_vdr_rpu_flag")?;
         if header.use_prev_vdr_rpu_flag {
-            header.prev_vdr_rpu_id = reader.read_ue()?;
+            header.prev_vdr_rpu_id = reader.read_ue("prev_vdr_rpu_id")?;
         }
 
         Ok(header)
@@ -132,44 +139,59 @@ impl RpuDataHeader {
             5 => {
                 ensure!(
                     self.vdr_rpu_profile == 0,
-                    "profile 5: vdr_rpu_profile should be 0"
+                    DoviError::InvalidProfile {
+                        profile,
+                        reason: "vdr_rpu_profile should be 0".into(),
+                    }
                 );
                 ensure!(
                     self.bl_video_full_range_flag,
-                    "profile 5: bl_video_full_range_flag should be true"
+                    DoviError::InvalidProfile {
+                        profile,
+                        reason: "bl_video_full_range_flag should be true".into(),
+                    }
                 );
             }
             7 => {
                 ensure!(
                     self.vdr_rpu_profile == 1,
-                    "profile 7: vdr_rpu_profile should be 1"
+                    DoviError::InvalidProfile {
+                        profile,
+                        reason: "vdr_rpu_profile should be 1".into(),
+                    }
                 );
             }
             8 => {
                 ensure!(
                     self.vdr_rpu_profile == 1,
-                    "profile 8: vdr_rpu_profile should be 1"
+                    DoviError::InvalidProfile {
+                        profile,
+                        reason: "vdr_rpu_profile should be 1".into(),
+                    }
                 );
             }
             _ => (),
         };
 
-        ensure!(self.vdr_rpu_level == 0, "vdr_rpu_level should be 0");
+        ensure!(
+            self.vdr_rpu_level == 0,
+            DoviError::InvalidHeader("vdr_rpu_level should be 0".into())
+        );
         ensure!(
             self.bl_bit_depth_minus8 == 2,
-            "bl_bit_depth_minus8 should be 2"
+            DoviError::InvalidHeader("bl_bit_depth_minus8 should be 2".into())
         );
         ensure!(
             self.el_bit_depth_minus8 == 2,
-            "el_bit_depth_minus8 should be 2"
+            DoviError::InvalidHeader("el_bit_depth_minus8 should be 2".into())
         );
         ensure!(
             self.vdr_bit_depth_minus8 <= 6,
-            "vdr_bit_depth_minus8 should be <= 6"
+            DoviError::InvalidHeader("vdr_bit_depth_minus8 should be <= 6".into())
         );
         ensure!(
             self.coefficient_log2_denom <= 23,
-            "coefficient_log2_denom should be <= 23"
+            DoviError::InvalidHeader("coefficient_log2_denom should be <= 23".into())
         );
 
         Ok(())
@@ -1,13 +1,12 @@
-use anyhow::{Result, bail, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use anyhow::{Context, Result, bail, ensure};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tinyvec::{ArrayVec, array_vec};
 
 use crate::rpu::MMR_MAX_COEFFS;
+use crate::rpu::trace::RpuBitReader;
 
 use super::rpu_data_header::RpuDataHeader;
 use super::rpu_data_nlq::{DoviELType, RpuDataNlq};
@@ -15,7 +14,7 @@ use super::rpu_data_nlq::{DoviELType, RpuDataNlq};
 use super::{NLQ_NUM_PIVOTS, NUM_COMPONENTS};
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum DoviMappingMethod {
     /// Not a valid value, placeholder for Default
     #[default]
@@ -26,13 +25,13 @@ pub enum DoviMappingMethod {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum DoviNlqMethod {
     LinearDeadzone = 0,
 }
 
-#[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RpuDataMapping {
     // [0, 15]
     pub vdr_rpu_id: u64,
@@ -55,8 +54,8 @@ pub struct RpuDataMapping {
     pub nlq: Option<RpuDataNlq>,
 }
 
-#[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct DoviReshapingCurve {
     // [2, 9]
     pub num_pivots_minus2: u64,
@@ -78,8 +77,8 @@ pub struct DoviReshapingCurve {
     pub mmr: Option<DoviMMRCurve>,
 }
 
-#[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct DoviPolynomialCurve {
     pub poly_order_minus1: Vec<u64>,
     pub linear_interp_flag: Vec<bool>,
@@ -87,8 +86,8 @@ pub struct DoviPolynomialCurve {
     pub poly_coef: Vec<ArrayVec<[u64; 3]>>,
 }
 
-#[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct DoviMMRCurve {
     pub mmr_order_minus1: Vec<u8>,
     pub mmr_constant_int: Vec<i64>,
@@ -99,13 +98,13 @@ pub struct DoviMMRCurve {
 
 impl RpuDataMapping {
     pub(crate) fn parse(
-        reader: &mut BsIoSliceReader,
+        reader: &mut RpuBitReader,
         header: &RpuDataHeader,
     ) -> Result<RpuDataMapping> {
         let mut mapping = RpuDataMapping {
-            vdr_rpu_id: reader.read_ue()?,
-            mapping_color_space: reader.read_ue()?,
-            mapping_chroma_format_idc: reader.read_ue()?,
+            vdr_rpu_id: reader.read_ue("vdr_rpu_id")?,
+            mapping_color_space: reader.read_ue("mapping_color_space")?,
+            mapping_chroma_format_idc: reader.read_ue("mapping_chroma_format_idc")?,
             ..Default::default()
         };
 
@@ -114,19 +113,21 @@ impl RpuDataMapping {
         for cmp in 0..NUM_COMPONENTS {
             let curve = &mut mapping.curves[cmp];
 
-            curve.num_pivots_minus2 = reader.read_ue()?;
+            reader.begin_with(|| format!("cmp[{cmp}]"));
+            curve.num_pivots_minus2 = reader.read_ue("num_pivots_minus2")?;
             let num_pivots = (curve.num_pivots_minus2 + 2) as usize;
 
             curve.pivots = vec![0; num_pivots];
 
             for i in 0..num_pivots {
-                curve.pivots[i] = reader.read_var(bl_bit_depth)?;
+                curve.pivots[i] = reader.read_var(bl_bit_depth, "pred_pivot_value")?;
             }
+            reader.end();
         }
 
         // Profile 7 only
         if header.rpu_format & 0x700 == 0 && !header.disable_residual_flag {
-            let nlq_method_idc = reader.read::<3, u8>()?;
+            let nlq_method_idc = reader.read::<3, u8>("nlq_method_idc")?;
             ensure!(nlq_method_idc == 0);
 
             mapping.nlq_method_idc = Some(DoviNlqMethod::from(nlq_method_idc));
@@ -134,14 +135,14 @@ impl RpuDataMapping {
 
             let mut nlq_pred_pivot_value = [0; NLQ_NUM_PIVOTS];
             for pv in &mut nlq_pred_pivot_value {
-                *pv = reader.read_var(bl_bit_depth)?;
+                *pv = reader.read_var(bl_bit_depth, "nlq_pred_pivot_value")?;
             }
 
             mapping.nlq_pred_pivot_value = Some(nlq_pred_pivot_value);
         }
 
-        mapping.num_x_partitions_minus1 = reader.read_ue()?;
-        mapping.num_y_partitions_minus1 = reader.read_ue()?;
+        mapping.num_x_partitions_minus1 = reader.read_ue("num_x_partitions_minus1")?;
+        mapping.num_y_partitions_minus1 = reader.read_ue("num_y_partitions_minus1")?;
 
         // rpu_data_mapping_param
 
@@ -149,8 +150,9 @@ impl RpuDataMapping {
             let curve = &mut mapping.curves[cmp];
             let num_pieces = (curve.num_pivots_minus2 + 1) as usize;
 
-            for _ in 0..num_pieces {
-                let mapping_idc = DoviMappingMethod::try_from(reader.read_ue()?)?;
+            for pivot_idx in 0..num_pieces {
+                reader.begin_with(|| format!("cmp[{cmp}] pivot_idx[{pivot_idx}]"));
+                let mapping_idc = DoviMappingMethod::try_from(reader.read_ue("mapping_idc")?)?;
                 curve.mapping_idc = mapping_idc;
 
                 // MAPPING_POLYNOMIAL
@@ -167,11 +169,14 @@ impl RpuDataMapping {
 
                     mmr_curve.parse(reader, header)?;
                 }
+                reader.end();
             }
         }
 
         if mapping.nlq_method_idc.is_some() {
+            reader.begin("rpu_data_nlq");
             mapping.nlq = Some(RpuDataNlq::parse(reader, header, &mapping)?);
+            reader.end();
         }
 
         Ok(mapping)
@@ -362,6 +367,44 @@ impl RpuDataMapping {
         Ok(())
     }
 
+    /// Validates the curves and NLQ params against the header, as they are written.
+    /// Parsed mappings are always consistent, but deserialized or edited ones may not be.
+    pub fn validate_structure(&self, header: &RpuDataHeader) -> Result<()> {
+        let with_int_coefs = header.coefficient_data_type == 0;
+
+        for (cmp, curve) in self.curves.iter().enumerate() {
+            curve
+                .validate(with_int_coefs)
+                .with_context(|| format!("cmp[{cmp}]"))?;
+        }
+
+        if header.rpu_format & 0x700 == 0 && !header.disable_residual_flag {
+            ensure!(
+                self.nlq_method_idc.is_some(),
+                "nlq_method_idc should be defined when the residual is enabled"
+            );
+            ensure!(
+                self.nlq_num_pivots_minus2 == Some(0),
+                "nlq_num_pivots_minus2 should be 0 when the residual is enabled"
+            );
+            ensure!(
+                self.nlq_pred_pivot_value.is_some(),
+                "nlq_pred_pivot_value should be defined when the residual is enabled"
+            );
+            ensure!(
+                self.nlq.is_some(),
+                "nlq should be defined when the residual is enabled"
+            );
+        } else {
+            ensure!(
+                self.nlq_method_idc.is_none() && self.nlq.is_none(),
+                "NLQ params should be undefined when the residual is disabled"
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn set_empty_p81_mapping(&mut self) {
         self.curves.iter_mut().for_each(|curve| {
             curve.num_pivots_minus2 = 0;
@@ -385,6 +428,31 @@ impl RpuDataMapping {
     }
 }
 
+impl DoviReshapingCurve {
+    fn validate(&self, with_int_coefs: bool) -> Result<()> {
+        let num_pieces = (self.num_pivots_minus2 + 1) as usize;
+
+        ensure!(
+            self.pivots.len() == num_pieces + 1,
+            "expected {} pivots, got {}",
+            num_pieces + 1,
+            self.pivots.len()
+        );
+
+        match (self.mapping_idc, &self.polynomial, &self.mmr) {
+            (DoviMappingMethod::Polynomial, Some(poly_curve), None) => {
+                poly_curve.validate(num_pieces, with_int_coefs)
+            }
+            (DoviMappingMethod::MMR, None, Some(mmr_curve)) => {
+                mmr_curve.validate(num_pieces, with_int_coefs)
+            }
+            (mapping_idc, _, _) => {
+                bail!("mapping_idc {mapping_idc:?} does not match the curve params")
+            }
+        }
+    }
+}
+
 impl DoviPolynomialCurve {
     fn new(num_pieces: usize) -> Self {
         DoviPolynomialCurve {
@@ -395,16 +463,16 @@ impl DoviPolynomialCurve {
         }
     }
 
-    fn parse(&mut self, reader: &mut BsIoSliceReader, header: &RpuDataHeader) -> Result<()> {
+    fn parse(&mut self, reader: &mut RpuBitReader, header: &RpuDataHeader) -> Result<()> {
         let coefficient_log2_denom_length = header.coefficient_log2_denom_length;
 
-        let poly_order_minus1 = reader.read_ue()?;
+        let poly_order_minus1 = reader.read_ue("poly_order_minus1")?;
         ensure!(poly_order_minus1 <= 1);
 
         self.poly_order_minus1.push(poly_order_minus1);
 
         let linear_interp_flag = if poly_order_minus1 == 0 {
-            reader.read_bit()?
+            reader.read_bit("linear_interp_flag")?
         } else {
             false
         };
@@ -437,10 +505,10 @@ impl DoviPolynomialCurve {
 
             for _j in 0..poly_coef_count {
                 if header.coefficient_data_type == 0 {
-                    poly_coef_int.push(reader.read_se()?);
+                    poly_coef_int.push(reader.read_se("poly_coef_int")?);
                 }
 
-                poly_coef.push(reader.read_var(coefficient_log2_denom_length)?);
+                poly_coef.push(reader.read_var(coefficient_log2_denom_length, "poly_coef")?);
             }
 
             self.poly_coef_int.push(poly_coef_int);
@@ -450,6 +518,42 @@ impl DoviPolynomialCurve {
         Ok(())
     }
 
+    fn validate(&self, num_pieces: usize, with_int_coefs: bool) -> Result<()> {
+        ensure!(
+            self.poly_order_minus1.len() == num_pieces && self.poly_coef.len() == num_pieces,
+            "polynomial params should have {num_pieces} pieces"
+        );
+        ensure!(
+            !with_int_coefs || self.poly_coef_int.len() == num_pieces,
+            "poly_coef_int should have {num_pieces} pieces"
+        );
+
+        for i in 0..num_pieces {
+            let poly_order_minus1 = self.poly_order_minus1[i];
+            ensure!(poly_order_minus1 <= 1, "poly_order_minus1 should be <= 1");
+
+            // Only written for first order polynomials
+            if poly_order_minus1 == 0 {
+                ensure!(
+                    self.linear_interp_flag.get(i) == Some(&false),
+                    "linear_interp_flag[{i}] should be false, interpolation is not supported"
+                );
+            }
+
+            let poly_coef_count = poly_order_minus1 as usize + 2;
+            ensure!(
+                self.poly_coef[i].len() == poly_coef_count,
+                "poly_coef[{i}] should have {poly_coef_count} coefficients"
+            );
+            ensure!(
+                !with_int_coefs || self.poly_coef_int[i].len() == poly_coef_count,
+                "poly_coef_int[{i}] should have {poly_coef_count} coefficients"
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn p81_default() -> Self {
         let mut poly_curve = Self::new(1);
         poly_curve.set_p81_params();
@@ -483,10 +587,10 @@ impl DoviMMRCurve {
         }
     }
 
-    fn parse(&mut self, reader: &mut BsIoSliceReader, header: &RpuDataHeader) -> Result<()> {
+    fn parse(&mut self, reader: &mut RpuBitReader, header: &RpuDataHeader) -> Result<()> {
         let coefficient_log2_denom_length = header.coefficient_log2_denom_length;
 
-        let mmr_order_minus1 = reader.read::<2, u8>()?;
+        let mmr_order_minus1 = reader.read::<2, u8>("mmr_order_minus1")?;
         ensure!(mmr_order_minus1 <= 2);
 
         self.mmr_order_minus1.push(mmr_order_minus1);
@@ -494,10 +598,11 @@ impl DoviMMRCurve {
         let mmr_orders_count = mmr_order_minus1 as usize + 1;
 
         if header.coefficient_data_type == 0 {
-            self.mmr_constant_int.push(reader.read_se()?);
+            self.mmr_constant_int
+                .push(reader.read_se("mmr_constant_int")?);
         }
         self.mmr_constant
-            .push(reader.read_var(coefficient_log2_denom_length)?);
+            .push(reader.read_var(coefficient_log2_denom_length, "mmr_constant")?);
 
         let mut mmr_coef_int = array_vec!();
         let mut mmr_coef = array_vec!();
@@ -508,10 +613,10 @@ impl DoviMMRCurve {
 
             for _k in 0..MMR_MAX_COEFFS {
                 if header.coefficient_data_type == 0 {
-                    mmr_coef_int2.push(reader.read_se()?);
+                    mmr_coef_int2.push(reader.read_se("mmr_coef_int")?);
                 }
 
-                mmr_coef2.push(reader.read_var(coefficient_log2_denom_length)?);
+                mmr_coef2.push(reader.read_var(coefficient_log2_denom_length, "mmr_coef")?);
             }
 
             mmr_coef_int.push(mmr_coef_int2);
@@ -523,6 +628,43 @@ impl DoviMMRCurve {
 
         Ok(())
     }
+
+    fn validate(&self, num_pieces: usize, with_int_coefs: bool) -> Result<()> {
+        ensure!(
+            self.mmr_order_minus1.len() == num_pieces
+                && self.mmr_constant.len() == num_pieces
+                && self.mmr_coef.len() == num_pieces,
+            "MMR params should have {num_pieces} pieces"
+        );
+        ensure!(
+            !with_int_coefs
+                || (self.mmr_constant_int.len() == num_pieces
+                    && self.mmr_coef_int.len() == num_pieces),
+            "MMR int params should have {num_pieces} pieces"
+        );
+
+        for i in 0..num_pieces {
+            let mmr_order_minus1 = self.mmr_order_minus1[i];
+            ensure!(mmr_order_minus1 <= 2, "mmr_order_minus1 should be <= 2");
+
+            let mmr_orders_count = mmr_order_minus1 as usize + 1;
+            ensure!(
+                self.mmr_coef[i].len() == mmr_orders_count
+                    && self.mmr_coef[i].iter().all(|c| c.len() == MMR_MAX_COEFFS),
+                "mmr_coef[{i}] should have {mmr_orders_count} orders of {MMR_MAX_COEFFS} coefficients"
+            );
+            ensure!(
+                !with_int_coefs
+                    || (self.mmr_coef_int[i].len() == mmr_orders_count
+                        && self.mmr_coef_int[i]
+                            .iter()
+                            .all(|c| c.len() == MMR_MAX_COEFFS)),
+                "mmr_coef_int[{i}] should have {mmr_orders_count} orders of {MMR_MAX_COEFFS} coefficients"
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<u64> for DoviMappingMethod {
@@ -1,12 +1,12 @@
 use std::fmt::Display;
 
 use anyhow::{Result, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::rpu::trace::RpuBitReader;
 
 use super::rpu_data_header::RpuDataHeader;
 use super::rpu_data_mapping::{DoviNlqMethod, RpuDataMapping};
@@ -17,14 +17,14 @@ const FEL_STR: &str = "FEL";
 const MEL_STR: &str = "MEL";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum DoviELType {
     MEL,
     FEL,
 }
 
-#[derive(Debug, Default, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RpuDataNlq {
     // [0, 512]
     pub nlq_offset: [u16; NUM_COMPONENTS],
@@ -38,7 +38,7 @@ pub struct RpuDataNlq {
 
 impl RpuDataNlq {
     pub(crate) fn parse(
-        reader: &mut BsIoSliceReader,
+        reader: &mut RpuBitReader,
         header: &RpuDataHeader,
         mapping: &RpuDataMapping,
     ) -> Result<RpuDataNlq> {
@@ -56,33 +56,39 @@ impl RpuDataNlq {
 
         for cmp in 0..NUM_COMPONENTS {
             // rpu_data_nlq_param
+            reader.begin_with(|| format!("cmp[{cmp}]"));
 
-            data.nlq_offset[cmp] = reader.read_var((header.el_bit_depth_minus8 + 8) as u32)?;
+            data.nlq_offset[cmp] =
+                reader.read_var((header.el_bit_depth_minus8 + 8) as u32, "nlq_offset")?;
 
             if header.coefficient_data_type == 0 {
-                data.vdr_in_max_int[cmp] = reader.read_ue()?;
+                data.vdr_in_max_int[cmp] = reader.read_ue("vdr_in_max_int")?;
             }
 
-            data.vdr_in_max[cmp] = reader.read_var(coefficient_log2_denom_length)?;
+            data.vdr_in_max[cmp] = reader.read_var(coefficient_log2_denom_length, "vdr_in_max")?;
 
             // NLQ_LINEAR_DZ
             if let Some(nlq_method_idc) = mapping.nlq_method_idc {
                 if nlq_method_idc == DoviNlqMethod::LinearDeadzone {
                     if header.coefficient_data_type == 0 {
-                        data.linear_deadzone_slope_int[cmp] = reader.read_ue()?;
+                        data.linear_deadzone_slope_int[cmp] =
+                            reader.read_ue("linear_deadzone_slope_int")?;
                     }
 
                     data.linear_deadzone_slope[cmp] =
-                        reader.read_var(coefficient_log2_denom_length)?;
+                        reader.read_var(coefficient_log2_denom_length, "linear_deadzone_slope")?;
 
                     if header.coefficient_data_type == 0 {
-                        data.linear_deadzone_threshold_int[cmp] = reader.read_ue()?;
+                        data.linear_deadzone_threshold_int[cmp] =
+                            reader.read_ue("linear_deadzone_threshold_int")?;
                     }
 
-                    data.linear_deadzone_threshold[cmp] =
-                        reader.read_var(coefficient_log2_denom_length)?;
+                    data.linear_deadzone_threshold[cmp] = reader
+                        .read_var(coefficient_log2_denom_length, "linear_deadzone_threshold")?;
                 }
             }
+
+            reader.end();
         }
 
         Ok(data)
@@ -212,7 +218,10 @@ impl Display for DoviELType {
 mod tests {
     use anyhow::Result;
 
-    use crate::rpu::{dovi_rpu::DoviRpu, generate::GenerateConfig};
+    use crate::rpu::{
+        dovi_rpu::{DoviRpu, RpuParseOpts},
+        generate::GenerateConfig,
+    };
 
     #[test]
     fn write_linear_dz_threshold() -> Result<()> {
@@ -229,7 +238,7 @@ mod tests {
         }
 
         let out = rpu.write_rpu()?;
-        let rpu = DoviRpu::parse(&out)?;
+        let rpu = DoviRpu::parse(&out, RpuParseOpts::default())?;
 
         let nlq = rpu.rpu_data_mapping.and_then(|e| e.nlq).unwrap();
         assert_eq!(nlq.linear_deadzone_threshold_int, [1, 2, 3]);
@@ -0,0 +1,491 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Bit-level trace of the syntax elements read when parsing an RPU.
+///
+/// Offsets are in bits, from the start of the RPU data (the `rpu_nal_prefix` byte),
+/// after the emulation prevention bytes were removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RpuTrace {
+    pub nodes: Vec<TraceNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum TraceNode {
+    Element(TraceElement),
+    Section(TraceSection),
+}
+
+/// Single syntax element
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TraceElement {
+    pub name: Cow<'static, str>,
+    pub offset: u64,
+    pub width: u64,
+    pub descriptor: TraceDescriptor,
+    pub value: TraceValue,
+}
+
+/// Group of syntax elements, such as a syntax structure or a loop iteration
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TraceSection {
+    pub name: Cow<'static, str>,
+    pub offset: u64,
+    pub width: u64,
+    pub nodes: Vec<TraceNode>,
+}
+
+/// Syntax element descriptor, as used in the specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum TraceDescriptor {
+    /// Unsigned integer, `u(n)`
+    U,
+    /// Signed integer, `i(n)`
+    I,
+    /// Unsigned Exp-Golomb, `ue(v)`
+    Ue,
+    /// Signed Exp-Golomb, `se(v)`
+    Se,
+    /// Bytes, `b(8)`
+    B,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum TraceValue {
+    Unsigned(u64),
+    Signed(i64),
+    Bytes(Vec<u8>),
+}
+
+/// Bitstream reader used to parse the RPU syntax elements.
+///
+/// When tracing, every element read is recorded with its name, offset and width.
+pub struct RpuBitReader<'a> {
+    reader: BsIoSliceReader<'a>,
+
+    /// Open sections, the first one being the root
+    sections: Option<Vec<TraceSection>>,
+}
+
+/// Integer types that can be read and traced
+pub(crate) trait TraceInt: Sized + Copy {
+    const DESCRIPTOR: TraceDescriptor;
+
+    fn read_const<const BITS: u32>(reader: &mut BsIoSliceReader) -> io::Result<Self>;
+    fn read_var(reader: &mut BsIoSliceReader, bits: u32) -> io::Result<Self>;
+    fn trace_value(self) -> TraceValue;
+}
+
+macro_rules! impl_trace_int {
+    ($descriptor:ident, $variant:ident, $cast:ty, $($t:ty),+) => {
+        $(
+            impl TraceInt for $t {
+                const DESCRIPTOR: TraceDescriptor = TraceDescriptor::$descriptor;
+
+                #[inline(always)]
+                fn read_const<const BITS: u32>(reader: &mut BsIoSliceReader) -> io::Result<Self> {
+                    reader.read::<BITS, $t>()
+                }
+
+                #[inline(always)]
+                fn read_var(reader: &mut BsIoSliceReader, bits: u32) -> io::Result<Self> {
+                    reader.read_var(bits)
+                }
+
+                fn trace_value(self) -> TraceValue {
+                    TraceValue::$variant(self as $cast)
+                }
+            }
+        )+
+    };
+}
+
+impl_trace_int!(U, Unsigned, u64, u8, u16, u32, u64);
+impl_trace_int!(I, Signed, i64, i8, i16, i32, i64);
+
+impl<'a> RpuBitReader<'a> {
+    pub(crate) fn new(data: &'a [u8], trace: bool) -> Self {
+        let sections = trace.then(|| {
+            vec![TraceSection {
+                name: Cow::Borrowed(""),
+                offset: 0,
+                width: 0,
+                nodes: Vec::new(),
+            }]
+        });
+
+        Self {
+            reader: BsIoSliceReader::from_slice(data),
+            sections,
+        }
+    }
+
+    /// Closes the sections left open by a parsing error
+    pub(crate) fn into_trace(mut self) -> RpuTrace {
+        let Some(sections) = self.sections.as_ref() else {
+            return RpuTrace::default();
+        };
+
+        for _ in 1..sections.len() {
+            self.end();
+        }
+
+        let root = self.sections.and_then(|mut s| s.pop()).unwrap();
+
+        RpuTrace { nodes: root.nodes }
+    }
+
+    #[inline(always)]
+    pub(crate) fn read<const BITS: u32, I: TraceInt>(
+        &mut self,
+        name: &'static str,
+    ) -> io::Result<I> {
+        let offset = self.offset();
+        let value = I::read_const::<BITS>(&mut self.reader)?;
+
+        self.trace_element(name, offset, I::DESCRIPTOR, || value.trace_value());
+
+        Ok(value)
+    }
+
+    #[inline(always)]
+    pub(crate) fn read_var<I: TraceInt>(&mut self, bits: u32, name: &'static str) -> io::Result<I> {
+        let offset = self.offset();
+        let value = I::read_var(&mut self.reader, bits)?;
+
+        self.trace_element(name, offset, I::DESCRIPTOR, || value.trace_value());
+
+        Ok(value)
+    }
+
+    #[inline(always)]
+    pub(crate) fn read_bit(&mut self, name: &'static str) -> io::Result<bool> {
+        let offset = self.offset();
+        let value = self.reader.read_bit()?;
+
+        self.trace_element(name, offset, TraceDescriptor::U, || {
+            TraceValue::Unsigned(value as u64)
+        });
+
+        Ok(value)
+    }
+
+    #[inline(always)]
+    pub(crate) fn read_ue(&mut self, name: &'static str) -> io::Result<u64> {
+        let offset = self.offset();
+        let value = self.reader.read_ue()?;
+
+        self.trace_element(name, offset, TraceDescriptor::Ue, || {
+            TraceValue::Unsigned(value)
+        });
+
+        Ok(value)
+    }
+
+    #[inline(always)]
+    pub(crate) fn read_se(&mut self, name: &'static str) -> io::Result<i64> {
+        let offset = self.offset();
+        let value = self.reader.read_se()?;
+
+        self.trace_element(name, offset, TraceDescriptor::Se, || {
+            TraceValue::Signed(value)
+        });
+
+        Ok(value)
+    }
+
+    pub(crate) fn read_bytes(&mut self, buf: &mut [u8], name: &'static str) -> io::Result<()> {
+        let offset = self.offset();
+        self.reader.read_bytes(buf)?;
+
+        self.trace_element(name, offset, TraceDescriptor::B, || {
+            TraceValue::Bytes(buf.to_vec())
+        });
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(crate) fn available(&mut self) -> io::Result<u64> {
+        self.reader.available()
+    }
+
+    #[inline(always)]
+    pub(crate) fn byte_aligned(&self) -> bool {
+        self.reader.byte_aligned()
+    }
+
+    /// Opens a section, closed by `end`
+    #[inline(always)]
+    pub(crate) fn begin(&mut self, name: &'static str) {
+        self.begin_with(|| Cow::Borrowed(name));
+    }
+
+    /// Opens a section with a formatted name, only formatted when tracing
+    #[inline(always)]
+    pub(crate) fn begin_with<N: Into<Cow<'static, str>>>(&mut self, name: impl FnOnce() -> N) {
+        if self.sections.is_some() {
+            let offset = self.offset();
+
+            if let Some(sections) = self.sections.as_mut() {
+                sections.push(TraceSection {
+                    name: name().into(),
+                    offset,
+                    width: 0,
+                    nodes: Vec::new(),
+                });
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn end(&mut self) {
+        if self.sections.is_some() {
+            let offset = self.offset();
+
+            if let Some(sections) = self.sections.as_mut()
+                && sections.len() > 1
+            {
+                let mut section = sections.pop().unwrap();
+                section.width = offset - section.offset;
+
+                sections
+                    .last_mut()
+                    .unwrap()
+                    .nodes
+                    .push(TraceNode::Section(section));
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn offset(&mut self) -> u64 {
+        if self.sections.is_some() {
+            self.reader.position_in_bits().unwrap_or_default()
+        } else {
+            0
+        }
+    }
+
+    #[inline(always)]
+    fn trace_element(
+        &mut self,
+        name: &'static str,
+        offset: u64,
+        descriptor: TraceDescriptor,
+        value: impl FnOnce() -> TraceValue,
+    ) {
+        if self.sections.is_some() {
+            let width = self.offset() - offset;
+
+            if let Some(section) = self.sections.as_mut().and_then(|s| s.last_mut()) {
+                section.nodes.push(TraceNode::Element(TraceElement {
+                    name: Cow::Borrowed(name),
+                    offset,
+                    width,
+                    descriptor,
+                    value: value(),
+                }));
+            }
+        }
+    }
+}
+
+impl RpuTrace {
+    /// Iterates over all the elements, depth first
+    pub fn elements(&self) -> impl Iterator<Item = &TraceElement> {
+        let mut stack: Vec<std::slice::Iter<TraceNode>> = vec![self.nodes.iter()];
+
+        std::iter::from_fn(move || {
+            while let Some(iter) = stack.last_mut() {
+                match iter.next() {
+                    Some(TraceNode::Element(element)) => return Some(element),
+                    Some(TraceNode::Section(section)) => stack.push(section.nodes.iter()),
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+
+            None
+        })
+    }
+
+    fn fmt_nodes(f: &mut fmt::Formatter<'_>, nodes: &[TraceNode], depth: usize) -> fmt::Result {
+        let indent = depth * 2;
+
+        for node in nodes {
+            match node {
+                TraceNode::Element(e) => {
+                    let descriptor = e.descriptor.display(e.width);
+                    let name_width = 48usize.saturating_sub(indent);
+
+                    writeln!(
+                        f,
+                        "{:>6} {:<8} {:indent$}{:<name_width$} {}",
+                        e.offset, descriptor, "", e.name, e.value
+                    )?;
+                }
+                TraceNode::Section(s) => {
+                    writeln!(
+                        f,
+                        "{:>6} {:<8} {:indent$}{} ({} bits)",
+                        s.offset, "", "", s.name, s.width
+                    )?;
+
+                    Self::fmt_nodes(f, &s.nodes, depth + 1)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TraceDescriptor {
+    fn display(&self, width: u64) -> String {
+        match self {
+            TraceDescriptor::U => format!("u({width})"),
+            TraceDescriptor::I => format!("i({width})"),
+            TraceDescriptor::Ue => "ue(v)".to_string(),
+            TraceDescriptor::Se => "se(v)".to_string(),
+            TraceDescriptor::B => "b(8)".to_string(),
+        }
+    }
+}
+
+/// Tree of the elements, one per line: bit offset, descriptor, name and value
+impl fmt::Display for RpuTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Self::fmt_nodes(f, &self.nodes, 0)
+    }
+}
+
+impl fmt::Display for TraceValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceValue::Unsigned(v) => write!(f, "{v}"),
+            TraceValue::Signed(v) => write!(f, "{v}"),
+            TraceValue::Bytes(bytes) => {
+                for b in bytes {
+                    write!(f, "{b:02X}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    use anyhow::Result;
+
+    use super::{TraceDescriptor, TraceNode, TraceValue};
+    use crate::error::DoviError;
+    use crate::rpu::dovi_rpu::{DoviRpu, RpuParseOpts};
+    use crate::rpu::utils::{RpuReader, RpuReaderFormat};
+    use crate::utils::clear_start_code_emulation_prevention_3_byte;
+
+    fn first_nalu(file: &str) -> Result<Vec<u8>> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .unwrap()
+            .join("assets/tests")
+            .join(file);
+
+        RpuReader::new(File::open(path)?, RpuReaderFormat::Hevc)
+            .next_payload()
+            .unwrap()
+    }
+
+    #[test]
+    fn trace_covers_rpu() -> Result<()> {
+        for file in [
+            "fel_orig.bin",
+            "mel_orig.bin",
+            "profile8.bin",
+            "cmv40_full_rpu.bin",
+            "data_before_crc32.bin",
+        ] {
+            let data = first_nalu(file)?;
+            let (res, trace) = DoviRpu::trace_unspec62_nalu(&data, RpuParseOpts::default());
+            let rpu = res?;
+
+            // Every bit is traced once, in order
+            let mut end = 0;
+            for element in trace.elements() {
+                assert_eq!(element.offset, end, "{file}: {}", element.name);
+                end = element.offset + element.width;
+            }
+
+            let rpu_data = clear_start_code_emulation_prevention_3_byte(
+                DoviRpu::validated_trimmed_data(&data)?,
+            );
+            let rpu_len = rpu_data.len() - rpu_data.iter().rev().take_while(|b| **b == 0).count();
+            assert_eq!(end, rpu_len as u64 * 8, "{file}");
+
+            let crc32 = trace
+                .elements()
+                .find(|e| e.name == "rpu_data_crc32")
+                .unwrap();
+            assert_eq!(crc32.descriptor, TraceDescriptor::U);
+            assert_eq!(crc32.value, TraceValue::Unsigned(rpu.rpu_data_crc32 as u64));
+
+            let sections: Vec<_> = trace
+                .nodes
+                .iter()
+                .filter_map(|node| match node {
+                    TraceNode::Section(s) => Some(s.name.as_ref()),
+                    TraceNode::Element(_) => None,
+                })
+                .collect();
+            assert_eq!(sections[..2], ["rpu_data_header", "rpu_data_mapping"]);
+            assert!(sections.contains(&"vdr_dm_data_payload"));
+            assert_eq!(
+                sections.contains(&"remaining"),
+                rpu.remaining.is_some(),
+                "{file}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn trace_until_error() -> Result<()> {
+        let data = first_nalu("profile8.bin")?;
+        let mut truncated = data[..data.len() / 2].to_vec();
+        truncated.push(0x80);
+
+        let (res, trace) = DoviRpu::trace_unspec62_nalu(&truncated, RpuParseOpts::default());
+        assert_eq!(
+            DoviError::find(&res.unwrap_err()),
+            Some(&DoviError::Truncated)
+        );
+
+        let last = trace.elements().last().unwrap();
+        assert!(last.offset + last.width <= (truncated.len() as u64 - 4) * 8);
+        assert!(trace.to_string().contains("rpu_data_mapping"));
+
+        Ok(())
+    }
+}
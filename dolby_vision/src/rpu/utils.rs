@@ -4,9 +4,10 @@ use std::{
     path::Path,
 };
 
-use anyhow::{Result, bail};
+use anyhow::{Result, bail, ensure};
 
-use super::dovi_rpu::DoviRpu;
+use super::dovi_rpu::{DoviRpu, RpuParseOpts};
+use crate::error::DoviError;
 
 pub fn parse_rpu_file<P: AsRef<Path>>(input: P) -> Result<Vec<DoviRpu>> {
     let rpu_file = File::open(input)?;
@@ -135,3 +136,295 @@ pub fn parse_rpu_file<P: AsRef<Path>>(input: P) -> Result<Vec<DoviRpu>> {
         );
     }
 }
+
+/// Input format for `RpuReader`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RpuReaderFormat {
+    /// HEVC UNSPEC62 NALUs, each prefixed by a 4 bytes start code.
+    /// As written by `dovi_tool extract-rpu`.
+    #[default]
+    Hevc,
+    /// AV1 ITU-T T.35 metadata payloads, each prefixed by its size as a 32-bit big-endian integer
+    Av1ItuT35,
+}
+
+/// Streaming RPU reader, parsing one RPU at a time.
+///
+/// Invalid RPUs are returned as errors without stopping the iteration.
+/// Reading errors and truncated data end the iteration after returning the error.
+pub struct RpuReader<R: Read> {
+    reader: R,
+    format: RpuReaderFormat,
+    opts: RpuParseOpts,
+
+    buf: Vec<u8>,
+    /// Start of the unread data in `buf`, compacted when refilling
+    pos: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: Read> RpuReader<R> {
+    const CHUNK_SIZE: usize = 65536;
+    const START_CODE: &[u8] = &[0, 0, 0, 1];
+
+    pub fn new(reader: R, format: RpuReaderFormat) -> Self {
+        Self {
+            reader,
+            format,
+            opts: RpuParseOpts::default(),
+            buf: Vec::with_capacity(Self::CHUNK_SIZE),
+            pos: 0,
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Sets the options used to parse the RPUs, such as repair mode
+    pub fn with_opts(mut self, opts: RpuParseOpts) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    /// Unread data
+    fn data(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Returns the first `len` bytes of unread data, marking them as read
+    fn consume(&mut self, len: usize) -> &[u8] {
+        let start = self.pos;
+        self.pos += len;
+
+        &self.buf[start..self.pos]
+    }
+
+    /// Reads a chunk into the buffer, returns false at the end of the input
+    fn fill_buf(&mut self) -> Result<bool> {
+        if self.eof {
+            return Ok(false);
+        }
+
+        // Drop the read data before growing the buffer
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        let len = self.buf.len();
+        self.buf.resize(len + Self::CHUNK_SIZE, 0);
+
+        let read = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e.into());
+                }
+            }
+        };
+
+        self.buf.truncate(len + read);
+        self.eof = read == 0;
+
+        Ok(!self.eof)
+    }
+
+    fn find_start_code(&self, from: usize) -> Option<usize> {
+        self.data()
+            .get(from..)?
+            .windows(Self::START_CODE.len())
+            .position(|w| w == Self::START_CODE)
+            .map(|pos| from + pos)
+    }
+
+    fn next_hevc_nalu(&mut self) -> Result<Option<Vec<u8>>> {
+        // Skip anything before the first start code
+        let start = loop {
+            if let Some(start) = self.find_start_code(0) {
+                break start;
+            }
+
+            if !self.fill_buf()? {
+                ensure!(
+                    self.data().iter().all(|b| *b == 0),
+                    "No NALU start code found in the remaining {} bytes",
+                    self.data().len()
+                );
+
+                return Ok(None);
+            }
+        };
+        self.consume(start);
+
+        let mut search_pos = Self::START_CODE.len();
+        let end = loop {
+            if let Some(end) = self.find_start_code(search_pos) {
+                break end;
+            }
+
+            // The next start code could be split between two chunks
+            search_pos =
+                search_pos.max(self.data().len().saturating_sub(Self::START_CODE.len() - 1));
+
+            if !self.fill_buf()? {
+                break self.data().len();
+            }
+        };
+
+        Ok(Some(self.consume(end).to_vec()))
+    }
+
+    /// Reads the next RPU without parsing it.
+    /// HEVC NALUs are returned with their start code, AV1 payloads without their size.
+    pub fn next_payload(&mut self) -> Option<Result<Vec<u8>>> {
+        if self.done {
+            return None;
+        }
+
+        let res = match self.format {
+            RpuReaderFormat::Hevc => self.next_hevc_nalu(),
+            RpuReaderFormat::Av1ItuT35 => self.next_av1_payload(),
+        };
+
+        match res {
+            Ok(Some(data)) => Some(Ok(data)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    fn next_av1_payload(&mut self) -> Result<Option<Vec<u8>>> {
+        while self.data().len() < 4 && self.fill_buf()? {}
+
+        let data = self.data();
+        if data.is_empty() {
+            return Ok(None);
+        }
+        ensure!(data.len() >= 4, DoviError::Truncated);
+
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let end = 4 + size as usize;
+
+        while self.data().len() < end && self.fill_buf()? {}
+        ensure!(self.data().len() >= end, DoviError::Truncated);
+
+        Ok(Some(self.consume(end)[4..].to_vec()))
+    }
+}
+
+impl<R: Read> Iterator for RpuReader<R> {
+    type Item = Result<DoviRpu>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_payload().map(|res| {
+            res.and_then(|data| match self.format {
+                RpuReaderFormat::Hevc => DoviRpu::parse_unspec62_nalu_with_opts(&data, self.opts),
+                RpuReaderFormat::Av1ItuT35 => {
+                    DoviRpu::parse_itu_t35_dovi_metadata_obu_with_opts(&data, self.opts)
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    use anyhow::Result;
+
+    use super::{RpuReader, RpuReaderFormat, parse_rpu_file};
+
+    /// Reader returning a few bytes at a time, to split the RPUs between reads
+    struct SmallReads<'a>(&'a [u8]);
+
+    impl Read for SmallReads<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+
+            Ok(n)
+        }
+    }
+
+    fn assets_path() -> PathBuf {
+        let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        lib_path.parent().unwrap().join("assets/tests")
+    }
+
+    #[test]
+    fn stream_hevc_rpus() -> Result<()> {
+        for file in ["fel_orig.bin", "profile8.bin", "cmv4_0_2_xml_rpu.bin"] {
+            let path = assets_path().join(file);
+            let rpus = parse_rpu_file(&path)?;
+            let data = std::fs::read(&path)?;
+
+            let streamed = RpuReader::new(data.as_slice(), RpuReaderFormat::Hevc)
+                .collect::<Result<Vec<_>>>()?;
+            let small_reads = RpuReader::new(SmallReads(&data), RpuReaderFormat::Hevc)
+                .collect::<Result<Vec<_>>>()?;
+
+            assert_eq!(streamed.len(), rpus.len(), "{file}");
+            assert_eq!(small_reads.len(), rpus.len(), "{file}");
+
+            for ((a, b), c) in rpus.iter().zip(&streamed).zip(&small_reads) {
+                assert_eq!(a.write_rpu()?, b.write_rpu()?);
+                assert_eq!(a.write_rpu()?, c.write_rpu()?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_av1_payloads() -> Result<()> {
+        let rpus = parse_rpu_file(assets_path().join("profile8.bin"))?;
+
+        let mut data = Vec::new();
+        for rpu in &rpus {
+            let payload = rpu.write_av1_rpu_metadata_obu_t35_payload()?;
+            data.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            data.extend_from_slice(&payload);
+        }
+
+        let streamed = RpuReader::new(SmallReads(&data), RpuReaderFormat::Av1ItuT35)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(streamed.len(), rpus.len());
+        assert_eq!(streamed[0].write_rpu()?, rpus[0].write_rpu()?);
+
+        // Truncated payload ends the stream with an error
+        let res: Vec<_> =
+            RpuReader::new(&data[..data.len() - 10], RpuReaderFormat::Av1ItuT35).collect();
+        assert_eq!(res.len(), rpus.len());
+        assert!(res.last().unwrap().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_invalid_rpu() -> Result<()> {
+        let data = std::fs::read(assets_path().join("profile8.bin"))?;
+        let rpus = parse_rpu_file(assets_path().join("profile8.bin"))?;
+
+        let mut with_invalid = data.clone();
+        with_invalid.extend_from_slice(&[0, 0, 0, 1, 0x7C, 0x01, 0x19, 0x08, 0xFF]);
+        with_invalid.extend_from_slice(&data);
+
+        let res: Vec<_> = RpuReader::new(with_invalid.as_slice(), RpuReaderFormat::Hevc).collect();
+        assert_eq!(res.len(), rpus.len() * 2 + 1);
+        assert!(res[rpus.len()].is_err());
+        assert_eq!(res.iter().filter(|res| res.is_ok()).count(), rpus.len() * 2);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,13 @@
 use anyhow::{Context, Result, bail, ensure};
-use bitvec_helpers::{
-    bitstream_io_reader::BsIoSliceReader, bitstream_io_writer::BitstreamIoWriter,
-};
+use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
 
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use super::extension_metadata::blocks::{
-    ExtMetadataBlock, ExtMetadataBlockLevel9, ExtMetadataBlockLevel11,
+    ExtMetadataBlock, ExtMetadataBlockLevel2, ExtMetadataBlockLevel8, ExtMetadataBlockLevel9,
+    ExtMetadataBlockLevel10, ExtMetadataBlockLevel11, MAX_12_BIT_VALUE,
+    level8::PRESET_TARGET_DISPLAY_NITS, level10::PRESET_TARGET_DISPLAYS,
 };
 use super::extension_metadata::*;
 use super::generate::{GenerateConfig, GenerateProfile};
@@ -18,6 +18,8 @@ use super::profiles::profile84::Profile84;
 
 use super::extension_metadata::WithExtMetadataBlocks;
 use super::rpu_data_header::RpuDataHeader;
+use crate::rpu::trace::RpuBitReader;
+use crate::utils::nits_to_pq_12_bit;
 
 // 16 bits min for required level 254 + CRC32 + 0x80
 const DM_DATA_PAYLOAD2_MIN_BITS: u64 = 56;
@@ -64,12 +66,41 @@ pub struct VdrDmData {
     pub source_max_pq: u16,
     pub source_diagonal: u16,
 
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_cmv29_metadata",
+            default
+        )
+    )]
     pub cmv29_metadata: Option<DmData>,
-    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_cmv40_metadata",
+            default
+        )
+    )]
     pub cmv40_metadata: Option<DmData>,
 }
 
+/// `DmData` is untagged, the version is known from the field name
+#[cfg(feature = "serde")]
+fn deserialize_cmv29_metadata<'de, D: Deserializer<'de>>(
+    d: D,
+) -> std::result::Result<Option<DmData>, D::Error> {
+    Ok(Option::<CmV29DmData>::deserialize(d)?.map(DmData::V29))
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_cmv40_metadata<'de, D: Deserializer<'de>>(
+    d: D,
+) -> std::result::Result<Option<DmData>, D::Error> {
+    Ok(Option::<CmV40DmData>::deserialize(d)?.map(DmData::V40))
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum CmVersion {
@@ -78,7 +109,7 @@ pub enum CmVersion {
 }
 
 pub(crate) fn vdr_dm_data_payload(
-    reader: &mut BsIoSliceReader,
+    reader: &mut RpuBitReader,
     header: &RpuDataHeader,
 ) -> Result<VdrDmData> {
     let compressed_dm_data = header.reserved_zero_3bits == 1;
@@ -87,73 +118,77 @@ pub(crate) fn vdr_dm_data_payload(
         VdrDmData {
             compressed: true,
 
-            affected_dm_metadata_id: reader.read_ue()?,
-            current_dm_metadata_id: reader.read_ue()?,
-            scene_refresh_flag: reader.read_ue()?,
+            affected_dm_metadata_id: reader.read_ue("affected_dm_metadata_id")?,
+            current_dm_metadata_id: reader.read_ue("current_dm_metadata_id")?,
+            scene_refresh_flag: reader.read_ue("scene_refresh_flag")?,
             ..Default::default()
         }
     } else {
         VdrDmData::parse(reader)?
     };
 
+    reader.begin("dm_data_payload");
     if let Some(cmv29_dm_data) =
         DmData::parse::<CmV29DmData>(reader).with_context(|| CmV29DmData::VERSION)?
     {
         vdr_dm_data.cmv29_metadata = Some(DmData::V29(cmv29_dm_data));
     }
+    reader.end();
 
     if reader.available()? >= DM_DATA_PAYLOAD2_MIN_BITS {
+        reader.begin("dm_data_payload2");
         if let Some(cmv40_dm_data) =
             DmData::parse::<CmV40DmData>(reader).with_context(|| CmV40DmData::VERSION)?
         {
             vdr_dm_data.cmv40_metadata = Some(DmData::V40(cmv40_dm_data));
         }
+        reader.end();
     }
 
     Ok(vdr_dm_data)
 }
 
 impl VdrDmData {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<VdrDmData> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<VdrDmData> {
         let data = VdrDmData {
-            affected_dm_metadata_id: reader.read_ue()?,
-            current_dm_metadata_id: reader.read_ue()?,
-            scene_refresh_flag: reader.read_ue()?,
-
-            ycc_to_rgb_coef0: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef1: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef2: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef3: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef4: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef5: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef6: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef7: reader.read::<16, i16>()?,
-            ycc_to_rgb_coef8: reader.read::<16, i16>()?,
-            ycc_to_rgb_offset0: reader.read::<32, u32>()?,
-            ycc_to_rgb_offset1: reader.read::<32, u32>()?,
-            ycc_to_rgb_offset2: reader.read::<32, u32>()?,
-
-            rgb_to_lms_coef0: reader.read::<16, i16>()?,
-            rgb_to_lms_coef1: reader.read::<16, i16>()?,
-            rgb_to_lms_coef2: reader.read::<16, i16>()?,
-            rgb_to_lms_coef3: reader.read::<16, i16>()?,
-            rgb_to_lms_coef4: reader.read::<16, i16>()?,
-            rgb_to_lms_coef5: reader.read::<16, i16>()?,
-            rgb_to_lms_coef6: reader.read::<16, i16>()?,
-            rgb_to_lms_coef7: reader.read::<16, i16>()?,
-            rgb_to_lms_coef8: reader.read::<16, i16>()?,
-
-            signal_eotf: reader.read::<16, u16>()?,
-            signal_eotf_param0: reader.read::<16, u16>()?,
-            signal_eotf_param1: reader.read::<16, u16>()?,
-            signal_eotf_param2: reader.read::<32, u32>()?,
-            signal_bit_depth: reader.read::<5, u8>()?,
-            signal_color_space: reader.read::<2, u8>()?,
-            signal_chroma_format: reader.read::<2, u8>()?,
-            signal_full_range_flag: reader.read::<2, u8>()?,
-            source_min_pq: reader.read::<12, u16>()?,
-            source_max_pq: reader.read::<12, u16>()?,
-            source_diagonal: reader.read::<10, u16>()?,
+            affected_dm_metadata_id: reader.read_ue("affected_dm_metadata_id")?,
+            current_dm_metadata_id: reader.read_ue("current_dm_metadata_id")?,
+            scene_refresh_flag: reader.read_ue("scene_refresh_flag")?,
+
+            ycc_to_rgb_coef0: reader.read::<16, i16>("ycc_to_rgb_coef0")?,
+            ycc_to_rgb_coef1: reader.read::<16, i16>("ycc_to_rgb_coef1")?,
+            ycc_to_rgb_coef2: reader.read::<16, i16>("ycc_to_rgb_coef2")?,
+            ycc_to_rgb_coef3: reader.read::<16, i16>("ycc_to_rgb_coef3")?,
+            ycc_to_rgb_coef4: reader.read::<16, i16>("ycc_to_rgb_coef4")?,
+            ycc_to_rgb_coef5: reader.read::<16, i16>("ycc_to_rgb_coef5")?,
+            ycc_to_rgb_coef6: reader.read::<16, i16>("ycc_to_rgb_coef6")?,
+            ycc_to_rgb_coef7: reader.read::<16, i16>("ycc_to_rgb_coef7")?,
+            ycc_to_rgb_coef8: reader.read::<16, i16>("ycc_to_rgb_coef8")?,
+            ycc_to_rgb_offset0: reader.read::<32, u32>("ycc_to_rgb_offset0")?,
+            ycc_to_rgb_offset1: reader.read::<32, u32>("ycc_to_rgb_offset1")?,
+            ycc_to_rgb_offset2: reader.read::<32, u32>("ycc_to_rgb_offset2")?,
+
+            rgb_to_lms_coef0: reader.read::<16, i16>("rgb_to_lms_coef0")?,
+            rgb_to_lms_coef1: reader.read::<16, i16>("rgb_to_lms_coef1")?,
+            rgb_to_lms_coef2: reader.read::<16, i16>("rgb_to_lms_coef2")?,
+            rgb_to_lms_coef3: reader.read::<16, i16>("rgb_to_lms_coef3")?,
+            rgb_to_lms_coef4: reader.read::<16, i16>("rgb_to_lms_coef4")?,
+            rgb_to_lms_coef5: reader.read::<16, i16>("rgb_to_lms_coef5")?,
+            rgb_to_lms_coef6: reader.read::<16, i16>("rgb_to_lms_coef6")?,
+            rgb_to_lms_coef7: reader.read::<16, i16>("rgb_to_lms_coef7")?,
+            rgb_to_lms_coef8: reader.read::<16, i16>("rgb_to_lms_coef8")?,
+
+            signal_eotf: reader.read::<16, u16>("signal_eotf")?,
+            signal_eotf_param0: reader.read::<16, u16>("signal_eotf_param0")?,
+            signal_eotf_param1: reader.read::<16, u16>("signal_eotf_param1")?,
+            signal_eotf_param2: reader.read::<32, u32>("signal_eotf_param2")?,
+            signal_bit_depth: reader.read::<5, u8>("signal_bit_depth")?,
+            signal_color_space: reader.read::<2, u8>("signal_color_space")?,
+            signal_chroma_format: reader.read::<2, u8>("signal_chroma_format")?,
+            signal_full_range_flag: reader.read::<2, u8>("signal_full_range_flag")?,
+            source_min_pq: reader.read::<12, u16>("source_min_pq")?,
+            source_max_pq: reader.read::<12, u16>("source_max_pq")?,
+            source_diagonal: reader.read::<10, u16>("source_diagonal")?,
             ..Default::default()
         };
 
@@ -410,6 +445,174 @@ impl VdrDmData {
         Ok(())
     }
 
+    /// Converts CM v4.0 metadata to CM v2.9, then removes the CM v4.0 metadata.
+    ///
+    /// L2 blocks are derived from the L8 trims, for target displays without existing L2 metadata.
+    /// L8 trims for custom target displays are only converted if the matching L10 block is present.
+    /// The L3 offsets are applied to L1, clamped to valid CM v2.9 values.
+    pub fn downgrade_to_cmv29(&mut self) -> Result<()> {
+        if self.cmv40_metadata.is_none() {
+            return Ok(());
+        }
+
+        if self.cmv29_metadata.is_none() {
+            self.cmv29_metadata = Some(DmData::V29(CmV29DmData::default()));
+        }
+
+        let level8_blocks: Vec<ExtMetadataBlockLevel8> = self
+            .level_blocks_iter(8)
+            .filter_map(|block| match block {
+                ExtMetadataBlock::Level8(b) => Some(b.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for level8 in level8_blocks {
+            // Custom target displays are defined by a L10 block
+            let target_max_pq = if PRESET_TARGET_DISPLAYS.contains(&level8.target_display_index) {
+                nits_to_pq_12_bit(level8.trim_target_nits())
+            } else {
+                let level10_max_pq = self.level_blocks_iter(10).find_map(|block| match block {
+                    ExtMetadataBlock::Level10(b)
+                        if b.target_display_index == level8.target_display_index =>
+                    {
+                        Some(b.target_max_pq)
+                    }
+                    _ => None,
+                });
+
+                match level10_max_pq {
+                    Some(target_max_pq) => target_max_pq,
+                    None => continue,
+                }
+            };
+
+            let has_target = self.level_blocks_iter(2).any(|block| match block {
+                ExtMetadataBlock::Level2(b) => b.target_max_pq == target_max_pq,
+                _ => false,
+            });
+
+            if !has_target {
+                self.add_metadata_block(ExtMetadataBlock::Level2(ExtMetadataBlockLevel2 {
+                    target_max_pq,
+                    trim_slope: level8.trim_slope,
+                    trim_offset: level8.trim_offset,
+                    trim_power: level8.trim_power,
+                    trim_chroma_weight: level8.trim_chroma_weight,
+                    trim_saturation_gain: level8.trim_saturation_gain,
+                    ms_weight: level8.ms_weight as i16,
+                }))?;
+            }
+        }
+
+        let level3 = match self.get_block(3) {
+            Some(ExtMetadataBlock::Level3(b)) => Some(b.clone()),
+            _ => None,
+        };
+
+        if let (Some(level3), Some(ExtMetadataBlock::Level1(level1))) =
+            (level3, self.get_block_mut(1))
+        {
+            let apply_offset = |value: u16, offset: u16| {
+                (value as i32 + offset as i32 - 2048).clamp(0, MAX_12_BIT_VALUE as i32) as u16
+            };
+
+            level1.min_pq = apply_offset(level1.min_pq, level3.min_pq_offset);
+            level1.max_pq = apply_offset(level1.max_pq, level3.max_pq_offset);
+            level1.avg_pq = apply_offset(level1.avg_pq, level3.avg_pq_offset);
+
+            level1.clamp_values_cm_version(CmVersion::V29);
+        }
+
+        self.cmv40_metadata = None;
+
+        Ok(())
+    }
+
+    /// Converts CM v2.9 metadata to CM v4.0, with the default L254 metadata.
+    ///
+    /// L8 trims are derived from the L2 metadata.
+    /// Targets that don't match a preset target display use a custom L10 target display.
+    pub fn upgrade_to_cmv40(
+        &mut self,
+        level9: ExtMetadataBlockLevel9,
+        level11: ExtMetadataBlockLevel11,
+    ) -> Result<()> {
+        if self.cmv40_metadata.is_some() {
+            return Ok(());
+        }
+
+        let level2_blocks: Vec<ExtMetadataBlockLevel2> = self
+            .level_blocks_iter(2)
+            .filter_map(|block| match block {
+                ExtMetadataBlock::Level2(b) => Some(b.clone()),
+                _ => None,
+            })
+            .collect();
+
+        ensure!(
+            level2_blocks.len() <= 5,
+            "Cannot convert {} L2 metadata blocks, CM v4.0 supports at most 5 L8 blocks",
+            level2_blocks.len()
+        );
+
+        let mut cmv40 = CmV40DmData::new_with_l254_402();
+        let mut custom_target_index = u8::MAX;
+
+        for level2 in level2_blocks {
+            // Lowest preset target display index with the same peak brightness
+            let preset_index = PRESET_TARGET_DISPLAY_NITS
+                .iter()
+                .find(|(_, nits)| nits_to_pq_12_bit(*nits) == level2.target_max_pq)
+                .map(|(index, _)| *index);
+
+            let target_display_index = match preset_index {
+                Some(index) => index,
+                None => {
+                    let index = custom_target_index;
+                    custom_target_index -= 1;
+
+                    cmv40.add_block(ExtMetadataBlock::Level10(ExtMetadataBlockLevel10 {
+                        length: 5,
+                        target_display_index: index,
+                        target_max_pq: level2.target_max_pq,
+                        target_min_pq: 0,
+                        target_primary_index: MasteringDisplayPrimaries::DCIP3D65 as u8,
+                        ..Default::default()
+                    }))?;
+
+                    index
+                }
+            };
+
+            // -1 is the default weight
+            let ms_weight = if level2.ms_weight < 0 {
+                2048
+            } else {
+                level2.ms_weight as u16
+            };
+
+            cmv40.add_block(ExtMetadataBlock::Level8(ExtMetadataBlockLevel8 {
+                length: 10,
+                target_display_index,
+                trim_slope: level2.trim_slope,
+                trim_offset: level2.trim_offset,
+                trim_power: level2.trim_power,
+                trim_chroma_weight: level2.trim_chroma_weight,
+                trim_saturation_gain: level2.trim_saturation_gain,
+                ms_weight,
+                ..Default::default()
+            }))?;
+        }
+
+        cmv40.add_block(ExtMetadataBlock::Level9(level9))?;
+        cmv40.add_block(ExtMetadataBlock::Level11(level11))?;
+
+        self.cmv40_metadata = Some(DmData::V40(cmv40));
+
+        Ok(())
+    }
+
     pub fn set_p81_coeffs(&mut self) {
         self.ycc_to_rgb_coef0 = 9574;
         self.ycc_to_rgb_coef1 = 0;
@@ -543,9 +746,15 @@ impl CmVersion {
 
 #[cfg(test)]
 mod tests {
-    use crate::rpu::extension_metadata::blocks::{ExtMetadataBlock, ExtMetadataBlockLevel6};
+    use crate::rpu::extension_metadata::CmV40DmData;
+    use crate::rpu::extension_metadata::blocks::{
+        ExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel2, ExtMetadataBlockLevel3,
+        ExtMetadataBlockLevel6, ExtMetadataBlockLevel8, ExtMetadataBlockLevel9,
+        ExtMetadataBlockLevel11,
+    };
 
-    use super::VdrDmData;
+    use super::{DmData, VdrDmData};
+    use crate::utils::nits_to_pq_12_bit;
 
     #[test]
     fn change_source_levels_with_zero() {
@@ -564,4 +773,153 @@ mod tests {
         assert_eq!(vdr_dm_data.source_min_pq, 0);
         assert_eq!(vdr_dm_data.source_max_pq, 1000);
     }
+
+    #[test]
+    fn downgrade_to_cmv29() {
+        let mut vdr_dm_data = VdrDmData::default_pq().with_cmv29_dm_data();
+        vdr_dm_data.cmv40_metadata = Some(DmData::V40(CmV40DmData::new_with_l254_402()));
+
+        let blocks = [
+            ExtMetadataBlock::Level1(ExtMetadataBlockLevel1::new(0, 2800, 1500)),
+            ExtMetadataBlock::Level2(ExtMetadataBlockLevel2::from_nits(100)),
+            ExtMetadataBlock::Level3(ExtMetadataBlockLevel3 {
+                min_pq_offset: 2048,
+                max_pq_offset: 2000,
+                avg_pq_offset: 2100,
+            }),
+            ExtMetadataBlock::Level8(ExtMetadataBlockLevel8 {
+                trim_slope: 1900,
+                ..Default::default()
+            }),
+            ExtMetadataBlock::Level8(ExtMetadataBlockLevel8 {
+                target_display_index: 48,
+                trim_slope: 2200,
+                trim_power: 1800,
+                ..Default::default()
+            }),
+        ];
+        for block in blocks {
+            vdr_dm_data.add_metadata_block(block).unwrap();
+        }
+
+        vdr_dm_data.downgrade_to_cmv29().unwrap();
+        assert!(vdr_dm_data.cmv40_metadata.is_none());
+
+        let l2_blocks: Vec<_> = vdr_dm_data
+            .level_blocks_iter(2)
+            .filter_map(|b| match b {
+                ExtMetadataBlock::Level2(b) => Some((b.target_max_pq, b.trim_slope, b.trim_power)),
+                _ => None,
+            })
+            .collect();
+
+        // Existing 100 nits L2 is kept, 1000 nits derived from L8
+        assert_eq!(l2_blocks, vec![(2081, 2048, 2048), (3079, 2200, 1800)]);
+
+        let Some(ExtMetadataBlock::Level1(level1)) = vdr_dm_data.get_block(1) else {
+            panic!("No L1 block");
+        };
+        assert_eq!(
+            (level1.min_pq, level1.max_pq, level1.avg_pq),
+            (0, 2752, 1552)
+        );
+    }
+
+    #[test]
+    fn upgrade_to_cmv40() {
+        let mut vdr_dm_data = VdrDmData::default_pq().with_cmv29_dm_data();
+
+        let blocks = [
+            ExtMetadataBlock::Level1(ExtMetadataBlockLevel1::new(0, 2800, 1500)),
+            ExtMetadataBlock::Level2(ExtMetadataBlockLevel2 {
+                trim_slope: 1900,
+                ms_weight: -1,
+                ..ExtMetadataBlockLevel2::from_nits(100)
+            }),
+            ExtMetadataBlock::Level2(ExtMetadataBlockLevel2 {
+                trim_power: 1800,
+                ..ExtMetadataBlockLevel2::from_nits(400)
+            }),
+        ];
+        for block in blocks {
+            vdr_dm_data.add_metadata_block(block).unwrap();
+        }
+
+        vdr_dm_data
+            .upgrade_to_cmv40(
+                ExtMetadataBlockLevel9::default_dci_p3(),
+                ExtMetadataBlockLevel11::default_reference_cinema(),
+            )
+            .unwrap();
+
+        let l8_blocks: Vec<_> = vdr_dm_data
+            .level_blocks_iter(8)
+            .filter_map(|b| match b {
+                ExtMetadataBlock::Level8(b) => Some((
+                    b.target_display_index,
+                    b.trim_slope,
+                    b.trim_power,
+                    b.ms_weight,
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            l8_blocks,
+            vec![(1, 1900, 2048, 2048), (255, 2048, 1800, 2048)]
+        );
+
+        // Custom target display for 400 nits
+        let Some(ExtMetadataBlock::Level10(level10)) = vdr_dm_data.get_block(10) else {
+            panic!("No L10 block");
+        };
+        assert_eq!(level10.target_display_index, 255);
+        assert_eq!(level10.target_max_pq, 2672);
+
+        assert!(vdr_dm_data.get_block(9).is_some());
+        assert!(vdr_dm_data.get_block(11).is_some());
+        assert!(vdr_dm_data.get_block(254).is_some());
+
+        let Some(DmData::V40(cmv40)) = &vdr_dm_data.cmv40_metadata else {
+            panic!("No CM v4.0 metadata");
+        };
+        cmv40.validate().unwrap();
+    }
+
+    #[test]
+    fn upgrade_to_cmv40_300_nits() {
+        let mut vdr_dm_data = VdrDmData::default_pq().with_cmv29_dm_data();
+
+        vdr_dm_data
+            .add_metadata_block(ExtMetadataBlock::Level2(ExtMetadataBlockLevel2 {
+                trim_slope: 1900,
+                ..ExtMetadataBlockLevel2::from_nits(300)
+            }))
+            .unwrap();
+
+        vdr_dm_data
+            .upgrade_to_cmv40(
+                ExtMetadataBlockLevel9::default_dci_p3(),
+                ExtMetadataBlockLevel11::default_reference_cinema(),
+            )
+            .unwrap();
+
+        // 300 nits is a preset target display, no L10 block is needed
+        let Some(ExtMetadataBlock::Level8(level8)) = vdr_dm_data.get_block(8) else {
+            panic!("No L8 block");
+        };
+        assert_eq!(level8.target_display_index, 24);
+        assert_eq!(level8.trim_target_nits(), 300);
+        assert!(vdr_dm_data.get_block(10).is_none());
+
+        // Converting back keeps the L2 trim at 300 nits
+        vdr_dm_data.remove_metadata_level(2);
+        vdr_dm_data.downgrade_to_cmv29().unwrap();
+
+        let Some(ExtMetadataBlock::Level2(level2)) = vdr_dm_data.get_block(2) else {
+            panic!("No L2 block");
+        };
+        assert_eq!(level2.target_max_pq, nits_to_pq_12_bit(300));
+        assert_eq!(level2.trim_slope, 1900);
+    }
 }
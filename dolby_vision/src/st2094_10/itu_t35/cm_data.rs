@@ -1,9 +1,9 @@
 use anyhow::Result;
-use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
 
 use super::UserDataTypeStruct;
 
 use crate::rpu::NUM_COMPONENTS;
+use crate::rpu::trace::RpuBitReader;
 
 #[derive(Default, Debug)]
 pub struct ST2094_10CmData {
@@ -38,29 +38,29 @@ pub struct ST2094_10CmData {
 }
 
 impl ST2094_10CmData {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<UserDataTypeStruct> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<UserDataTypeStruct> {
         let mut meta = ST2094_10CmData {
-            ccm_profile: reader.read::<4, u8>()?,
-            ccm_level: reader.read::<4, u8>()?,
-            coefficient_log2_denom: reader.read_ue()?,
-            bl_bit_depth_minus8: reader.read_ue()?,
-            el_bit_depth_minus8: reader.read_ue()?,
-            hdr_bit_depth_minus8: reader.read_ue()?,
-            disable_residual_flag: reader.read_bit()?,
+            ccm_profile: reader.read::<4, u8>("ccm_profile")?,
+            ccm_level: reader.read::<4, u8>("ccm_level")?,
+            coefficient_log2_denom: reader.read_ue("coefficient_log2_denom")?,
+            bl_bit_depth_minus8: reader.read_ue("bl_bit_depth_minus8")?,
+            el_bit_depth_minus8: reader.read_ue("el_bit_depth_minus8")?,
+            hdr_bit_depth_minus8: reader.read_ue("hdr_bit_depth_minus8")?,
+            disable_residual_flag: reader.read_bit("disable_residual_flag")?,
             ..Default::default()
         };
 
         let coefficient_log2_denom_length = meta.coefficient_log2_denom as u32;
 
         for cmp in 0..NUM_COMPONENTS {
-            meta.num_pivots_minus2[cmp] = reader.read_ue()?;
+            meta.num_pivots_minus2[cmp] = reader.read_ue("num_pivots_minus2")?;
 
             meta.pred_pivot_value[cmp]
                 .resize_with((meta.num_pivots_minus2[cmp] as usize) + 2, Default::default);
 
             for pivot_idx in 0..(meta.num_pivots_minus2[cmp] as usize) + 2 {
                 meta.pred_pivot_value[cmp][pivot_idx] =
-                    reader.read_var((meta.el_bit_depth_minus8 as u32) + 8)?;
+                    reader.read_var((meta.el_bit_depth_minus8 as u32) + 8, "pred_pivot_value")?;
             }
         }
 
@@ -86,11 +86,11 @@ impl ST2094_10CmData {
                 .resize_with((meta.num_pivots_minus2[cmp] as usize) + 1, Default::default);
 
             for pivot_idx in 0..(meta.num_pivots_minus2[cmp] as usize) + 1 {
-                meta.mapping_idc[cmp][pivot_idx] = reader.read_ue()?;
+                meta.mapping_idc[cmp][pivot_idx] = reader.read_ue("mapping_idc")?;
 
                 // MAPPING_POLYNOMIAL
                 if meta.mapping_idc[cmp][pivot_idx] == 0 {
-                    meta.poly_order_minus1[cmp][pivot_idx] = reader.read_ue()?;
+                    meta.poly_order_minus1[cmp][pivot_idx] = reader.read_ue("poly_order_minus1")?;
 
                     meta.poly_coef_int[cmp][pivot_idx].resize_with(
                         (meta.poly_order_minus1[cmp][pivot_idx] as usize) + 2,
@@ -102,17 +102,18 @@ impl ST2094_10CmData {
                     );
 
                     for i in 0..=(meta.poly_order_minus1[cmp][pivot_idx] as usize) + 1 {
-                        meta.poly_coef_int[cmp][pivot_idx][i] = reader.read_se()?;
+                        meta.poly_coef_int[cmp][pivot_idx][i] = reader.read_se("poly_coef_int")?;
                         meta.poly_coef[cmp][pivot_idx][i] =
-                            reader.read_var(coefficient_log2_denom_length)?;
+                            reader.read_var(coefficient_log2_denom_length, "poly_coef")?;
                     }
                 } else if meta.mapping_idc[cmp][pivot_idx] == 1 {
                     // MAPPING_MMR
 
-                    meta.mmr_order_minus1[cmp][pivot_idx] = reader.read::<2, u8>()?;
-                    meta.mmr_constant_int[cmp][pivot_idx] = reader.read_se()?;
+                    meta.mmr_order_minus1[cmp][pivot_idx] =
+                        reader.read::<2, u8>("mmr_order_minus1")?;
+                    meta.mmr_constant_int[cmp][pivot_idx] = reader.read_se("mmr_constant_int")?;
                     meta.mmr_constant[cmp][pivot_idx] =
-                        reader.read_var(coefficient_log2_denom_length)?;
+                        reader.read_var(coefficient_log2_denom_length, "mmr_constant")?;
 
                     meta.mmr_coef_int[cmp][pivot_idx].resize_with(
                         (meta.mmr_order_minus1[cmp][pivot_idx] as usize) + 2,
@@ -128,9 +129,10 @@ impl ST2094_10CmData {
                         meta.mmr_coef[cmp][pivot_idx][i].resize_with(8, Default::default);
 
                         for j in 0..7_usize {
-                            meta.mmr_coef_int[cmp][pivot_idx][i][j] = reader.read_se()?;
+                            meta.mmr_coef_int[cmp][pivot_idx][i][j] =
+                                reader.read_se("mmr_coef_int")?;
                             meta.mmr_coef[cmp][pivot_idx][i][j] =
-                                reader.read_var(coefficient_log2_denom_length)?;
+                                reader.read_var(coefficient_log2_denom_length, "mmr_coef")?;
                         }
                     }
                 }
@@ -139,14 +141,19 @@ impl ST2094_10CmData {
 
         if !meta.disable_residual_flag {
             for cmp in 0..NUM_COMPONENTS {
-                meta.nlq_offset[cmp] = reader.read_var((meta.el_bit_depth_minus8 as u32) + 8)?;
-                meta.hdr_in_max_int[cmp] = reader.read_ue()?;
-                meta.hdr_in_max[cmp] = reader.read_var(coefficient_log2_denom_length)?;
-                meta.linear_deadzone_slope_int[cmp] = reader.read_ue()?;
-                meta.linear_deadzone_slope[cmp] = reader.read_var(coefficient_log2_denom_length)?;
-                meta.linear_deadzone_threshold_int[cmp] = reader.read_ue()?;
+                meta.nlq_offset[cmp] =
+                    reader.read_var((meta.el_bit_depth_minus8 as u32) + 8, "nlq_offset")?;
+                meta.hdr_in_max_int[cmp] = reader.read_ue("hdr_in_max_int")?;
+                meta.hdr_in_max[cmp] =
+                    reader.read_var(coefficient_log2_denom_length, "hdr_in_max")?;
+                meta.linear_deadzone_slope_int[cmp] =
+                    reader.read_ue("linear_deadzone_slope_int")?;
+                meta.linear_deadzone_slope[cmp] =
+                    reader.read_var(coefficient_log2_denom_length, "linear_deadzone_slope")?;
+                meta.linear_deadzone_threshold_int[cmp] =
+                    reader.read_ue("linear_deadzone_threshold_int")?;
                 meta.linear_deadzone_threshold[cmp] =
-                    reader.read_var(coefficient_log2_denom_length)?;
+                    reader.read_var(coefficient_log2_denom_length, "linear_deadzone_threshold")?;
             }
         }
 
@@ -1,7 +1,7 @@
 use anyhow::Result;
-use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
 
 use crate::rpu::extension_metadata::{CmV29DmData, DmData};
+use crate::rpu::trace::RpuBitReader;
 
 use super::UserDataTypeStruct;
 
@@ -14,11 +14,11 @@ pub struct ST2094_10DmData {
 }
 
 impl ST2094_10DmData {
-    pub(crate) fn parse(reader: &mut BsIoSliceReader) -> Result<UserDataTypeStruct> {
+    pub(crate) fn parse(reader: &mut RpuBitReader) -> Result<UserDataTypeStruct> {
         let mut meta = ST2094_10DmData {
-            app_identifier: reader.read_ue()?,
-            app_version: reader.read_ue()?,
-            metadata_refresh_flag: reader.read_bit()?,
+            app_identifier: reader.read_ue("app_identifier")?,
+            app_version: reader.read_ue("app_version")?,
+            metadata_refresh_flag: reader.read_bit("metadata_refresh_flag")?,
             ..Default::default()
         };
 
@@ -1,6 +1,6 @@
 use anyhow::{Result, bail, ensure};
-use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
 
+use crate::rpu::trace::RpuBitReader;
 use crate::utils::clear_start_code_emulation_prevention_3_byte;
 
 mod cm_data;
@@ -27,22 +27,22 @@ impl ST2094_10ItuT35 {
         let trimmed_data = Self::validated_trimmed_data(data)?;
         let bytes = clear_start_code_emulation_prevention_3_byte(trimmed_data);
 
-        let mut reader = BsIoSliceReader::from_slice(&bytes);
+        let mut reader = RpuBitReader::new(&bytes, false);
 
-        let itu_t_t35_country_code = reader.read::<8, u8>()?;
-        let itu_t_t35_provider_code = reader.read::<16, u16>()?;
+        let itu_t_t35_country_code = reader.read::<8, u8>("itu_t_t35_country_code")?;
+        let itu_t_t35_provider_code = reader.read::<16, u16>("itu_t_t35_provider_code")?;
 
         ensure!(itu_t_t35_country_code == 0xB5);
         ensure!(itu_t_t35_provider_code == 0x31);
 
-        let user_identifier = reader.read::<32, u32>()?;
+        let user_identifier = reader.read::<32, u32>("user_identifier")?;
         ensure!(
             user_identifier == 0x47413934,
             "invalid user_identifier: {}",
             user_identifier
         );
 
-        let user_data_type_code = reader.read::<8, u8>()?;
+        let user_data_type_code = reader.read::<8, u8>("user_data_type_code")?;
 
         let meta = match user_data_type_code {
             0x08 => ST2094_10CmData::parse(&mut reader)?,
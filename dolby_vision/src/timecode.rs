@@ -0,0 +1,270 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{Result, bail, ensure};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Video frame rate, as a fraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct FrameRate {
+    pub num: u32,
+    pub den: u32,
+}
+
+/// Frame index, or timecode/seconds string
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize), serde(untagged))]
+pub enum FramePosition {
+    Index(usize),
+    Time(String),
+}
+
+impl FrameRate {
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Frames per second counted in timecodes, 30 for 29.97 fps
+    pub fn nominal_fps(&self) -> usize {
+        self.num.div_ceil(self.den) as usize
+    }
+
+    /// Drop-frame timecodes are only valid for 30000/1001 and 60000/1001
+    fn dropped_frames_per_minute(&self) -> Option<usize> {
+        match (self.num, self.den) {
+            (30000, 1001) => Some(2),
+            (60000, 1001) => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Converts a `HH:MM:SS:FF` timecode to a frame index.
+    /// Drop-frame timecodes use `;` as the frames separator: `HH:MM:SS;FF`
+    pub fn timecode_to_frame(&self, timecode: &str) -> Result<usize> {
+        let drop_frame = timecode.contains(';');
+
+        let parts = timecode
+            .split([':', ';'])
+            .map(|e| {
+                e.trim()
+                    .parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("Invalid timecode {timecode}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let [hours, minutes, seconds, frames] = parts[..] else {
+            bail!("Invalid timecode {timecode}: expected HH:MM:SS:FF");
+        };
+
+        let fps = self.nominal_fps();
+
+        ensure!(
+            minutes < 60,
+            "Invalid timecode {timecode}: minutes out of range"
+        );
+        ensure!(
+            seconds < 60,
+            "Invalid timecode {timecode}: seconds out of range"
+        );
+        ensure!(
+            frames < fps,
+            "Invalid timecode {timecode}: frames out of range for {self} fps"
+        );
+
+        let total_minutes = hours * 60 + minutes;
+        let mut frame = (total_minutes * 60 + seconds) * fps + frames;
+
+        if drop_frame {
+            let Some(dropped) = self.dropped_frames_per_minute() else {
+                bail!("Invalid timecode {timecode}: drop-frame is not supported for {self} fps");
+            };
+
+            ensure!(
+                seconds > 0 || frames >= dropped || minutes % 10 == 0,
+                "Invalid timecode {timecode}: dropped frame number"
+            );
+
+            frame -= dropped * (total_minutes - total_minutes / 10);
+        }
+
+        Ok(frame)
+    }
+
+    /// Converts a time in seconds to the nearest frame index
+    pub fn seconds_to_frame(&self, seconds: f64) -> Result<usize> {
+        ensure!(
+            seconds.is_finite() && seconds >= 0.0,
+            "Invalid time in seconds: {seconds}"
+        );
+
+        Ok((seconds * self.as_f64()).round() as usize)
+    }
+
+    /// Parses a frame position, which can be a frame index, a timecode or seconds (`12.5s`).
+    /// Timecodes and seconds require a frame rate.
+    pub fn parse_position(frame_rate: Option<&Self>, position: &str) -> Result<usize> {
+        let position = position.trim();
+
+        if let Ok(frame) = position.parse::<usize>() {
+            return Ok(frame);
+        }
+
+        let Some(frame_rate) = frame_rate else {
+            bail!("Invalid frame index {position}: timecodes and seconds require a frame_rate");
+        };
+
+        if let Some(seconds) = position.strip_suffix('s') {
+            let seconds = seconds
+                .parse::<f64>()
+                .map_err(|_| anyhow::anyhow!("Invalid time in seconds {position}"))?;
+
+            frame_rate.seconds_to_frame(seconds)
+        } else if position.contains([':', ';']) {
+            frame_rate.timecode_to_frame(position)
+        } else {
+            bail!("Invalid frame position {position}")
+        }
+    }
+
+    /// Parses an inclusive `start-end` range of frame positions
+    pub fn parse_range(frame_rate: Option<&Self>, range: &str) -> Result<(usize, usize)> {
+        let Some((start, end)) = range.split_once('-') else {
+            bail!("Invalid edit range {range}");
+        };
+
+        let start = Self::parse_position(frame_rate, start)?;
+        let end = Self::parse_position(frame_rate, end)?;
+
+        ensure!(
+            start <= end,
+            "Invalid edit range {range}: start is after end"
+        );
+
+        Ok((start, end))
+    }
+}
+
+impl FramePosition {
+    pub fn to_frame(&self, frame_rate: Option<&FrameRate>) -> Result<usize> {
+        match self {
+            Self::Index(index) => Ok(*index),
+            Self::Time(position) => FrameRate::parse_position(frame_rate, position),
+        }
+    }
+}
+
+impl FromStr for FrameRate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        let (num, den) = if let Some((num, den)) = s.split_once('/') {
+            (num.trim().parse::<u32>()?, den.trim().parse::<u32>()?)
+        } else {
+            match s {
+                "23.976" | "23.98" => (24000, 1001),
+                "29.97" => (30000, 1001),
+                "47.952" => (48000, 1001),
+                "59.94" => (60000, 1001),
+                _ => (
+                    s.parse::<u32>().map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid frame rate {s}, expected a fraction like 24000/1001"
+                        )
+                    })?,
+                    1,
+                ),
+            }
+        };
+
+        ensure!(num > 0 && den > 0, "Invalid frame rate {s}");
+
+        Ok(Self { num, den })
+    }
+}
+
+impl TryFrom<String> for FrameRate {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::from_str(&value)
+    }
+}
+
+impl From<FrameRate> for String {
+    fn from(value: FrameRate) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::FrameRate;
+
+    #[test]
+    fn non_drop_frame_timecode() -> Result<()> {
+        let frame_rate: FrameRate = "24000/1001".parse()?;
+
+        assert_eq!(frame_rate.nominal_fps(), 24);
+        assert_eq!(frame_rate.timecode_to_frame("00:00:01:00")?, 24);
+        assert_eq!(frame_rate.timecode_to_frame("00:01:00:12")?, 1452);
+
+        assert!(frame_rate.timecode_to_frame("00:00:00:24").is_err());
+        assert!(frame_rate.timecode_to_frame("00:60:00:00").is_err());
+        assert!(frame_rate.timecode_to_frame("00:00:00;10").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn drop_frame_timecode() -> Result<()> {
+        let frame_rate: FrameRate = "30000/1001".parse()?;
+
+        assert_eq!(frame_rate.timecode_to_frame("00:00:59;29")?, 1799);
+        assert_eq!(frame_rate.timecode_to_frame("00:01:00;02")?, 1800);
+        assert_eq!(frame_rate.timecode_to_frame("00:10:00;00")?, 17982);
+        assert_eq!(frame_rate.timecode_to_frame("01:00:00;00")?, 107892);
+
+        // Dropped frame numbers
+        assert!(frame_rate.timecode_to_frame("00:01:00;00").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn positions_and_ranges() -> Result<()> {
+        let frame_rate: FrameRate = "25".parse()?;
+
+        assert_eq!(FrameRate::parse_position(None, "120")?, 120);
+        assert_eq!(FrameRate::parse_position(Some(&frame_rate), "2.5s")?, 63);
+        assert_eq!(
+            FrameRate::parse_range(Some(&frame_rate), "00:00:01:00-00:00:02:05")?,
+            (25, 55)
+        );
+
+        assert!(FrameRate::parse_position(None, "00:00:01:00").is_err());
+        assert!(FrameRate::parse_range(None, "40-20").is_err());
+
+        Ok(())
+    }
+}
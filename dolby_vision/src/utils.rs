@@ -1,7 +1,7 @@
 #[cfg(feature = "serde")]
 use {
     bitvec::prelude::*,
-    serde::{Serialize, ser::Serializer},
+    serde::{Deserialize, Deserializer, Serialize, ser::Serializer},
 };
 
 pub const ST2084_Y_MAX: f64 = 10000.0;
@@ -90,3 +90,17 @@ pub(crate) fn opt_bitvec_ser_bits<S: Serializer>(
     };
     bits.serialize(s)
 }
+
+/// Deserializing an optional bitvec from a vec of bits
+#[cfg(feature = "serde")]
+pub(crate) fn opt_bitvec_de_bits<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<BitVec<u8, Msb0>>, D::Error> {
+    let bits: Vec<u8> = Vec::deserialize(d)?;
+
+    if bits.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(bits.into_iter().map(|b| b != 0).collect()))
+    }
+}
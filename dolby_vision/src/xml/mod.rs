@@ -4,4 +4,4 @@ mod parser;
 #[cfg(test)]
 mod tests;
 
-pub use parser::{CmXmlParser, XmlParserOpts};
+pub use parser::{CmXmlParser, XmlOutput, XmlParserOpts};
@@ -1,12 +1,15 @@
-use anyhow::{Result, bail, ensure};
+use anyhow::{Context, Result, bail, ensure, format_err};
 use roxmltree::{Document, Node};
 use std::cmp::min;
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::fmt::Display;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::str::FromStr;
 
+use crate::error::DoviError;
 use crate::rpu::extension_metadata::{blocks::*, primaries};
 use crate::rpu::generate::{GenerateConfig, ShotFrameEdit, VideoShot};
 use crate::rpu::vdr_dm_data::CmVersion;
@@ -30,6 +33,22 @@ pub struct CmXmlParser {
 pub struct XmlParserOpts {
     pub canvas_width: Option<u16>,
     pub canvas_height: Option<u16>,
+
+    /// `Output` to parse, by name or index. Defaults to the first one
+    pub output: Option<String>,
+    /// Video track to parse, by name or index. Defaults to the first one
+    pub track: Option<String>,
+}
+
+/// Deliverable `Output` node of a CM XML
+#[derive(Default, Debug, Clone)]
+pub struct XmlOutput {
+    pub name: Option<String>,
+    pub canvas_aspect_ratio: Option<f32>,
+    pub image_aspect_ratio: Option<f32>,
+
+    /// Names of the video tracks
+    pub tracks: Vec<Option<String>>,
 }
 
 #[derive(Default, Debug)]
@@ -49,12 +68,16 @@ impl CmXmlParser {
     }
 
     pub fn new(s: String, opts: XmlParserOpts) -> Result<CmXmlParser> {
+        Self::parse(&s, opts).map_err(invalid_xml)
+    }
+
+    fn parse(s: &str, opts: XmlParserOpts) -> Result<CmXmlParser> {
         let mut parser = CmXmlParser {
             opts,
             ..Default::default()
         };
 
-        let doc = roxmltree::Document::parse(&s).unwrap();
+        let doc = Document::parse(s).context("Failed parsing XML document")?;
 
         parser.xml_version = parser.parse_xml_version(&doc)?;
 
@@ -65,14 +88,29 @@ impl CmXmlParser {
             parser.config.cm_version = CmVersion::V29;
         }
 
-        if let Some(output) = doc.descendants().find(|e| e.has_tag_name("Output")) {
+        let output = Self::select_node(
+            &Self::output_nodes(&doc),
+            parser.opts.output.as_deref(),
+            Self::output_name,
+            "Output",
+        )?;
+
+        if let Some(output) = output {
+            parser.apply_output_canvas(&output);
             parser.parse_global_level5(&output)?;
 
-            if let Some(video) = output.descendants().find(|e| e.has_tag_name("Video")) {
+            let video = Self::select_node(
+                &Self::track_nodes(&output),
+                parser.opts.track.as_deref(),
+                Self::track_name,
+                "Video track",
+            )?;
+
+            if let Some(video) = video {
                 let (max_frame_average_light_level, max_content_light_level) =
-                    parser.parse_level6(&video);
+                    parser.parse_level6(&video)?;
                 let (min_display_mastering_luminance, max_display_mastering_luminance) =
-                    parser.parse_mastering_display_metadata(&video);
+                    parser.parse_mastering_display_metadata(&video)?;
 
                 let source_min_pq =
                     nits_to_pq_12_bit(min_display_mastering_luminance as f64 / 10000.0);
@@ -86,7 +124,7 @@ impl CmXmlParser {
                     max_content_light_level,
                     max_frame_average_light_level,
                 });
-                parser.config.level254 = parser.parse_level254(&video);
+                parser.config.level254 = parser.parse_level254(&video)?;
 
                 parser.add_level11(&video)?;
 
@@ -111,6 +149,134 @@ impl CmXmlParser {
         Ok(parser)
     }
 
+    /// Lists the `Output` nodes and their video tracks
+    pub fn list_outputs_from_file<P: AsRef<Path>>(file_path: P) -> Result<Vec<XmlOutput>> {
+        let mut s = String::new();
+        File::open(file_path)?.read_to_string(&mut s)?;
+
+        Self::list_outputs(&s)
+    }
+
+    /// Lists the `Output` nodes and their video tracks
+    pub fn list_outputs(s: &str) -> Result<Vec<XmlOutput>> {
+        let doc = Document::parse(s)
+            .context("Failed parsing XML document")
+            .map_err(invalid_xml)?;
+
+        Ok(Self::output_nodes(&doc)
+            .iter()
+            .map(|output| XmlOutput {
+                name: Self::output_name(output).map(String::from),
+                canvas_aspect_ratio: Self::parse_aspect_ratio(output, "CanvasAspectRatio"),
+                image_aspect_ratio: Self::parse_aspect_ratio(output, "ImageAspectRatio"),
+                tracks: Self::track_nodes(output)
+                    .iter()
+                    .map(|track| Self::track_name(track).map(String::from))
+                    .collect(),
+            })
+            .collect())
+    }
+
+    fn output_nodes<'a, 'input>(doc: &'a Document<'input>) -> Vec<Node<'a, 'input>> {
+        doc.descendants()
+            .filter(|e| e.has_tag_name("Output"))
+            .collect()
+    }
+
+    /// Name from the CMv2.9 `name` attribute, or the CMv4.0 `CompositionName`
+    fn output_name<'a>(output: &Node<'a, '_>) -> Option<&'a str> {
+        output.attribute("name").or_else(|| {
+            output
+                .children()
+                .find(|e| e.has_tag_name("CompositionName"))
+                .and_then(|e| e.text())
+        })
+    }
+
+    /// `Track` nodes of the output, or the `Video` nodes when there are no tracks
+    fn track_nodes<'a, 'input>(output: &Node<'a, 'input>) -> Vec<Node<'a, 'input>> {
+        let videos = output.descendants().filter(|e| e.has_tag_name("Video"));
+        let tracks: Vec<_> = videos
+            .clone()
+            .flat_map(|video| video.children().filter(|e| e.has_tag_name("Track")))
+            .collect();
+
+        if tracks.is_empty() {
+            videos.collect()
+        } else {
+            tracks
+        }
+    }
+
+    /// Name from the CMv2.9 `name` attribute, or the CMv4.0 `TrackName`
+    fn track_name<'a>(track: &Node<'a, '_>) -> Option<&'a str> {
+        track.attribute("name").or_else(|| {
+            track
+                .children()
+                .find(|e| e.has_tag_name("TrackName"))
+                .and_then(|e| e.text())
+        })
+    }
+
+    /// Selects a node by name, or by index if no node matches the name.
+    /// Defaults to the first node when there is no selector.
+    fn select_node<'a, 'input>(
+        nodes: &[Node<'a, 'input>],
+        selector: Option<&str>,
+        name_fn: fn(&Node<'a, 'input>) -> Option<&'a str>,
+        kind: &str,
+    ) -> Result<Option<Node<'a, 'input>>> {
+        let Some(selector) = selector else {
+            return Ok(nodes.first().copied());
+        };
+
+        let by_name = nodes.iter().find(|n| name_fn(n) == Some(selector));
+        let by_index = || {
+            selector
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| nodes.get(index))
+        };
+
+        if let Some(node) = by_name.or_else(by_index) {
+            Ok(Some(*node))
+        } else {
+            let available = nodes
+                .iter()
+                .enumerate()
+                .map(|(i, n)| format!("{i}: {}", name_fn(n).unwrap_or("(unnamed)")))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            bail!("Could not find {kind} `{selector}`. Available: [{available}]");
+        }
+    }
+
+    fn parse_aspect_ratio(output: &Node, tag: &str) -> Option<f32> {
+        output
+            .children()
+            .find(|e| e.has_tag_name(tag))
+            .and_then(|e| e.text())
+            .and_then(|v| v.trim().parse::<f32>().ok())
+    }
+
+    /// Derives the missing canvas dimension from the output's canvas aspect ratio
+    fn apply_output_canvas(&mut self, output: &Node) {
+        let Some(canvas_ar) = Self::parse_aspect_ratio(output, "CanvasAspectRatio") else {
+            return;
+        };
+
+        match (self.opts.canvas_width, self.opts.canvas_height) {
+            (Some(width), None) => {
+                self.opts.canvas_height = Some((width as f32 / canvas_ar).round() as u16);
+            }
+            (None, Some(height)) => {
+                self.opts.canvas_width = Some((height as f32 * canvas_ar).round() as u16);
+            }
+            _ => {}
+        }
+    }
+
     fn parse_xml_version(&self, doc: &Document) -> Result<u16> {
         if let Some(node) = doc.descendants().find(|e| e.has_tag_name("DolbyLabsMDF")) {
             let version_attr = node.attribute("version");
@@ -135,9 +301,9 @@ impl CmXmlParser {
                 .iter()
                 .rev()
                 .enumerate()
-                .fold(0, |rev, (i, v)| {
-                    rev + (v.parse::<u16>().unwrap() << (i * 4))
-                });
+                .try_fold(0, |rev, (i, v)| {
+                    parse_value::<u16>(v, "Version").map(|v| rev + (v << (i * 4)))
+                })?;
 
             if rev >= 0x402 {
                 match rev {
@@ -163,58 +329,31 @@ impl CmXmlParser {
         }
     }
 
-    fn parse_level6(&self, video: &Node) -> (u16, u16) {
+    fn parse_level6(&self, video: &Node) -> Result<(u16, u16)> {
         if let Some(node) = video.descendants().find(|e| e.has_tag_name("Level6")) {
-            let maxfall = if let Some(fall) = node.children().find(|e| e.has_tag_name("MaxFALL")) {
-                fall.text()
-                    .map_or(0, |e| e.parse::<f32>().unwrap().round() as u16)
-            } else {
-                0
-            };
+            let maxfall =
+                parse_optional_child::<f32>(&node, "MaxFALL")?.map_or(0, |v| v.round() as u16);
+            let maxcll =
+                parse_optional_child::<f32>(&node, "MaxCLL")?.map_or(0, |v| v.round() as u16);
 
-            let maxcll = if let Some(cll) = node.children().find(|e| e.has_tag_name("MaxCLL")) {
-                cll.text()
-                    .map_or(0, |e| e.parse::<f32>().unwrap().round() as u16)
-            } else {
-                0
-            };
-
-            (maxfall, maxcll)
+            Ok((maxfall, maxcll))
         } else {
-            (0, 0)
+            Ok((0, 0))
         }
     }
 
-    fn parse_mastering_display_metadata(&self, video: &Node) -> (u16, u16) {
+    fn parse_mastering_display_metadata(&self, video: &Node) -> Result<(u16, u16)> {
         if let Some(node) = video
             .descendants()
             .find(|e| e.has_tag_name("MasteringDisplay"))
         {
-            let min = if let Some(min_brightness) = node
-                .children()
-                .find(|e| e.has_tag_name("MinimumBrightness"))
-            {
-                min_brightness.text().map_or(0, |e| {
-                    let v = e.parse::<f32>().unwrap();
-                    (v * 10000.0) as u16
-                })
-            } else {
-                0
-            };
+            let min = parse_optional_child::<f32>(&node, "MinimumBrightness")?
+                .map_or(0, |v| (v * 10000.0) as u16);
+            let max = parse_optional_child::<u16>(&node, "PeakBrightness")?.unwrap_or(0);
 
-            let max = if let Some(max_brightness) =
-                node.children().find(|e| e.has_tag_name("PeakBrightness"))
-            {
-                max_brightness
-                    .text()
-                    .map_or(0, |e| e.parse::<u16>().unwrap())
-            } else {
-                0
-            };
-
-            (min, max)
+            Ok((min, max))
         } else {
-            (0, 0)
+            Ok((0, 0))
         }
     }
 
@@ -225,65 +364,20 @@ impl CmXmlParser {
             .filter(|e| e.has_tag_name("TargetDisplay"));
 
         for target_node in target_display_nodes {
-            let id = target_node
-                .children()
-                .find(|e| e.has_tag_name("ID"))
-                .unwrap()
-                .text()
-                .unwrap()
-                .to_string();
+            let id = child_text(&target_node, "ID")?.to_string();
+            let peak_nits = parse_child::<u16>(&target_node, "PeakBrightness")?;
+            let min_nits = parse_child::<f64>(&target_node, "MinimumBrightness")?;
 
-            let peak_nits = target_node
-                .children()
-                .find(|e| e.has_tag_name("PeakBrightness"))
-                .unwrap()
-                .text()
-                .unwrap()
-                .parse::<u16>()
-                .unwrap();
-
-            let min_nits = target_node
-                .children()
-                .find(|e| e.has_tag_name("MinimumBrightness"))
-                .unwrap()
-                .text()
-                .unwrap()
-                .parse::<f64>()
-                .unwrap();
-
-            let primary_red = target_node
-                .descendants()
-                .find(|e| e.has_tag_name("Red"))
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let primary_green = target_node
-                .descendants()
-                .find(|e| e.has_tag_name("Green"))
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let primary_blue = target_node
-                .descendants()
-                .find(|e| e.has_tag_name("Blue"))
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let primary_white = target_node
-                .children()
-                .find(|e| e.has_tag_name("WhitePoint"))
-                .unwrap()
-                .text()
-                .unwrap();
+            let primary_red = descendant_text(&target_node, "Red")?;
+            let primary_green = descendant_text(&target_node, "Green")?;
+            let primary_blue = descendant_text(&target_node, "Blue")?;
+            let primary_white = child_text(&target_node, "WhitePoint")?;
 
-            let primaries: Vec<f64> = [primary_red, primary_green, primary_blue, primary_white]
-                .join(&self.separator.to_string())
-                .split(self.separator)
-                .map(|v| v.parse::<f64>().unwrap())
-                .collect();
+            let primaries: Vec<f64> = self.parse_values(
+                &[primary_red, primary_green, primary_blue, primary_white]
+                    .join(&self.separator.to_string()),
+                "Primaries",
+            )?;
 
             ensure!(
                 primaries.len() == 8,
@@ -291,16 +385,10 @@ impl CmXmlParser {
             );
 
             let include_target = if self.xml_version >= 0x500 {
-                let application_type = target_node
-                    .children()
-                    .find(|e| e.has_tag_name("ApplicationType"));
-
-                ensure!(
-                    application_type.is_some(),
-                    format!("XML v5.0+: Missing ApplicationType for Target display ID {id}")
-                );
-
-                let application_type = application_type.unwrap().text().unwrap().to_string();
+                let application_type =
+                    child_text(&target_node, "ApplicationType").with_context(|| {
+                        format!("XML v5.0+: Missing ApplicationType for Target display ID {id}")
+                    })?;
 
                 // Only parse HOME targets
                 application_type == "HOME"
@@ -324,49 +412,25 @@ impl CmXmlParser {
         Ok(targets)
     }
 
-    fn parse_level254(&self, video: &Node) -> Option<ExtMetadataBlockLevel254> {
+    fn parse_level254(&self, video: &Node) -> Result<Option<ExtMetadataBlockLevel254>> {
         if let Some(node) = video.descendants().find(|e| e.has_tag_name("Level254")) {
-            let dm_mode = if let Some(dmm) = node.children().find(|e| e.has_tag_name("DMMode")) {
-                dmm.text().map_or(0, |e| e.parse::<u8>().unwrap())
-            } else {
-                0
-            };
-
-            let dm_version_index =
-                if let Some(dmv) = node.children().find(|e| e.has_tag_name("DMVersion")) {
-                    dmv.text().map_or(2, |e| e.parse::<u8>().unwrap())
-                } else {
-                    2
-                };
+            let dm_mode = parse_optional_child::<u8>(&node, "DMMode")?.unwrap_or(0);
+            let dm_version_index = parse_optional_child::<u8>(&node, "DMVersion")?.unwrap_or(2);
 
-            Some(ExtMetadataBlockLevel254 {
+            Ok(Some(ExtMetadataBlockLevel254 {
                 dm_mode,
                 dm_version_index,
-            })
+            }))
         } else {
             // No L254 in the case of CM v2.9
-            None
+            Ok(None)
         }
     }
 
     fn add_level11(&mut self, video: &Node) -> Result<()> {
         if let Some(node) = video.descendants().find(|e| e.has_tag_name("Level11")) {
-            let content_type: Option<u8> = if let Some(content_type_node) =
-                node.children().find(|e| e.has_tag_name("ContentType"))
-            {
-                content_type_node.text().map(|e| e.parse::<u8>().unwrap())
-            } else {
-                None
-            };
-
-            let whitepoint: Option<u8> = if let Some(wp_node) = node
-                .children()
-                .find(|e| e.has_tag_name("IntendedWhitePoint"))
-            {
-                wp_node.text().map(|e| e.parse::<u8>().unwrap())
-            } else {
-                None
-            };
+            let content_type = parse_optional_child::<u8>(&node, "ContentType")?;
+            let whitepoint = parse_optional_child::<u8>(&node, "IntendedWhitePoint")?;
 
             if let (Some(content_type), Some(whitepoint)) = (content_type, whitepoint) {
                 self.config
@@ -387,33 +451,13 @@ impl CmXmlParser {
             .filter(|e| e.has_tag_name("Shot"))
             .map(|n| {
                 let mut shot = VideoShot {
-                    id: n
-                        .children()
-                        .find(|e| e.has_tag_name("UniqueID"))
-                        .unwrap()
-                        .text()
-                        .unwrap()
-                        .to_string(),
+                    id: child_text(&n, "UniqueID")?.to_string(),
                     ..Default::default()
                 };
 
                 if let Some(record) = n.children().find(|e| e.has_tag_name("Record")) {
-                    shot.start = record
-                        .children()
-                        .find(|e| e.has_tag_name("In"))
-                        .unwrap()
-                        .text()
-                        .unwrap()
-                        .parse::<usize>()
-                        .unwrap();
-                    shot.duration = record
-                        .children()
-                        .find(|e| e.has_tag_name("Duration"))
-                        .unwrap()
-                        .text()
-                        .unwrap()
-                        .parse::<usize>()
-                        .unwrap();
+                    shot.start = parse_child::<usize>(&record, "In")?;
+                    shot.duration = parse_child::<usize>(&record, "Duration")?;
                 }
 
                 shot.metadata_blocks = self.parse_shot_trims(&n)?;
@@ -421,14 +465,7 @@ impl CmXmlParser {
                 let frames = n.children().filter(|e| e.has_tag_name("Frame"));
 
                 for frame in frames {
-                    let edit_offset = frame
-                        .children()
-                        .find(|e| e.has_tag_name("EditOffset"))
-                        .unwrap()
-                        .text()
-                        .unwrap()
-                        .parse::<usize>()
-                        .unwrap();
+                    let edit_offset = parse_child::<usize>(&frame, "EditOffset")?;
 
                     shot.frame_edits.push(ShotFrameEdit {
                         edit_offset,
@@ -460,7 +497,7 @@ impl CmXmlParser {
                     .filter(|e| e.has_attribute("level"));
 
                 for level_node in level_nodes {
-                    let level = level_node.attribute("level").unwrap();
+                    let level = level_node.attribute("level").unwrap_or_default();
                     self.parse_trim_levels(&level_node, level, &mut metadata_blocks)?;
                 }
             } else {
@@ -469,7 +506,7 @@ impl CmXmlParser {
                     .filter(|e| e.has_tag_name("DolbyEDR") && e.has_attribute("level"));
 
                 for edr in edr_nodes {
-                    let level = edr.attribute("level").unwrap();
+                    let level = edr.attribute("level").unwrap_or_default();
                     self.parse_trim_levels(&edr, level, &mut metadata_blocks)?;
                 }
             };
@@ -502,23 +539,8 @@ impl CmXmlParser {
     }
 
     pub fn parse_global_level5(&mut self, output: &Node) -> Result<()> {
-        let canvas_ar = if let Some(canvas_ar) = output
-            .children()
-            .find(|e| e.has_tag_name("CanvasAspectRatio"))
-        {
-            canvas_ar.text().and_then(|v| v.parse::<f32>().ok())
-        } else {
-            None
-        };
-
-        let image_ar = if let Some(image_ar) = output
-            .children()
-            .find(|e| e.has_tag_name("ImageAspectRatio"))
-        {
-            image_ar.text().and_then(|v| v.parse::<f32>().ok())
-        } else {
-            None
-        };
+        let canvas_ar = Self::parse_aspect_ratio(output, "CanvasAspectRatio");
+        let image_ar = Self::parse_aspect_ratio(output, "ImageAspectRatio");
 
         if let (Some(c_ar), Some(i_ar)) = (canvas_ar, image_ar) {
             self.config.level5 = self
@@ -537,9 +559,11 @@ impl CmXmlParser {
 
             let length = if index == 255 { 21 } else { 5 };
 
+            let target_display_index = parse_value::<u8>(&target.id, "TargetDisplay ID")?;
+
             let mut block = ExtMetadataBlockLevel10 {
                 length,
-                target_display_index: target.id.parse::<u8>().unwrap(),
+                target_display_index,
                 target_max_pq: min(4095, nits_to_pq_12_bit(target.peak_nits)),
                 target_min_pq: min(4095, nits_to_pq_12_bit(target.min_nits)),
                 target_primary_index: index,
@@ -552,7 +576,7 @@ impl CmXmlParser {
             }
 
             // Only allow custom L10
-            if !PRESET_TARGET_DISPLAYS.contains(&id.parse::<u8>().unwrap()) {
+            if !PRESET_TARGET_DISPLAYS.contains(&parse_value::<u8>(id, "TargetDisplay ID")?) {
                 self.config
                     .default_metadata_blocks
                     .push(ExtMetadataBlock::Level10(block));
@@ -563,22 +587,17 @@ impl CmXmlParser {
     }
 
     pub fn parse_level1_trim(&self, node: &Node) -> Result<ExtMetadataBlockLevel1> {
-        let measurements = node
-            .children()
-            .find(|e| e.has_tag_name("ImageCharacter"))
-            .unwrap()
-            .text()
-            .unwrap();
-        let measurements: Vec<&str> = measurements.split(self.separator).collect();
+        let measurements: Vec<f32> =
+            self.parse_values(child_text(node, "ImageCharacter")?, "ImageCharacter")?;
 
         ensure!(
             measurements.len() == 3,
             "invalid L1 trim: should be 3 values"
         );
 
-        let min_pq = (measurements[0].parse::<f32>().unwrap() * 4095.0).round() as u16;
-        let avg_pq = (measurements[1].parse::<f32>().unwrap() * 4095.0).round() as u16;
-        let max_pq = (measurements[2].parse::<f32>().unwrap() * 4095.0).round() as u16;
+        let min_pq = (measurements[0] * 4095.0).round() as u16;
+        let avg_pq = (measurements[1] * 4095.0).round() as u16;
+        let max_pq = (measurements[2] * 4095.0).round() as u16;
 
         Ok(ExtMetadataBlockLevel1::from_stats_cm_version(
             min_pq,
@@ -589,32 +608,19 @@ impl CmXmlParser {
     }
 
     pub fn parse_level2_trim(&self, node: &Node) -> Result<ExtMetadataBlockLevel2> {
-        let target_id = node
-            .children()
-            .find(|e| e.has_tag_name("TID"))
-            .unwrap()
-            .text()
-            .unwrap()
-            .to_string();
-
-        let trim = node
-            .children()
-            .find(|e| e.has_tag_name("Trim"))
-            .unwrap()
-            .text()
-            .unwrap();
-        let trim: Vec<&str> = trim.split(self.separator).collect();
+        let target_id = child_text(node, "TID")?;
+        let trim: Vec<f32> = self.parse_values(child_text(node, "Trim")?, "Trim")?;
 
         let target_display = self
             .target_displays
-            .get(&target_id)
-            .expect("No target display found for L2 trim");
+            .get(target_id)
+            .ok_or_else(|| format_err!("No target display found for L2 trim TID {target_id}"))?;
 
         ensure!(trim.len() == 9, "invalid L2 trim: should be 9 values");
 
-        let trim_lift = trim[3].parse::<f32>().unwrap();
-        let trim_gain = trim[4].parse::<f32>().unwrap();
-        let trim_gamma = trim[5].parse::<f32>().unwrap().clamp(-1.0, 1.0);
+        let trim_lift = trim[3];
+        let trim_gain = trim[4];
+        let trim_gamma = trim[5].clamp(-1.0, 1.0);
 
         let trim_slope = min(
             4095,
@@ -629,18 +635,9 @@ impl CmXmlParser {
             4095,
             (((2.0 / (1.0 + trim_gamma / 2.0) - 2.0) * 2048.0) + 2048.0).round() as u16,
         );
-        let trim_chroma_weight = min(
-            4095,
-            ((trim[6].parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
-        );
-        let trim_saturation_gain = min(
-            4095,
-            ((trim[7].parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
-        );
-        let ms_weight = min(
-            4095,
-            ((trim[8].parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as i16,
-        );
+        let trim_chroma_weight = min(4095, ((trim[6] * 2048.0) + 2048.0).round() as u16);
+        let trim_saturation_gain = min(4095, ((trim[7] * 2048.0) + 2048.0).round() as u16);
+        let ms_weight = min(4095, ((trim[8] * 2048.0) + 2048.0).round() as i16);
 
         Ok(ExtMetadataBlockLevel2 {
             trim_slope,
@@ -654,15 +651,9 @@ impl CmXmlParser {
     }
 
     pub fn parse_level3_trim(&self, node: &Node) -> Result<ExtMetadataBlockLevel3> {
-        let measurements = node
-            .children()
-            .find(|e| e.has_tag_name("L1Offset"))
-            .unwrap()
-            .text()
-            .unwrap();
-
         // [min, avg, max]
-        let measurements: Vec<&str> = measurements.split(self.separator).collect();
+        let measurements: Vec<f32> =
+            self.parse_values(child_text(node, "L1Offset")?, "L1Offset")?;
 
         ensure!(
             measurements.len() == 3,
@@ -670,28 +661,20 @@ impl CmXmlParser {
         );
 
         Ok(ExtMetadataBlockLevel3 {
-            min_pq_offset: ((measurements[0].parse::<f32>().unwrap() * 2048.0) + 2048.0).round()
-                as u16,
-            avg_pq_offset: ((measurements[1].parse::<f32>().unwrap() * 2048.0) + 2048.0).round()
-                as u16,
-            max_pq_offset: ((measurements[2].parse::<f32>().unwrap() * 2048.0) + 2048.0).round()
-                as u16,
+            min_pq_offset: ((measurements[0] * 2048.0) + 2048.0).round() as u16,
+            avg_pq_offset: ((measurements[1] * 2048.0) + 2048.0).round() as u16,
+            max_pq_offset: ((measurements[2] * 2048.0) + 2048.0).round() as u16,
         })
     }
 
     pub fn parse_level5_trim(&self, node: &Node) -> Result<ExtMetadataBlockLevel5> {
-        let ratios = node
-            .children()
-            .find(|e| e.has_tag_name("AspectRatios"))
-            .unwrap()
-            .text()
-            .unwrap();
-        let ratios: Vec<&str> = ratios.split(self.separator).collect();
+        let ratios: Vec<f32> =
+            self.parse_values(child_text(node, "AspectRatios")?, "AspectRatios")?;
 
         ensure!(ratios.len() == 2, "invalid L5 trim: should be 2 values");
 
-        let canvas_ar = ratios[0].parse::<f32>().unwrap();
-        let image_ar = ratios[1].parse::<f32>().unwrap();
+        let canvas_ar = ratios[0];
+        let image_ar = ratios[1];
 
         Ok(self
             .calculate_level5_metadata(canvas_ar, image_ar)
@@ -700,32 +683,19 @@ impl CmXmlParser {
     }
 
     pub fn parse_level8_trim(&self, node: &Node) -> Result<ExtMetadataBlockLevel8> {
-        let target_id = node
-            .children()
-            .find(|e| e.has_tag_name("TID"))
-            .unwrap()
-            .text()
-            .unwrap()
-            .to_string();
-
-        let trim = node
-            .children()
-            .find(|e| e.has_tag_name("L8Trim"))
-            .unwrap()
-            .text()
-            .unwrap();
-        let trim: Vec<&str> = trim.split(self.separator).collect();
+        let target_id = child_text(node, "TID")?;
+        let trim: Vec<f32> = self.parse_values(child_text(node, "L8Trim")?, "L8Trim")?;
 
         let target_display = self
             .target_displays
-            .get(&target_id)
-            .expect("No target display found for L8 trim");
+            .get(target_id)
+            .ok_or_else(|| format_err!("No target display found for L8 trim TID {target_id}"))?;
 
         ensure!(trim.len() == 6, "Invalid L8 trim: should be 6 values");
 
-        let trim_lift = trim[0].parse::<f32>().unwrap();
-        let trim_gain = trim[1].parse::<f32>().unwrap();
-        let trim_gamma = trim[2].parse::<f32>().unwrap().clamp(-1.0, 1.0);
+        let trim_lift = trim[0];
+        let trim_gain = trim[1];
+        let trim_gamma = trim[2].clamp(-1.0, 1.0);
 
         let trim_slope = min(
             4095,
@@ -740,59 +710,27 @@ impl CmXmlParser {
             4095,
             (((2.0 / (1.0 + trim_gamma / 2.0) - 2.0) * 2048.0) + 2048.0).round() as u16,
         );
-        let trim_chroma_weight = min(
-            4095,
-            ((trim[3].parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
-        );
-        let trim_saturation_gain = min(
-            4095,
-            ((trim[4].parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
-        );
-        let ms_weight = min(
-            4095,
-            ((trim[5].parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
-        );
+        let trim_chroma_weight = min(4095, ((trim[3] * 2048.0) + 2048.0).round() as u16);
+        let trim_saturation_gain = min(4095, ((trim[4] * 2048.0) + 2048.0).round() as u16);
+        let ms_weight = min(4095, ((trim[5] * 2048.0) + 2048.0).round() as u16);
 
-        let mid_contrast_bias_text = node
-            .children()
-            .find(|e| e.has_tag_name("MidContrastBias"))
-            .unwrap()
-            .text()
-            .unwrap();
-
-        let highlight_clipping_text = node
-            .children()
-            .find(|e| e.has_tag_name("HighlightClipping"))
-            .unwrap()
-            .text()
-            .unwrap();
-
-        let target_mid_contrast = min(
-            4095,
-            ((mid_contrast_bias_text.parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
-        );
+        let mid_contrast_bias = parse_child::<f32>(node, "MidContrastBias")?;
+        let highlight_clipping = parse_child::<f32>(node, "HighlightClipping")?;
 
+        let target_mid_contrast = min(4095, ((mid_contrast_bias * 2048.0) + 2048.0).round() as u16);
         let clip_trim = min(
             4095,
-            ((highlight_clipping_text.parse::<f32>().unwrap() * 2048.0) + 2048.0).round() as u16,
+            ((highlight_clipping * 2048.0) + 2048.0).round() as u16,
         );
 
         // L8 SaturationVectorField
-        let satvec_text = node
-            .children()
-            .find(|e| e.has_tag_name("SaturationVectorField"))
-            .unwrap()
-            .text()
-            .unwrap();
-
-        let satvec: Vec<u8> = satvec_text
-            .split(self.separator)
-            .map(|v| {
-                min(
-                    255,
-                    ((v.parse::<f32>().unwrap() * 128.0) + 128.0).round() as u8,
-                )
-            })
+        let satvec: Vec<u8> = self
+            .parse_values::<f32>(
+                child_text(node, "SaturationVectorField")?,
+                "SaturationVectorField",
+            )?
+            .into_iter()
+            .map(|v| min(255, ((v * 128.0) + 128.0).round() as u8))
             .collect();
 
         ensure!(
@@ -801,21 +739,10 @@ impl CmXmlParser {
         );
 
         // L8 HueVectorField
-        let huevec_text = node
-            .children()
-            .find(|e| e.has_tag_name("HueVectorField"))
-            .unwrap()
-            .text()
-            .unwrap();
-
-        let huevec: Vec<u8> = huevec_text
-            .split(self.separator)
-            .map(|v| {
-                min(
-                    255,
-                    ((v.parse::<f32>().unwrap() * 128.0) + 128.0).round() as u8,
-                )
-            })
+        let huevec: Vec<u8> = self
+            .parse_values::<f32>(child_text(node, "HueVectorField")?, "HueVectorField")?
+            .into_iter()
+            .map(|v| min(255, ((v * 128.0) + 128.0).round() as u8))
             .collect();
 
         ensure!(
@@ -901,17 +828,10 @@ impl CmXmlParser {
 
     /// Mastering display primaries
     fn parse_level9_trim(&self, node: &Node) -> Result<ExtMetadataBlockLevel9> {
-        let source_color_primary = node
-            .children()
-            .find(|e| e.has_tag_name("SourceColorPrimary"))
-            .unwrap()
-            .text()
-            .unwrap();
-
-        let primaries: Vec<f64> = source_color_primary
-            .split(self.separator)
-            .map(|v| v.parse::<f64>().unwrap())
-            .collect();
+        let primaries: Vec<f64> = self.parse_values(
+            child_text(node, "SourceColorPrimary")?,
+            "SourceColorPrimary",
+        )?;
         ensure!(
             primaries.len() == 8,
             "Invalid L9 SourceColorPrimary: should be 8 values"
@@ -951,35 +871,75 @@ impl CmXmlParser {
             "Missing canvas height to calculate L5"
         );
 
-        let cw = self.opts.canvas_width.unwrap() as f32;
-        let ch = self.opts.canvas_height.unwrap() as f32;
-
-        let mut calculated_level5 = ExtMetadataBlockLevel5::default();
+        let cw = self.opts.canvas_width.unwrap();
+        let ch = self.opts.canvas_height.unwrap();
 
-        if (canvas_ar - image_ar).abs() < f32::EPSILON {
-            // No AR difference, zero offsets
-        } else if image_ar > canvas_ar {
-            let image_h = (ch * (canvas_ar / image_ar)).round();
-            let diff = ch - image_h;
-            let offset_top = (diff / 2.0).trunc();
-            let offset_bottom = diff - offset_top;
-
-            calculated_level5.active_area_top_offset = offset_top as u16;
-            calculated_level5.active_area_bottom_offset = offset_bottom as u16;
-        } else {
-            let image_w = (cw * (image_ar / canvas_ar)).round();
-            let diff = cw - image_w;
-            let offset_left = (diff / 2.0).trunc();
-            let offset_right = diff - offset_left;
-
-            calculated_level5.active_area_left_offset = offset_left as u16;
-            calculated_level5.active_area_right_offset = offset_right as u16;
-        }
-
-        Ok(calculated_level5)
+        Ok(ExtMetadataBlockLevel5::from_aspect_ratios(
+            cw, ch, canvas_ar, image_ar,
+        ))
     }
 
     pub fn is_cmv4(&self) -> bool {
         self.xml_version >= 0x402
     }
+
+    /// Parses a list of values delimited by the XML version's separator
+    fn parse_values<T: FromStr>(&self, text: &str, tag: &str) -> Result<Vec<T>>
+    where
+        T::Err: Display,
+    {
+        text.split(self.separator)
+            .map(|v| parse_value(v, tag))
+            .collect()
+    }
+}
+
+/// Flattens the error chain into `DoviError::InvalidXml`
+fn invalid_xml(err: anyhow::Error) -> anyhow::Error {
+    DoviError::InvalidXml(format!("{err:#}")).into()
+}
+
+fn parse_value<T: FromStr>(value: &str, tag: &str) -> Result<T>
+where
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|e| format_err!("Invalid {tag} value `{value}`: {e}"))
+}
+
+/// Text of the first child element with the tag name
+fn child_text<'a>(node: &Node<'a, '_>, tag: &str) -> Result<&'a str> {
+    node.children()
+        .find(|e| e.has_tag_name(tag))
+        .map(|e| e.text().unwrap_or_default())
+        .ok_or_else(|| format_err!("Missing {tag} in {} node", node.tag_name().name()))
+}
+
+/// Text of the first descendant element with the tag name
+fn descendant_text<'a>(node: &Node<'a, '_>, tag: &str) -> Result<&'a str> {
+    node.descendants()
+        .find(|e| e.has_tag_name(tag))
+        .map(|e| e.text().unwrap_or_default())
+        .ok_or_else(|| format_err!("Missing {tag} in {} node", node.tag_name().name()))
+}
+
+fn parse_child<T: FromStr>(node: &Node, tag: &str) -> Result<T>
+where
+    T::Err: Display,
+{
+    parse_value(child_text(node, tag)?, tag)
+}
+
+/// Parses the child element if present and not empty
+fn parse_optional_child<T: FromStr>(node: &Node, tag: &str) -> Result<Option<T>>
+where
+    T::Err: Display,
+{
+    node.children()
+        .find(|e| e.has_tag_name(tag))
+        .and_then(|e| e.text())
+        .map(|text| parse_value(text, tag))
+        .transpose()
 }
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use crate::error::DoviError;
 use crate::rpu::{extension_metadata::blocks::ExtMetadataBlock, vdr_dm_data::CmVersion};
 
 use super::{CmXmlParser, XmlParserOpts};
@@ -19,6 +20,7 @@ fn parse_cmv2_9() -> Result<()> {
     let opts = XmlParserOpts {
         canvas_width: Some(3840),
         canvas_height: Some(2160),
+        ..Default::default()
     };
     let parser = CmXmlParser::parse_file(assets_path.join("assets/tests/cmv2_9.xml"), opts)?;
 
@@ -138,6 +140,7 @@ fn parse_cmv4_0_2_with_l5() -> Result<()> {
     let opts = XmlParserOpts {
         canvas_width: Some(3840),
         canvas_height: Some(2160),
+        ..Default::default()
     };
 
     let parser = CmXmlParser::parse_file(assets_path.join("assets/tests/cmv4_0_2.xml"), opts)?;
@@ -326,3 +329,115 @@ fn parse_level6_decimals() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn list_multiple_outputs() -> Result<()> {
+    let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let assets_path = lib_path.parent().unwrap();
+
+    let outputs = CmXmlParser::list_outputs_from_file(
+        assets_path.join("assets/tests/cmv4_0_2_multiple_outputs.xml"),
+    )?;
+
+    assert_eq!(outputs.len(), 2);
+
+    assert_eq!(outputs[0].name.as_deref(), Some("Timeline 1"));
+    assert_eq!(outputs[0].tracks, vec![Some("V1".to_string())]);
+
+    assert_eq!(outputs[1].name.as_deref(), Some("Scope"));
+    assert_eq!(outputs[1].image_aspect_ratio, Some(2.4));
+    assert_eq!(
+        outputs[1].tracks,
+        vec![Some("V1".to_string()), Some("V2".to_string())]
+    );
+
+    // CMv2.9 names from attributes
+    let outputs = CmXmlParser::list_outputs_from_file(assets_path.join("assets/tests/cmv2_9.xml"))?;
+    assert_eq!(outputs[0].name.as_deref(), Some("Timeline 1"));
+    assert_eq!(outputs[0].tracks, vec![Some("V1".to_string())]);
+
+    Ok(())
+}
+
+#[test]
+fn select_output_and_track() -> Result<()> {
+    let lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let assets_path = lib_path.parent().unwrap();
+    let xml_path = assets_path.join("assets/tests/cmv4_0_2_multiple_outputs.xml");
+
+    // Defaults to the first output
+    let opts = XmlParserOpts {
+        canvas_width: Some(3840),
+        canvas_height: Some(2160),
+        ..Default::default()
+    };
+    let config = CmXmlParser::parse_file(&xml_path, opts)?.config;
+    assert_eq!(config.length, 259);
+    assert_eq!(config.level5.get_offsets(), (480, 480, 0, 0));
+
+    // By name, canvas height derived from the output's canvas aspect ratio
+    let opts = XmlParserOpts {
+        canvas_width: Some(3840),
+        output: Some("Scope".to_string()),
+        ..Default::default()
+    };
+    let config = CmXmlParser::parse_file(&xml_path, opts)?.config;
+    assert_eq!(config.length, 259);
+    assert_eq!(config.level5.get_offsets(), (0, 0, 280, 280));
+
+    // By index, second track
+    let opts = XmlParserOpts {
+        output: Some("1".to_string()),
+        track: Some("V2".to_string()),
+        ..Default::default()
+    };
+    let config = CmXmlParser::parse_file(&xml_path, opts)?.config;
+    assert_eq!(config.length, 120);
+    assert_eq!(config.shots.len(), 1);
+
+    let opts = XmlParserOpts {
+        output: Some("Flat".to_string()),
+        ..Default::default()
+    };
+    let err = CmXmlParser::parse_file(&xml_path, opts).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Could not find Output `Flat`. Available: [0: Timeline 1, 1: Scope]"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn malformed_xml_errors() {
+    let opts = XmlParserOpts::default();
+    let err = CmXmlParser::new("<DolbyLabsMDF><Version>".to_string(), opts).unwrap_err();
+    assert!(err.to_string().starts_with("Failed parsing XML document"));
+
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<DolbyLabsMDF>
+  <Version>4.0.2</Version>
+  <Outputs>
+    <Output>
+      <Video>
+        <Track>
+          <Shot>
+            <Record>
+              <In>abc</In>
+              <Duration>10</Duration>
+            </Record>
+          </Shot>
+        </Track>
+      </Video>
+    </Output>
+  </Outputs>
+</DolbyLabsMDF>"#;
+
+    let opts = XmlParserOpts::default();
+    let err = CmXmlParser::new(xml.to_string(), opts).unwrap_err();
+    assert_eq!(err.to_string(), "Missing UniqueID in Shot node");
+    assert!(matches!(
+        DoviError::find(&err),
+        Some(DoviError::InvalidXml(_))
+    ));
+}
@@ -0,0 +1,426 @@
+//! Python bindings for the `dolby_vision` crate, built with the `python` feature.
+//!
+//! Metadata structs are exposed as dicts, in the same format as the `serde` serialization.
+#![cfg(feature = "python")]
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+
+use dolby_vision::rpu::ConversionMode;
+use dolby_vision::rpu::dovi_rpu::DoviRpu;
+use dolby_vision::rpu::extension_metadata::blocks::ExtMetadataBlock;
+use dolby_vision::rpu::extension_metadata::{DmData, WithExtMetadataBlocks};
+use dolby_vision::rpu::generate::GenerateConfig;
+
+const OUT_NAL_HEADER: &[u8] = &[0, 0, 0, 1];
+const NAL_UNSPEC62_HEADER: &[u8] = &[0x7C, 0x01];
+
+/// Parsed Dolby Vision RPU
+#[pyclass(name = "DoviRpu", module = "dovi")]
+pub struct PyDoviRpu {
+    rpu: DoviRpu,
+}
+
+/// RPU conversion modes, see `DoviRpu.convert_with_mode`
+#[pyclass(name = "ConversionMode", module = "dovi", eq, eq_int, from_py_object)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyConversionMode {
+    Lossless,
+    ToMel,
+    To81,
+    To84,
+    To81MappingPreserved,
+}
+
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{e:#}"))
+}
+
+/// Converts a serializable value to Python objects, through JSON
+fn to_py_object<'py, T: serde::Serialize>(
+    py: Python<'py>,
+    value: &T,
+) -> PyResult<Bound<'py, PyAny>> {
+    let json = serde_json::to_string(value).map_err(|e| to_py_err(e.into()))?;
+
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+/// Converts a JSON string or Python object to a deserializable value
+fn from_py_object<T: serde::de::DeserializeOwned>(obj: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json: String = if obj.is_instance_of::<PyString>() {
+        obj.extract()?
+    } else {
+        obj.py()
+            .import("json")?
+            .call_method1("dumps", (obj,))?
+            .extract()?
+    };
+
+    serde_json::from_str(&json).map_err(|e| to_py_err(e.into()))
+}
+
+impl From<PyConversionMode> for ConversionMode {
+    fn from(mode: PyConversionMode) -> Self {
+        match mode {
+            PyConversionMode::Lossless => ConversionMode::Lossless,
+            PyConversionMode::ToMel => ConversionMode::ToMel,
+            PyConversionMode::To81 => ConversionMode::To81,
+            PyConversionMode::To84 => ConversionMode::To84,
+            PyConversionMode::To81MappingPreserved => ConversionMode::To81MappingPreserved,
+        }
+    }
+}
+
+impl From<DoviRpu> for PyDoviRpu {
+    fn from(rpu: DoviRpu) -> Self {
+        Self { rpu }
+    }
+}
+
+#[pymethods]
+impl PyDoviRpu {
+    /// Parses an unescaped HEVC UNSPEC62 NALU, starting with 0x7C01
+    #[staticmethod]
+    fn parse_unspec62_nalu(data: &[u8]) -> PyResult<Self> {
+        DoviRpu::parse_unspec62_nalu(data)
+            .map(Self::from)
+            .map_err(to_py_err)
+    }
+
+    /// Parses an unescaped RPU buffer, starting with 0x19
+    #[staticmethod]
+    fn parse_rpu(data: &[u8]) -> PyResult<Self> {
+        DoviRpu::parse_rpu(data).map(Self::from).map_err(to_py_err)
+    }
+
+    /// Parses an AV1 ITU-T T.35 metadata OBU payload
+    #[staticmethod]
+    fn parse_itu_t35_dovi_metadata_obu(data: &[u8]) -> PyResult<Self> {
+        DoviRpu::parse_itu_t35_dovi_metadata_obu(data)
+            .map(Self::from)
+            .map_err(to_py_err)
+    }
+
+    /// Deserializes a RPU from JSON, as returned by `to_json`
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serde_json::from_str::<DoviRpu>(json)
+            .map(Self::from)
+            .map_err(|e| to_py_err(e.into()))
+    }
+
+    #[getter]
+    fn dovi_profile(&self) -> u8 {
+        self.rpu.dovi_profile
+    }
+
+    #[getter]
+    fn el_type(&self) -> Option<String> {
+        self.rpu.el_type.as_ref().map(|el_type| el_type.to_string())
+    }
+
+    #[getter]
+    fn header<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py_object(py, &self.rpu.header)
+    }
+
+    #[getter]
+    fn rpu_data_mapping<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py_object(py, &self.rpu.rpu_data_mapping)
+    }
+
+    #[getter]
+    fn vdr_dm_data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py_object(py, &self.rpu.vdr_dm_data)
+    }
+
+    #[getter]
+    fn scene_refresh_flag(&self) -> Option<bool> {
+        self.rpu
+            .vdr_dm_data
+            .as_ref()
+            .map(|vdr_dm_data| vdr_dm_data.scene_refresh_flag == 1)
+    }
+
+    /// The whole RPU as a dict
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py_object(py, &self.rpu)
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.rpu).map_err(|e| to_py_err(e.into()))
+    }
+
+    /// Extension metadata blocks as dicts, optionally only for one level.
+    /// The dicts are in the `GenerateConfig` format: `{"Level1": {...}}`
+    #[pyo3(signature = (level=None))]
+    fn blocks<'py>(&self, py: Python<'py>, level: Option<u8>) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        let Some(vdr_dm_data) = self.rpu.vdr_dm_data.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        let blocks: Vec<&ExtMetadataBlock> = if let Some(level) = level {
+            vdr_dm_data.level_blocks_iter(level).collect()
+        } else {
+            [&vdr_dm_data.cmv29_metadata, &vdr_dm_data.cmv40_metadata]
+                .into_iter()
+                .flatten()
+                .flat_map(|dm_data| match dm_data {
+                    DmData::V29(meta) => meta.blocks_ref(),
+                    DmData::V40(meta) => meta.blocks_ref(),
+                })
+                .collect()
+        };
+
+        blocks
+            .into_iter()
+            .map(|block| to_py_object(py, block))
+            .collect()
+    }
+
+    /// Adds a metadata block, from a dict or JSON string
+    fn add_block(&mut self, block: &Bound<'_, PyAny>) -> PyResult<()> {
+        let block: ExtMetadataBlock = from_py_object(block)?;
+        self.rpu.add_metadata_block(block).map_err(to_py_err)
+    }
+
+    /// Replaces the metadata block of the same level (and target display for L2/L8/L10)
+    fn replace_block(&mut self, block: &Bound<'_, PyAny>) -> PyResult<()> {
+        let block: ExtMetadataBlock = from_py_object(block)?;
+        self.rpu.replace_metadata_block(block).map_err(to_py_err)
+    }
+
+    /// Removes every metadata block of the level
+    fn remove_level(&mut self, level: u8) -> PyResult<()> {
+        self.rpu.remove_metadata_level(level).map_err(to_py_err)
+    }
+
+    fn set_scene_refresh_flag(&mut self, is_scene_cut: bool) -> PyResult<()> {
+        self.rpu
+            .set_scene_refresh_flag(is_scene_cut)
+            .map_err(to_py_err)
+    }
+
+    fn convert_with_mode(&mut self, mode: PyConversionMode) -> PyResult<()> {
+        self.rpu.convert_with_mode(mode).map_err(to_py_err)
+    }
+
+    /// Writes the unescaped RPU buffer
+    fn write_rpu<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let data = self.rpu.write_rpu().map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Writes the escaped HEVC UNSPEC62 NALU, starting with 0x7C01
+    fn write_hevc_unspec62_nalu<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let data = self.rpu.write_hevc_unspec62_nalu().map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Writes the AV1 ITU-T T.35 metadata OBU payload
+    fn write_av1_rpu_metadata_obu_t35_payload<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let data = self
+            .rpu
+            .write_av1_rpu_metadata_obu_t35_payload()
+            .map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DoviRpu(profile={}, el_type={:?}, modified={})",
+            self.rpu.dovi_profile,
+            self.el_type(),
+            self.rpu.modified
+        )
+    }
+}
+
+/// Parses a HEVC RPU file, as written by `dovi_tool extract-rpu`
+#[pyfunction]
+fn parse_rpu_file(path: PathBuf) -> PyResult<Vec<PyDoviRpu>> {
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(path).map_err(to_py_err)?;
+
+    Ok(rpus.into_iter().map(PyDoviRpu::from).collect())
+}
+
+/// Writes the RPUs to a HEVC RPU file, in the same format as `dovi_tool extract-rpu`.
+///
+/// Each RPU is prefixed by a 4 bytes start code, without the 0x7C01 NAL header,
+/// which `parse_rpu_file` does not accept after a start code.
+#[pyfunction]
+fn write_rpu_file(path: PathBuf, rpus: Vec<PyRef<'_, PyDoviRpu>>) -> PyResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    for rpu in rpus {
+        let encoded_rpu = rpu.rpu.write_hevc_unspec62_nalu().map_err(to_py_err)?;
+
+        writer.write_all(OUT_NAL_HEADER)?;
+        writer.write_all(
+            encoded_rpu
+                .strip_prefix(NAL_UNSPEC62_HEADER)
+                .unwrap_or(&encoded_rpu),
+        )?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Generates RPUs from a `GenerateConfig`, as a dict or JSON string
+#[pyfunction]
+fn generate_rpus(config: &Bound<'_, PyAny>) -> PyResult<Vec<PyDoviRpu>> {
+    let mut config: GenerateConfig = from_py_object(config)?;
+    config.fill_defaults().map_err(to_py_err)?;
+
+    let rpus = config.generate_rpu_list().map_err(to_py_err)?;
+
+    Ok(rpus.into_iter().map(PyDoviRpu::from).collect())
+}
+
+#[pymodule]
+fn dovi(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDoviRpu>()?;
+    m.add_class::<PyConversionMode>()?;
+    m.add_function(wrap_pyfunction!(parse_rpu_file, m)?)?;
+    m.add_function(wrap_pyfunction!(write_rpu_file, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_rpus, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::path::PathBuf;
+
+    use pyo3::prelude::*;
+    use pyo3::types::{PyDict, PyModule};
+
+    fn run_python(code: &str) -> PyResult<()> {
+        Python::initialize();
+
+        Python::attach(|py| {
+            let module = PyModule::new(py, "dovi")?;
+            super::dovi(&module)?;
+
+            let assets_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .parent()
+                .unwrap()
+                .join("assets");
+
+            let globals = PyDict::new(py);
+            globals.set_item("dovi", module)?;
+            globals.set_item("assets", assets_path)?;
+
+            py.run(&CString::new(code)?, Some(&globals), None)
+        })
+    }
+
+    #[test]
+    fn parse_and_write() -> PyResult<()> {
+        run_python(
+            r#"
+rpus = dovi.parse_rpu_file(assets / "tests/fel_orig.bin")
+rpu = rpus[0]
+
+assert rpu.dovi_profile == 7
+assert rpu.el_type == "FEL"
+assert rpu.header["vdr_rpu_profile"] == 1
+
+nalu = rpu.write_hevc_unspec62_nalu()
+assert dovi.DoviRpu.parse_unspec62_nalu(nalu).write_rpu() == rpu.write_rpu()
+
+payload = rpu.write_av1_rpu_metadata_obu_t35_payload()
+assert dovi.DoviRpu.parse_itu_t35_dovi_metadata_obu(payload).write_rpu() == rpu.write_rpu()
+
+assert dovi.DoviRpu.from_json(rpu.to_json()).write_rpu() == rpu.write_rpu()
+
+rpu.convert_with_mode(dovi.ConversionMode.To81)
+assert rpu.dovi_profile == 8
+assert rpu.el_type is None
+"#,
+        )
+    }
+
+    #[test]
+    fn write_file_as_extracted() -> PyResult<()> {
+        run_python(
+            r#"
+import tempfile, pathlib
+
+original = assets / "tests/fel_orig.bin"
+rpus = dovi.parse_rpu_file(original)
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = pathlib.Path(tmp) / "RPU.bin"
+    dovi.write_rpu_file(path, rpus)
+
+    data = path.read_bytes()
+    assert data[:6] == bytes([0, 0, 0, 1, 0x19, 0x08])
+    assert data == original.read_bytes()
+    assert len(dovi.parse_rpu_file(path)) == len(rpus)
+"#,
+        )
+    }
+
+    #[test]
+    fn edit_blocks() -> PyResult<()> {
+        run_python(
+            r#"
+rpu = dovi.parse_rpu_file(assets / "tests/profile8.bin")[0]
+
+level1 = rpu.blocks(1)[0]["Level1"]
+rpu.replace_block({"Level1": {**level1, "max_pq": 3000}})
+assert rpu.blocks(1)[0]["Level1"]["max_pq"] == 3000
+
+rpu.remove_level(5)
+assert rpu.blocks(5) == []
+assert all("Level5" not in block for block in rpu.blocks())
+
+rpu.set_scene_refresh_flag(True)
+assert rpu.scene_refresh_flag
+assert dovi.DoviRpu.parse_rpu(rpu.write_rpu()).blocks(1)[0]["Level1"]["max_pq"] == 3000
+
+try:
+    rpu.replace_block({"Level42": {}})
+    raise AssertionError("invalid block")
+except ValueError:
+    pass
+"#,
+        )
+    }
+
+    #[test]
+    fn generate() -> PyResult<()> {
+        run_python(
+            r#"
+import tempfile, pathlib
+
+rpus = dovi.generate_rpus({"cm_version": "V40", "length": 5, "level6": {
+    "max_display_mastering_luminance": 1000,
+    "min_display_mastering_luminance": 1,
+    "max_content_light_level": 1000,
+    "max_frame_average_light_level": 400,
+}})
+assert len(rpus) == 5
+assert rpus[0].blocks(6)[0]["Level6"]["max_content_light_level"] == 1000
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = pathlib.Path(tmp) / "RPU.bin"
+    dovi.write_rpu_file(path, rpus)
+    assert len(dovi.parse_rpu_file(path)) == 5
+"#,
+        )
+    }
+}
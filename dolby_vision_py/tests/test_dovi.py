@@ -0,0 +1,39 @@
+from pathlib import Path
+
+import dovi
+
+ASSETS = Path(__file__).parents[2] / "assets"
+
+
+def test_parse_and_write():
+    rpus = dovi.parse_rpu_file(ASSETS / "hevc_tests/regular_rpu.bin")
+    assert len(rpus) == 259
+
+    rpu = rpus[0]
+    assert rpu.dovi_profile == 8
+    assert rpu.scene_refresh_flag
+
+    nalu = rpu.write_hevc_unspec62_nalu()
+    assert dovi.DoviRpu.parse_unspec62_nalu(nalu).write_rpu() == rpu.write_rpu()
+
+
+def test_blocks():
+    rpu = dovi.parse_rpu_file(ASSETS / "tests/profile8.bin")[0]
+
+    level1 = rpu.blocks(1)[0]["Level1"]
+    rpu.replace_block({"Level1": {**level1, "avg_pq": 1500}})
+
+    rpu = dovi.DoviRpu.parse_rpu(rpu.write_rpu())
+    assert rpu.blocks(1)[0]["Level1"]["avg_pq"] == 1500
+
+
+def test_generate(tmp_path):
+    rpus = dovi.generate_rpus('{"length": 24}')
+    assert len(rpus) == 24
+
+    path = tmp_path / "RPU.bin"
+    dovi.write_rpu_file(path, rpus)
+    assert len(dovi.parse_rpu_file(path)) == 24
+
+    # Same format as `dovi_tool extract-rpu`, without the 0x7C01 NAL header
+    assert path.read_bytes()[:6] == bytes([0, 0, 0, 1, 0x19, 0x08])
@@ -0,0 +1,40 @@
+use clap::{Args, ValueHint};
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct ConcatArgs {
+    #[arg(
+        id = "inputs",
+        help = "Sets the input RPU files to concatenate entirely, in order",
+        conflicts_with = "json",
+        required_unless_present = "json",
+        num_args = 1..,
+        value_hint = ValueHint::FilePath
+    )]
+    pub inputs: Vec<PathBuf>,
+
+    #[arg(
+        id = "json",
+        long,
+        short = 'j',
+        help = "Sets the JSON segments list to use",
+        value_hint = ValueHint::FilePath
+    )]
+    pub json_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 'n',
+        help = "Metadata levels to normalize across segments, using the first segment's metadata. Supported levels: 6, 11, 254",
+        value_delimiter = ','
+    )]
+    pub normalize_levels: Vec<u8>,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "Concatenated RPU output file location",
+        value_hint = ValueHint::FilePath
+    )]
+    pub rpu_out: Option<PathBuf>,
+}
@@ -0,0 +1,98 @@
+use clap::{ArgGroup, Args, ValueHint};
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+#[command(group = ArgGroup::new("video").required(true).args(["y4m", "raw-yuv"]))]
+pub struct DetectActiveAreaArgs {
+    #[arg(
+        id = "y4m",
+        long,
+        help = "Y4M video to analyze, `-` to read from stdin",
+        value_hint = ValueHint::FilePath
+    )]
+    pub y4m: Option<PathBuf>,
+
+    #[arg(
+        id = "raw-yuv",
+        long,
+        help = "Raw planar YUV 4:2:0 video to analyze, `-` to read from stdin",
+        requires = "frame-width",
+        requires = "frame-height",
+        value_hint = ValueHint::FilePath
+    )]
+    pub raw_yuv: Option<PathBuf>,
+
+    #[arg(
+        id = "frame-width",
+        long,
+        help = "Raw YUV: frame width",
+        requires = "raw-yuv"
+    )]
+    pub frame_width: Option<usize>,
+
+    #[arg(
+        id = "frame-height",
+        long,
+        help = "Raw YUV: frame height",
+        requires = "raw-yuv"
+    )]
+    pub frame_height: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Raw YUV: bit depth of the samples",
+        requires = "raw-yuv",
+        default_value = "10"
+    )]
+    pub bit_depth: u8,
+
+    #[arg(
+        long,
+        help = "Raw YUV: the samples are full range instead of limited",
+        requires = "raw-yuv"
+    )]
+    pub full_range: bool,
+
+    #[arg(
+        long,
+        help = "Canvas width for the L5 offsets. Defaults to the video width"
+    )]
+    pub canvas_width: Option<u16>,
+
+    #[arg(
+        long,
+        help = "Canvas height for the L5 offsets. Defaults to the video height"
+    )]
+    pub canvas_height: Option<u16>,
+
+    #[arg(
+        long,
+        short = 't',
+        help = "Black level threshold, as normalized luma from 0.0 to 1.0",
+        default_value = "0.1"
+    )]
+    pub threshold: f64,
+
+    #[arg(
+        long,
+        help = "Minimum number of frames for an active area to be kept, shorter ones are merged",
+        default_value = "24"
+    )]
+    pub min_duration: usize,
+
+    #[arg(
+        long,
+        short = 'r',
+        help = "RPU file to inject the detected L5 metadata into, instead of writing an editor config",
+        value_hint = ValueHint::FilePath
+    )]
+    pub rpu_in: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "Output file location. Editor config JSON, or RPU file with --rpu-in",
+        value_hint = ValueHint::FilePath
+    )]
+    pub output: Option<PathBuf>,
+}
@@ -39,4 +39,10 @@ pub struct EditorArgs {
         value_hint = ValueHint::FilePath
     )]
     pub rpu_out: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Sets use_prev_vdr_rpu_flag to reuse the first RPU's mapping, to reduce the RPU size. Fails unless every RPU has the same mapping, consecutive identical mappings are not shared"
+    )]
+    pub reuse_mappings: bool,
 }
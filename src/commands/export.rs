@@ -60,6 +60,8 @@ pub enum ExportData {
     Scenes,
     /// Exports the video's L5 metadata in the form of an `editor` config JSON
     Level5,
+    /// Exports the L1 metadata as a madVR HDR measurement file
+    Madvr,
 }
 
 impl ExportData {
@@ -68,6 +70,7 @@ impl ExportData {
             ExportData::All => "RPU_export.json",
             ExportData::Scenes => "RPU_scenes.txt",
             ExportData::Level5 => "RPU_L5_edit_config.json",
+            ExportData::Madvr => "RPU_madVR.bin",
         }
     }
 }
@@ -2,6 +2,8 @@ use clap::{Args, ValueHint};
 use hdr10plus::metadata::PeakBrightnessSource;
 use std::path::PathBuf;
 
+use dolby_vision::timecode::FrameRate;
+
 use crate::dovi::generator::GeneratorProfile;
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
@@ -71,6 +73,27 @@ pub struct GenerateArgs {
     #[arg(long, help = "Canvas height for L5 metadata generation")]
     pub canvas_height: Option<u16>,
 
+    #[arg(
+        long,
+        help = "XML: Output (deliverable) to generate from, by name or index. Defaults to the first one",
+        requires = "xml"
+    )]
+    pub xml_output: Option<String>,
+
+    #[arg(
+        long,
+        help = "XML: video track to generate from, by name or index. Defaults to the first one",
+        requires = "xml"
+    )]
+    pub xml_track: Option<String>,
+
+    #[arg(
+        long,
+        help = "XML: list the available outputs and video tracks, without generating",
+        requires = "xml"
+    )]
+    pub list_xml_outputs: bool,
+
     #[arg(
         id = "madvr-file",
         long,
@@ -85,6 +108,88 @@ pub struct GenerateArgs {
     )]
     pub use_custom_targets: bool,
 
+    #[arg(
+        id = "y4m",
+        long,
+        help = "Y4M PQ video to measure for per-shot L1 metadata, `-` to read from stdin",
+        conflicts_with_all = &["hdr10plus-json", "madvr-file", "raw-yuv"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub y4m: Option<PathBuf>,
+
+    #[arg(
+        id = "raw-yuv",
+        long,
+        help = "Raw planar YUV 4:2:0 PQ video to measure for per-shot L1 metadata, `-` to read from stdin",
+        conflicts_with_all = &["hdr10plus-json", "madvr-file"],
+        requires = "frame-width",
+        requires = "frame-height",
+        value_hint = ValueHint::FilePath
+    )]
+    pub raw_yuv: Option<PathBuf>,
+
+    #[arg(
+        id = "frame-width",
+        long,
+        help = "Raw YUV: frame width",
+        requires = "raw-yuv"
+    )]
+    pub frame_width: Option<usize>,
+
+    #[arg(
+        id = "frame-height",
+        long,
+        help = "Raw YUV: frame height",
+        requires = "raw-yuv"
+    )]
+    pub frame_height: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Raw YUV: bit depth of the samples",
+        requires = "raw-yuv",
+        default_value = "10"
+    )]
+    pub bit_depth: u8,
+
+    #[arg(
+        long,
+        help = "Raw YUV: the samples are full range instead of limited",
+        requires = "raw-yuv"
+    )]
+    pub full_range: bool,
+
+    #[arg(
+        long,
+        help = "Video measurement: scene cut detection threshold, from 0.0 to 1.0",
+        default_value = "0.3"
+    )]
+    pub scene_threshold: f64,
+
+    #[arg(
+        id = "csv",
+        long,
+        help = "CSV or plain text shot list to generate from",
+        conflicts_with_all = &["hdr10plus-json", "madvr-file", "y4m", "raw-yuv", "scene-cuts"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub csv: Option<PathBuf>,
+
+    #[arg(
+        id = "scene-cuts",
+        long,
+        help = "Scene cuts list to generate the shots from, one frame index per line",
+        conflicts_with_all = &["hdr10plus-json", "madvr-file", "y4m", "raw-yuv"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub scene_cuts: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Frame rate of the video. Allows using timecodes or seconds in the CSV shot list"
+    )]
+    pub frame_rate: Option<FrameRate>,
+
     #[arg(
         value_enum,
         short = 'p',
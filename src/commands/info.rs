@@ -2,6 +2,8 @@ use clap::{Args, ValueHint};
 use std::path::PathBuf;
 
 #[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("json_modes").args(["summary", "scenes", "trace"]))]
+#[command(group = clap::ArgGroup::new("file_outputs").args(["scenes", "trace", "json"]).multiple(true))]
 pub struct InfoArgs {
     #[arg(
         id = "input",
@@ -33,4 +35,37 @@ pub struct InfoArgs {
 
     #[arg(id = "summary", long, short = 's', help = "Show the RPU summary")]
     pub summary: bool,
+
+    #[arg(
+        id = "scenes",
+        long,
+        help = "Show per-scene statistics (L1, L2, L3, L5 and L8 metadata)",
+        conflicts_with_all = &["frame", "summary"]
+    )]
+    pub scenes: bool,
+
+    #[arg(
+        long,
+        help = "Show the bit offset, width and value of every syntax element of the frame",
+        requires = "frame",
+        conflicts_with_all = &["summary", "scenes"]
+    )]
+    pub trace: bool,
+
+    #[arg(
+        id = "json",
+        long,
+        help = "Output the summary, the scenes statistics (instead of CSV) or the trace as JSON",
+        requires = "json_modes"
+    )]
+    pub json: bool,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "Output file location for the scenes statistics, the trace or the JSON output. Defaults to stdout",
+        requires = "file_outputs",
+        value_hint = ValueHint::FilePath
+    )]
+    pub output: Option<PathBuf>,
 }
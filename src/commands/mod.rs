@@ -2,8 +2,10 @@ use clap::Parser;
 
 use dolby_vision::rpu::ConversionMode;
 
+mod concat;
 mod convert;
 mod demux;
+mod detect_active_area;
 mod editor;
 mod export;
 mod extract_rpu;
@@ -13,9 +15,13 @@ mod inject_rpu;
 mod mux;
 mod plot;
 mod remove;
+mod repair;
+mod split;
 
+pub use concat::ConcatArgs;
 pub use convert::ConvertArgs;
 pub use demux::DemuxArgs;
+pub use detect_active_area::DetectActiveAreaArgs;
 pub use editor::EditorArgs;
 pub use export::{ExportArgs, ExportData};
 pub use extract_rpu::ExtractRpuArgs;
@@ -25,9 +31,14 @@ pub use inject_rpu::InjectRpuArgs;
 pub use mux::MuxArgs;
 pub use plot::PlotArgs;
 pub use remove::RemoveArgs;
+pub use repair::RepairArgs;
+pub use split::SplitArgs;
 
 #[derive(Parser, Debug)]
 pub enum Commands {
+    #[command(about = "Concatenates segments of one or more RPU files into a single RPU file")]
+    Concat(ConcatArgs),
+
     #[command(about = "Converts RPU within a single layer HEVC file")]
     Convert(ConvertArgs),
 
@@ -36,6 +47,11 @@ pub enum Commands {
     )]
     Demux(DemuxArgs),
 
+    #[command(
+        about = "Detects the black bars of a decoded video to create L5 active area metadata"
+    )]
+    DetectActiveArea(DetectActiveAreaArgs),
+
     #[command(about = "Edits a binary RPU according to a JSON config")]
     Editor(EditorArgs),
 
@@ -62,6 +78,14 @@ pub enum Commands {
 
     #[command(about = "Removes the enhancement layer and RPU data from the video")]
     Remove(RemoveArgs),
+
+    #[command(
+        about = "Repairs RPUs with invalid CRC32 or trailing data, optionally replacing unrecoverable frames"
+    )]
+    Repair(RepairArgs),
+
+    #[command(about = "Splits an RPU file into multiple segments")]
+    Split(SplitArgs),
 }
 
 #[derive(clap::ValueEnum, Debug, Copy, Clone)]
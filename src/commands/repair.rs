@@ -0,0 +1,47 @@
+use clap::{Args, ValueHint};
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct RepairArgs {
+    #[arg(
+        id = "input",
+        help = "Sets the input RPU file to use",
+        long,
+        short = 'i',
+        conflicts_with = "input_pos",
+        required_unless_present = "input_pos",
+        value_hint = ValueHint::FilePath,
+    )]
+    pub input: Option<PathBuf>,
+
+    #[arg(
+        id = "input_pos",
+        help = "Sets the input RPU file to use (positional)",
+        conflicts_with = "input",
+        required_unless_present = "input",
+        value_hint = ValueHint::FilePath
+    )]
+    pub input_pos: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "Repaired RPU output file location",
+        value_hint = ValueHint::FilePath
+    )]
+    pub rpu_out: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Replaces the RPUs that cannot be repaired with a copy of the nearest valid RPU"
+    )]
+    pub replace_invalid: bool,
+
+    #[arg(
+        long,
+        short = 'r',
+        help = "Writes a JSON report of the repaired and replaced frames",
+        value_hint = ValueHint::FilePath
+    )]
+    pub report: Option<PathBuf>,
+}
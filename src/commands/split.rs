@@ -0,0 +1,74 @@
+use clap::{ArgGroup, Args, ValueHint};
+use std::path::PathBuf;
+
+use dolby_vision::timecode::FrameRate;
+
+#[derive(Args, Debug)]
+#[command(group = ArgGroup::new("split_by").required(true).args(["ranges", "scene_cuts", "chapters", "scenes"]))]
+pub struct SplitArgs {
+    #[arg(
+        id = "input",
+        help = "Sets the input RPU file to use",
+        long,
+        short = 'i',
+        conflicts_with = "input_pos",
+        required_unless_present = "input_pos",
+        value_hint = ValueHint::FilePath,
+    )]
+    pub input: Option<PathBuf>,
+
+    #[arg(
+        id = "input_pos",
+        help = "Sets the input RPU file to use (positional)",
+        conflicts_with = "input",
+        required_unless_present = "input",
+        value_hint = ValueHint::FilePath
+    )]
+    pub input_pos: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 'r',
+        help = "List of frame ranges (inclusive) to write as segments, formatted as `start-end,...`",
+        value_delimiter = ','
+    )]
+    pub ranges: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Splits at every frame listed in a text file, one frame index per line",
+        value_hint = ValueHint::FilePath
+    )]
+    pub scene_cuts: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 'c',
+        help = "Splits at every chapter of a Matroska XML or OGM chapters file. Requires --frame-rate",
+        requires = "frame_rate",
+        value_hint = ValueHint::FilePath
+    )]
+    pub chapters: Option<PathBuf>,
+
+    #[arg(
+        long,
+        short = 's',
+        help = "Splits every N scenes, delimited by scene_refresh_flag",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub scenes: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Frame rate of the video, as a fraction or integer. Allows using timecodes or seconds for ranges"
+    )]
+    pub frame_rate: Option<FrameRate>,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "Output directory for the segments. Defaults to the current directory",
+        value_hint = ValueHint::DirPath
+    )]
+    pub output_dir: Option<PathBuf>,
+}
@@ -0,0 +1,268 @@
+use std::fs::File;
+use std::io::{Write, stdout};
+use std::path::PathBuf;
+
+use anyhow::{Result, bail, ensure};
+use serde_json::{Map, Value, json};
+
+use dolby_vision::rpu::editor::EditConfig;
+use dolby_vision::rpu::extension_metadata::blocks::ExtMetadataBlockLevel5;
+use dolby_vision::rpu::generate::GenerateConfig;
+use dolby_vision::rpu::utils::parse_rpu_file;
+
+use super::frame_analyzer::{ChromaSubsampling, FrameFormat, FrameReader};
+use super::{DoviRpu, write_rpu_file};
+use crate::commands::DetectActiveAreaArgs;
+
+pub struct ActiveAreaDetector {
+    reader: FrameReader,
+    canvas_width: u16,
+    canvas_height: u16,
+    threshold: f64,
+    min_duration: usize,
+    rpu_in: Option<PathBuf>,
+    output: PathBuf,
+}
+
+/// Consecutive frames with the same active area
+#[derive(Debug)]
+struct ActiveAreaRegion {
+    start: usize,
+    end: usize,
+    level5: ExtMetadataBlockLevel5,
+}
+
+impl ActiveAreaDetector {
+    pub fn from_args(args: DetectActiveAreaArgs) -> Result<Self> {
+        let DetectActiveAreaArgs {
+            y4m,
+            raw_yuv,
+            frame_width,
+            frame_height,
+            bit_depth,
+            full_range,
+            canvas_width,
+            canvas_height,
+            threshold,
+            min_duration,
+            rpu_in,
+            output,
+        } = args;
+
+        ensure!(
+            (0.0..=1.0).contains(&threshold),
+            "Invalid threshold {threshold}, must be between 0.0 and 1.0"
+        );
+
+        let reader = if let Some(y4m) = y4m {
+            FrameReader::open_y4m(&y4m)?
+        } else if let (Some(raw_yuv), Some(width), Some(height)) =
+            (raw_yuv, frame_width, frame_height)
+        {
+            let format = FrameFormat {
+                width,
+                height,
+                bit_depth,
+                chroma: ChromaSubsampling::Cs420,
+                full_range,
+            };
+
+            FrameReader::open_raw(&raw_yuv, format)?
+        } else {
+            bail!("No video input provided. See `dovi_tool detect-active-area --help`");
+        };
+
+        let format = reader.format();
+        let canvas_width = canvas_width.map_or_else(|| u16::try_from(format.width), Ok)?;
+        let canvas_height = canvas_height.map_or_else(|| u16::try_from(format.height), Ok)?;
+
+        let output = output.unwrap_or_else(|| {
+            if let Some(rpu_in) = &rpu_in {
+                PathBuf::from(format!(
+                    "{}_L5.bin",
+                    rpu_in.file_stem().unwrap_or_default().to_string_lossy()
+                ))
+            } else {
+                PathBuf::from("active_area.json")
+            }
+        });
+
+        Ok(Self {
+            reader,
+            canvas_width,
+            canvas_height,
+            threshold,
+            min_duration,
+            rpu_in,
+            output,
+        })
+    }
+
+    pub fn detect(args: DetectActiveAreaArgs) -> Result<()> {
+        let mut detector = ActiveAreaDetector::from_args(args)?;
+
+        println!("Detecting black bars...");
+        stdout().flush().ok();
+
+        let frames_level5 = detector.detect_frames()?;
+        let frame_count = frames_level5.len();
+        ensure!(frame_count > 0, "No frames to analyze in the video");
+
+        let regions = detector.group_regions(frames_level5);
+
+        println!("Detected {} active area regions:", regions.len());
+        for region in &regions {
+            let (left, right, top, bottom) = region.level5.get_offsets();
+            println!(
+                "  {}-{}: left {left}, right {right}, top {top}, bottom {bottom}",
+                region.start, region.end
+            );
+        }
+
+        let config = Self::editor_config(&regions);
+
+        if let Some(rpu_in) = &detector.rpu_in {
+            println!("Parsing RPU file...");
+            stdout().flush().ok();
+
+            let mut rpus: Vec<Option<DoviRpu>> =
+                parse_rpu_file(rpu_in)?.into_iter().map(Some).collect();
+
+            ensure!(
+                rpus.len() == frame_count,
+                "RPU length mismatch: {} RPUs for {frame_count} video frames",
+                rpus.len()
+            );
+
+            let edit_config: EditConfig = serde_json::from_value(config)?;
+            edit_config.execute(&mut rpus)?;
+
+            let data = GenerateConfig::encode_option_rpus(&rpus).collect::<Result<Vec<_>>>()?;
+            write_rpu_file(&detector.output, data)?;
+        } else {
+            let mut writer = File::create(&detector.output)?;
+            serde_json::to_writer_pretty(&mut writer, &config)?;
+            writeln!(writer)?;
+        }
+
+        println!("Done.");
+
+        Ok(())
+    }
+
+    /// L5 offsets of every frame. Black frames keep the previous frame's active area
+    fn detect_frames(&mut self) -> Result<Vec<ExtMetadataBlockLevel5>> {
+        let format = *self.reader.format();
+        let canvas_ar = self.canvas_width as f32 / self.canvas_height as f32;
+
+        let mut detected = Vec::new();
+
+        while let Some(frame) = self.reader.next_frame()? {
+            let level5 = frame.black_bars(&format, self.threshold).map(|bars| {
+                ExtMetadataBlockLevel5::from_aspect_ratios(
+                    self.canvas_width,
+                    self.canvas_height,
+                    canvas_ar,
+                    bars.active_aspect_ratio(&format),
+                )
+            });
+
+            detected.push(level5);
+        }
+
+        // Leading black frames use the first detected active area
+        let mut previous = detected
+            .iter()
+            .flatten()
+            .next()
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(detected
+            .into_iter()
+            .map(|level5| {
+                if let Some(level5) = level5 {
+                    previous = level5;
+                }
+
+                previous.clone()
+            })
+            .collect())
+    }
+
+    /// Groups the frames in regions, merging regions shorter than the minimum duration
+    fn group_regions(&self, frames_level5: Vec<ExtMetadataBlockLevel5>) -> Vec<ActiveAreaRegion> {
+        let mut regions: Vec<ActiveAreaRegion> = Vec::new();
+
+        for (i, level5) in frames_level5.into_iter().enumerate() {
+            match regions.last_mut() {
+                Some(last) if last.level5 == level5 => last.end = i,
+                _ => regions.push(ActiveAreaRegion {
+                    start: i,
+                    end: i,
+                    level5,
+                }),
+            }
+        }
+
+        let mut merged: Vec<ActiveAreaRegion> = Vec::with_capacity(regions.len());
+
+        for region in regions {
+            let duration = region.end - region.start + 1;
+
+            match merged.last_mut() {
+                Some(last) if last.level5 == region.level5 || duration < self.min_duration => {
+                    last.end = region.end
+                }
+                // Short first region, takes the next region's active area
+                Some(last) if last.end - last.start + 1 < self.min_duration => {
+                    last.end = region.end;
+                    last.level5 = region.level5;
+                }
+                _ => merged.push(region),
+            }
+        }
+
+        merged
+    }
+
+    fn editor_config(regions: &[ActiveAreaRegion]) -> Value {
+        let mut presets: Vec<&ExtMetadataBlockLevel5> = Vec::new();
+        let mut edits = Map::new();
+
+        for region in regions {
+            let id = presets
+                .iter()
+                .position(|e| **e == region.level5)
+                .unwrap_or_else(|| {
+                    presets.push(&region.level5);
+                    presets.len() - 1
+                });
+
+            edits.insert(format!("{}-{}", region.start, region.end), json!(id));
+        }
+
+        let presets: Vec<Value> = presets
+            .into_iter()
+            .enumerate()
+            .map(|(id, level5)| {
+                let (left, right, top, bottom) = level5.get_offsets();
+
+                json!({
+                    "id": id,
+                    "left": left,
+                    "right": right,
+                    "top": top,
+                    "bottom": bottom,
+                })
+            })
+            .collect();
+
+        json!({
+            "active_area": {
+                "presets": presets,
+                "edits": edits,
+            }
+        })
+    }
+}
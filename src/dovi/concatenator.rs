@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Write, stdout};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail, ensure};
+use serde::{Deserialize, Serialize};
+
+use dolby_vision::rpu::extension_metadata::blocks::ExtMetadataBlock;
+use dolby_vision::rpu::generate::GenerateConfig;
+use dolby_vision::rpu::prev_vdr_rpu::PrevVdrRpuResolver;
+use dolby_vision::rpu::utils::parse_rpu_file;
+use dolby_vision::timecode::{FramePosition, FrameRate};
+
+use super::{DoviRpu, write_rpu_file};
+use crate::commands::ConcatArgs;
+
+pub struct Concatenator {
+    config: ConcatConfig,
+    rpu_out: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ConcatConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frame_rate: Option<FrameRate>,
+
+    segments: Vec<ConcatSegment>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    normalize_levels: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ConcatSegment {
+    rpu_file: PathBuf,
+
+    /// First frame of the segment, defaults to the start of the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<FramePosition>,
+
+    /// Last frame of the segment (inclusive), defaults to the end of the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<FramePosition>,
+}
+
+impl Concatenator {
+    const NORMALIZE_LEVELS: &[u8] = &[6, 11, 254];
+
+    pub fn from_args(args: ConcatArgs) -> Result<Self> {
+        let ConcatArgs {
+            inputs,
+            json_file,
+            normalize_levels,
+            rpu_out,
+        } = args;
+
+        let mut config = if let Some(json_file) = json_file {
+            ConcatConfig::from_path(json_file)?
+        } else {
+            ConcatConfig {
+                segments: inputs
+                    .into_iter()
+                    .map(|rpu_file| ConcatSegment {
+                        rpu_file,
+                        start: None,
+                        end: None,
+                    })
+                    .collect(),
+                ..Default::default()
+            }
+        };
+
+        if !normalize_levels.is_empty() {
+            config.normalize_levels = normalize_levels;
+        }
+
+        ensure!(!config.segments.is_empty(), "No segments to concatenate");
+
+        if let Some(level) = config
+            .normalize_levels
+            .iter()
+            .find(|level| !Self::NORMALIZE_LEVELS.contains(level))
+        {
+            bail!("Invalid level to normalize: {level}. Supported levels: 6, 11, 254");
+        }
+
+        let rpu_out = rpu_out.unwrap_or_else(|| PathBuf::from("RPU_concat.bin"));
+
+        Ok(Self { config, rpu_out })
+    }
+
+    pub fn concat(args: ConcatArgs) -> Result<()> {
+        let concatenator = Concatenator::from_args(args)?;
+
+        println!("Parsing RPU files...");
+        stdout().flush().ok();
+
+        let mut rpus = concatenator.config.concat_segments()?;
+
+        concatenator.config.normalize(&mut rpus)?;
+
+        println!("Final metadata length: {}", rpus.len());
+
+        let data = GenerateConfig::encode_rpus(&rpus).collect::<Result<Vec<_>>>()?;
+        write_rpu_file(&concatenator.rpu_out, data)?;
+
+        Ok(())
+    }
+}
+
+impl ConcatConfig {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json_file = File::open(path)?;
+        let config: ConcatConfig = serde_json::from_reader(&json_file)?;
+
+        Ok(config)
+    }
+
+    fn concat_segments(&self) -> Result<Vec<DoviRpu>> {
+        // Files can be used in multiple segments
+        let mut parsed_files: HashMap<&Path, Vec<DoviRpu>> = HashMap::new();
+        let mut rpus = Vec::new();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            let path = segment.rpu_file.as_path();
+
+            if !parsed_files.contains_key(path) {
+                let file_rpus = parse_rpu_file(path)
+                    .with_context(|| format!("Failed parsing RPU file {}", path.display()))?;
+                parsed_files.insert(path, file_rpus);
+            }
+
+            let file_rpus = &parsed_files[path];
+            let (start, end) = segment.frame_range(self.frame_rate.as_ref(), file_rpus.len())?;
+
+            println!(
+                "Segment {i}: frames {start}-{end} from {}",
+                segment.rpu_file.display()
+            );
+
+            let first_idx = rpus.len();
+            rpus.extend_from_slice(&file_rpus[start..=end]);
+
+            // The mapping referenced by the first RPU is not part of the output
+            let first_rpu = &mut rpus[first_idx];
+            PrevVdrRpuResolver::detach(&file_rpus[..start], first_rpu).with_context(|| {
+                format!("Segment {i}: failed resolving the mapping of frame {start}")
+            })?;
+
+            // Splice points are always scene cuts
+            if let Some(vdr_dm_data) = first_rpu.vdr_dm_data.as_mut()
+                && vdr_dm_data.scene_refresh_flag != 1
+            {
+                first_rpu.modified = true;
+                vdr_dm_data.set_scene_cut(true);
+            }
+        }
+
+        Ok(rpus)
+    }
+
+    /// Replaces the metadata levels by the ones from the first RPU
+    fn normalize(&self, rpus: &mut [DoviRpu]) -> Result<()> {
+        let Some((first_rpu, rpus)) = rpus.split_first_mut() else {
+            return Ok(());
+        };
+        let Some(first_vdr_dm_data) = first_rpu.vdr_dm_data.as_ref() else {
+            return Ok(());
+        };
+
+        for level in &self.normalize_levels {
+            let blocks: Vec<ExtMetadataBlock> = first_vdr_dm_data
+                .level_blocks_iter(*level)
+                .cloned()
+                .collect();
+
+            if blocks.is_empty() {
+                println!("No L{level} metadata in the first segment, skipping normalization");
+                continue;
+            }
+
+            println!("Normalizing L{level} metadata...");
+
+            for rpu in rpus.iter_mut() {
+                if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+                    rpu.modified = true;
+
+                    vdr_dm_data.remove_metadata_level(*level);
+                    for block in &blocks {
+                        vdr_dm_data.add_metadata_block(block.clone())?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConcatSegment {
+    fn frame_range(&self, frame_rate: Option<&FrameRate>, count: usize) -> Result<(usize, usize)> {
+        ensure!(count > 0, "Empty RPU file {}", self.rpu_file.display());
+
+        let start = self
+            .start
+            .as_ref()
+            .map(|e| e.to_frame(frame_rate))
+            .transpose()?
+            .unwrap_or(0);
+        let end = self
+            .end
+            .as_ref()
+            .map(|e| e.to_frame(frame_rate))
+            .transpose()?
+            .unwrap_or(count - 1);
+
+        ensure!(
+            end < count,
+            "Invalid segment end {end} for {}: {count} available RPUs",
+            self.rpu_file.display()
+        );
+        ensure!(
+            start <= end,
+            "Invalid segment range {start}-{end} for {}",
+            self.rpu_file.display()
+        );
+
+        Ok((start, end))
+    }
+}
@@ -4,9 +4,11 @@ use std::io::{BufWriter, Write, stdout};
 use std::ops::Range;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use dolby_vision::rpu::extension_metadata::blocks::{ExtMetadataBlock, ExtMetadataBlockLevel5};
+use dolby_vision::utils::pq_to_nits;
 use itertools::Itertools;
+use madvr_parse::{MadVRFrame, MadVRHeader, MadVRMeasurements, MadVRScene};
 use serde::Serializer;
 use serde::ser::SerializeSeq;
 
@@ -16,7 +18,7 @@ use serde_json::json;
 use crate::commands::{ExportArgs, ExportData};
 use crate::dovi::input_from_either;
 
-use super::DoviRpu;
+use super::{DoviRpu, madvr};
 
 pub struct Exporter {
     input: PathBuf,
@@ -101,6 +103,9 @@ impl Exporter {
                 ExportData::Level5 => {
                     self.export_level5_config(rpus, &mut writer)?;
                 }
+                ExportData::Madvr => {
+                    self.export_madvr_measurements(rpus, &mut writer)?;
+                }
             }
 
             writer.flush()?;
@@ -181,4 +186,112 @@ impl Exporter {
 
         Ok(())
     }
+
+    fn export_madvr_measurements<W: Write>(&self, rpus: &[DoviRpu], writer: &mut W) -> Result<()> {
+        println!("Exporting madVR measurement file...");
+
+        let mut frames = Vec::with_capacity(rpus.len());
+        let mut scene_starts = Vec::new();
+
+        for (i, rpu) in rpus.iter().enumerate() {
+            let vdr_dm_data = rpu.vdr_dm_data.as_ref();
+            let level1 = vdr_dm_data.and_then(|vdr| match vdr.get_block(1) {
+                Some(ExtMetadataBlock::Level1(b)) => Some(b),
+                _ => None,
+            });
+
+            let Some(level1) = level1 else {
+                bail!("Cannot export madVR measurements: RPU {i} has no L1 metadata");
+            };
+
+            if i == 0 || vdr_dm_data.is_some_and(|vdr| vdr.scene_refresh_flag == 1) {
+                scene_starts.push(i);
+            }
+
+            let avg_pq = level1.avg_pq as f64 / 4095.0;
+
+            frames.push(MadVRFrame {
+                peak_pq_2020: level1.max_pq as f64 / 4095.0,
+                lum_histogram: madvr::lum_histogram(avg_pq),
+                hue_histogram: Some(vec![0.0; 31]),
+                avg_pq,
+                ..Default::default()
+            });
+        }
+
+        let frame_nits = |f: &MadVRFrame| (pq_to_nits(f.peak_pq_2020), pq_to_nits(f.avg_pq));
+
+        let scenes: Vec<MadVRScene> = scene_starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = scene_starts.get(i + 1).map_or(frames.len(), |next| *next) - 1;
+                let scene_frames = &frames[start..=end];
+
+                let max_pq = scene_frames
+                    .iter()
+                    .map(|f| f.peak_pq_2020)
+                    .fold(0.0, f64::max);
+                let avg_pq = scene_frames.iter().map(|f| f.avg_pq).fold(0.0, f64::max);
+
+                MadVRScene {
+                    start: start as u32,
+                    end: end as u32,
+                    peak_nits: pq_to_nits(max_pq).round() as u32,
+                    length: scene_frames.len(),
+                    max_pq,
+                    avg_pq,
+                }
+            })
+            .collect();
+
+        let (computed_maxcll, computed_maxfall) = frames
+            .iter()
+            .map(frame_nits)
+            .fold((0.0, 0.0), |(cll, fall), (peak, avg)| {
+                (f64::max(cll, peak), f64::max(fall, avg))
+            });
+        let avgfall = frames.iter().map(|f| frame_nits(f).1).sum::<f64>() / frames.len() as f64;
+
+        // Static metadata from L6 when set, otherwise computed from the L1 metadata
+        let level6 = rpus[0]
+            .vdr_dm_data
+            .as_ref()
+            .and_then(|vdr| match vdr.get_block(6) {
+                Some(ExtMetadataBlock::Level6(b)) => Some(b),
+                _ => None,
+            });
+        let (maxcll, maxfall) = match level6 {
+            Some(l6) if l6.max_content_light_level > 0 && l6.max_frame_average_light_level > 0 => (
+                l6.max_content_light_level as u32,
+                l6.max_frame_average_light_level as u32,
+            ),
+            _ => (
+                computed_maxcll.round() as u32,
+                computed_maxfall.round() as u32,
+            ),
+        };
+
+        let measurements = MadVRMeasurements {
+            header: MadVRHeader {
+                version: 5,
+                // Magic code and 8 header fields
+                header_size: 36,
+                scene_count: scenes.len() as u32,
+                frame_count: frames.len() as u32,
+                // Complete measurements, without custom per-frame target nits
+                flags: 2,
+                maxcll,
+                maxfall,
+                avgfall: avgfall.round() as u32,
+                target_peak_nits: 0,
+            },
+            scenes,
+            frames,
+        };
+
+        writer.write_all(&measurements.write_measurements()?)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,434 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, stdin};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result, bail, ensure};
+
+use dolby_vision::utils::pq_to_nits;
+
+const Y4M_SIGNATURE: &str = "YUV4MPEG2";
+const Y4M_FRAME_HEADER: &str = "FRAME";
+
+const HISTOGRAM_BINS: usize = 64;
+
+/// Nits of every 12 bit PQ value
+static PQ_12_BIT_NITS: LazyLock<Vec<f64>> =
+    LazyLock::new(|| (0..4096).map(|e| pq_to_nits(e as f64 / 4095.0)).collect());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChromaSubsampling {
+    Cs420,
+    Cs422,
+    Cs444,
+    Mono,
+}
+
+/// Format of the decoded frames, in planar YUV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFormat {
+    pub width: usize,
+    pub height: usize,
+    pub bit_depth: u8,
+    pub chroma: ChromaSubsampling,
+    pub full_range: bool,
+}
+
+/// Decoded frame, with samples at the source bit depth
+pub struct Frame {
+    pub y: Vec<u16>,
+    pub u: Vec<u16>,
+    pub v: Vec<u16>,
+}
+
+/// Reads planar YUV frames, from a Y4M stream or raw YUV data
+pub struct FrameReader {
+    reader: Box<dyn BufRead>,
+    format: FrameFormat,
+    y4m: bool,
+    buf: Vec<u8>,
+}
+
+/// Black bars sizes in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlackBars {
+    pub left: usize,
+    pub right: usize,
+    pub top: usize,
+    pub bottom: usize,
+}
+
+/// Per-frame PQ statistics of the max RGB component, normalized to 0.0-1.0
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    pub min_pq: f64,
+    pub max_pq: f64,
+    pub avg_pq: f64,
+    /// Frame average light level
+    pub avg_nits: f64,
+    histogram: [f64; HISTOGRAM_BINS],
+}
+
+impl ChromaSubsampling {
+    /// Horizontal and vertical subsampling shifts
+    fn shifts(&self) -> (usize, usize) {
+        match self {
+            Self::Cs420 => (1, 1),
+            Self::Cs422 => (1, 0),
+            Self::Cs444 | Self::Mono => (0, 0),
+        }
+    }
+}
+
+impl FrameFormat {
+    fn chroma_dims(&self) -> (usize, usize) {
+        if self.chroma == ChromaSubsampling::Mono {
+            return (0, 0);
+        }
+
+        let (shift_x, shift_y) = self.chroma.shifts();
+
+        (
+            self.width.div_ceil(1 << shift_x),
+            self.height.div_ceil(1 << shift_y),
+        )
+    }
+
+    fn bytes_per_sample(&self) -> usize {
+        if self.bit_depth > 8 { 2 } else { 1 }
+    }
+
+    pub fn frame_size(&self) -> usize {
+        let (chroma_width, chroma_height) = self.chroma_dims();
+        let samples = self.width * self.height + 2 * chroma_width * chroma_height;
+
+        samples * self.bytes_per_sample()
+    }
+
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.width > 0 && self.height > 0,
+            "Invalid frame dimensions {}x{}",
+            self.width,
+            self.height
+        );
+        ensure!(
+            (8..=16).contains(&self.bit_depth),
+            "Unsupported bit depth {}",
+            self.bit_depth
+        );
+
+        Ok(())
+    }
+
+    /// Converts a luma sample to normalized 0.0-1.0
+    fn normalize_luma(&self, value: u16) -> f64 {
+        let shift = self.bit_depth - 8;
+
+        if self.full_range {
+            value as f64 / ((1_u32 << self.bit_depth) - 1) as f64
+        } else {
+            (value as f64 - (16 << shift) as f64) / (219 << shift) as f64
+        }
+    }
+
+    /// Converts a chroma sample to normalized -0.5-0.5
+    fn normalize_chroma(&self, value: u16) -> f64 {
+        let shift = self.bit_depth - 8;
+        let offset = (128 << shift) as f64;
+
+        if self.full_range {
+            (value as f64 - offset) / ((1_u32 << self.bit_depth) - 1) as f64
+        } else {
+            (value as f64 - offset) / (224 << shift) as f64
+        }
+    }
+}
+
+impl FrameReader {
+    /// Opens a Y4M stream, `-` reads from stdin
+    pub fn open_y4m(path: &Path) -> Result<Self> {
+        let mut reader = Self::open_input(path)?;
+
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+
+        let format = Self::parse_y4m_header(header.trim_end())?;
+
+        Ok(Self {
+            reader,
+            format,
+            y4m: true,
+            buf: vec![0; format.frame_size()],
+        })
+    }
+
+    /// Opens raw planar YUV data, `-` reads from stdin
+    pub fn open_raw(path: &Path, format: FrameFormat) -> Result<Self> {
+        format.validate()?;
+
+        Ok(Self {
+            reader: Self::open_input(path)?,
+            format,
+            y4m: false,
+            buf: vec![0; format.frame_size()],
+        })
+    }
+
+    fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+        if path.as_os_str() == "-" {
+            Ok(Box::new(BufReader::new(stdin())))
+        } else {
+            let file = File::open(path)
+                .with_context(|| format!("Failed opening video file {}", path.display()))?;
+
+            Ok(Box::new(BufReader::with_capacity(100_000, file)))
+        }
+    }
+
+    pub fn format(&self) -> &FrameFormat {
+        &self.format
+    }
+
+    fn parse_y4m_header(header: &str) -> Result<FrameFormat> {
+        let mut params = header.split(' ');
+
+        ensure!(
+            params.next() == Some(Y4M_SIGNATURE),
+            "Invalid Y4M file: missing {Y4M_SIGNATURE} signature"
+        );
+
+        let mut width = None;
+        let mut height = None;
+        let mut colorspace = "420";
+        let mut full_range = false;
+
+        for param in params.filter(|e| !e.is_empty()) {
+            let (tag, value) = param.split_at(1);
+
+            match tag {
+                "W" => width = Some(value.parse::<usize>()?),
+                "H" => height = Some(value.parse::<usize>()?),
+                "C" => colorspace = value,
+                "X" => {
+                    if let Some(range) = value.strip_prefix("COLORRANGE=") {
+                        full_range = range.eq_ignore_ascii_case("FULL");
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (Some(width), Some(height)) = (width, height) else {
+            bail!("Invalid Y4M header: missing frame dimensions");
+        };
+
+        let (chroma, bit_depth) = match colorspace {
+            "420" | "420jpeg" | "420mpeg2" | "420paldv" => (ChromaSubsampling::Cs420, 8),
+            "422" => (ChromaSubsampling::Cs422, 8),
+            "444" => (ChromaSubsampling::Cs444, 8),
+            "mono" => (ChromaSubsampling::Mono, 8),
+            "mono16" => (ChromaSubsampling::Mono, 16),
+            cs => {
+                let Some((chroma, depth)) = cs.split_once('p') else {
+                    bail!("Unsupported Y4M colorspace {cs}");
+                };
+
+                let chroma = match chroma {
+                    "420" => ChromaSubsampling::Cs420,
+                    "422" => ChromaSubsampling::Cs422,
+                    "444" => ChromaSubsampling::Cs444,
+                    _ => bail!("Unsupported Y4M colorspace {cs}"),
+                };
+                let depth = depth
+                    .parse::<u8>()
+                    .with_context(|| format!("Unsupported Y4M colorspace {cs}"))?;
+
+                (chroma, depth)
+            }
+        };
+
+        let format = FrameFormat {
+            width,
+            height,
+            bit_depth,
+            chroma,
+            full_range,
+        };
+        format.validate()?;
+
+        Ok(format)
+    }
+
+    /// Reads the next frame, `None` at the end of the stream
+    pub fn next_frame(&mut self) -> Result<Option<Frame>> {
+        if self.y4m {
+            let mut frame_header = String::new();
+
+            if self.reader.read_line(&mut frame_header)? == 0 {
+                return Ok(None);
+            }
+
+            ensure!(
+                frame_header.starts_with(Y4M_FRAME_HEADER),
+                "Invalid Y4M frame header"
+            );
+        } else if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        self.reader
+            .read_exact(&mut self.buf)
+            .context("Incomplete frame data")?;
+
+        let samples: Vec<u16> = if self.format.bytes_per_sample() == 2 {
+            self.buf
+                .chunks_exact(2)
+                .map(|e| u16::from_le_bytes([e[0], e[1]]))
+                .collect()
+        } else {
+            self.buf.iter().map(|e| *e as u16).collect()
+        };
+
+        let luma_size = self.format.width * self.format.height;
+        let (chroma_width, chroma_height) = self.format.chroma_dims();
+        let chroma_size = chroma_width * chroma_height;
+
+        let mut samples = samples.into_iter();
+        let y = samples.by_ref().take(luma_size).collect();
+        let u = samples.by_ref().take(chroma_size).collect();
+        let v = samples.collect();
+
+        Ok(Some(Frame { y, u, v }))
+    }
+}
+
+impl Frame {
+    /// Computes the PQ statistics of the max RGB component of every pixel.
+    /// The frame is assumed to be PQ encoded with BT.2020 non-constant luminance
+    pub fn stats(&self, format: &FrameFormat) -> FrameStats {
+        // BT.2020 YCbCr to R'G'B'
+        const CR_TO_R: f64 = 1.4746;
+        const CB_TO_G: f64 = 0.16455;
+        const CR_TO_G: f64 = 0.57135;
+        const CB_TO_B: f64 = 1.8814;
+
+        let (shift_x, shift_y) = format.chroma.shifts();
+        let (chroma_width, _) = format.chroma_dims();
+        let has_chroma = format.chroma != ChromaSubsampling::Mono;
+
+        let mut min_pq = f64::MAX;
+        let mut max_pq = 0.0_f64;
+        let mut sum = 0.0;
+        let mut nits_sum = 0.0;
+        let mut histogram = [0.0; HISTOGRAM_BINS];
+
+        for (row, luma_row) in self.y.chunks_exact(format.width).enumerate() {
+            let chroma_row = (row >> shift_y) * chroma_width;
+
+            for (col, luma) in luma_row.iter().enumerate() {
+                let y = format.normalize_luma(*luma);
+
+                let max_rgb = if has_chroma {
+                    let chroma_idx = chroma_row + (col >> shift_x);
+                    let cb = format.normalize_chroma(self.u[chroma_idx]);
+                    let cr = format.normalize_chroma(self.v[chroma_idx]);
+
+                    let r = y + CR_TO_R * cr;
+                    let g = y - CB_TO_G * cb - CR_TO_G * cr;
+                    let b = y + CB_TO_B * cb;
+
+                    r.max(g).max(b)
+                } else {
+                    y
+                };
+                let max_rgb = max_rgb.clamp(0.0, 1.0);
+
+                min_pq = min_pq.min(max_rgb);
+                max_pq = max_pq.max(max_rgb);
+                sum += max_rgb;
+                nits_sum += PQ_12_BIT_NITS[(max_rgb * 4095.0).round() as usize];
+
+                let bin = ((max_rgb * HISTOGRAM_BINS as f64) as usize).min(HISTOGRAM_BINS - 1);
+                histogram[bin] += 1.0;
+            }
+        }
+
+        let pixel_count = (format.width * format.height) as f64;
+        histogram.iter_mut().for_each(|e| *e /= pixel_count);
+
+        FrameStats {
+            min_pq,
+            max_pq,
+            avg_pq: sum / pixel_count,
+            avg_nits: nits_sum / pixel_count,
+            histogram,
+        }
+    }
+
+    /// Black bars around the picture, in pixels. `None` if the frame is entirely black.
+    /// A row or column is black when all of its luma samples are below `threshold`
+    pub fn black_bars(&self, format: &FrameFormat, threshold: f64) -> Option<BlackBars> {
+        let width = format.width;
+        let is_active = |value: u16| format.normalize_luma(value) > threshold;
+
+        let rows: Vec<&[u16]> = self.y.chunks_exact(width).collect();
+        let row_is_active = |row: &&[u16]| row.iter().any(|e| is_active(*e));
+
+        let top = rows.iter().position(row_is_active)?;
+        let bottom = rows.iter().rev().position(row_is_active)?;
+
+        let active_rows = &rows[top..rows.len() - bottom];
+        let column_is_active = |col: &usize| active_rows.iter().any(|row| is_active(row[*col]));
+
+        let left = (0..width).find(column_is_active)?;
+        let right = width - 1 - (0..width).rev().find(column_is_active)?;
+
+        Some(BlackBars {
+            left,
+            right,
+            top,
+            bottom,
+        })
+    }
+}
+
+impl BlackBars {
+    /// Aspect ratio of the active picture, assuming square pixels
+    pub fn active_aspect_ratio(&self, format: &FrameFormat) -> f32 {
+        let active_width = format.width - self.left - self.right;
+        let active_height = format.height - self.top - self.bottom;
+
+        active_width as f32 / active_height as f32
+    }
+}
+
+impl FrameStats {
+    /// Difference between the frames' histograms, from 0.0 (identical) to 1.0
+    pub fn difference(&self, other: &Self) -> f64 {
+        let diff: f64 = self
+            .histogram
+            .iter()
+            .zip(other.histogram.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+
+        diff / 2.0
+    }
+}
+
+/// Returns the first frame of every scene, detected from the histogram difference
+/// between consecutive frames
+pub fn detect_scene_cuts(stats: &[FrameStats], threshold: f64) -> Vec<usize> {
+    if stats.is_empty() {
+        return Vec::new();
+    }
+
+    let cuts = stats
+        .windows(2)
+        .enumerate()
+        .filter(|(_, frames)| frames[1].difference(&frames[0]) > threshold)
+        .map(|(i, _)| i + 1);
+
+    std::iter::once(0).chain(cuts).collect()
+}
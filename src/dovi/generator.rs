@@ -5,12 +5,16 @@ use std::fs::File;
 use std::io::{Write, stdout};
 use std::path::{Path, PathBuf};
 
+use super::frame_analyzer::{ChromaSubsampling, FrameFormat, FrameReader, detect_scene_cuts};
+use super::madvr;
+use super::shot_list::{ShotListTable, parse_scene_cuts_file, shots_from_scene_cuts};
 use crate::commands::GenerateArgs;
 use dolby_vision::rpu::extension_metadata::blocks::{
     ExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel6,
 };
 use dolby_vision::rpu::generate::{GenerateConfig, GenerateProfile, ShotFrameEdit, VideoShot};
-use dolby_vision::utils::nits_to_pq_12_bit;
+use dolby_vision::timecode::FrameRate;
+use dolby_vision::utils::{nits_to_pq_12_bit, pq_to_nits};
 use dolby_vision::xml::{CmXmlParser, XmlParserOpts};
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,8 +36,18 @@ pub struct Generator {
     xml_path: Option<PathBuf>,
     canvas_width: Option<u16>,
     canvas_height: Option<u16>,
+    xml_output: Option<String>,
+    xml_track: Option<String>,
+    list_xml_outputs: bool,
     madvr_path: Option<PathBuf>,
     use_custom_targets: bool,
+    y4m_path: Option<PathBuf>,
+    raw_yuv_path: Option<PathBuf>,
+    raw_yuv_format: Option<FrameFormat>,
+    scene_threshold: f64,
+    csv_path: Option<PathBuf>,
+    scene_cuts_path: Option<PathBuf>,
+    frame_rate: Option<FrameRate>,
     profile: Option<GeneratorProfile>,
     long_play_mode: Option<bool>,
 
@@ -50,12 +64,41 @@ impl Generator {
             xml,
             canvas_width,
             canvas_height,
+            xml_output,
+            xml_track,
+            list_xml_outputs,
             madvr_file,
             use_custom_targets,
+            y4m,
+            raw_yuv,
+            frame_width,
+            frame_height,
+            bit_depth,
+            full_range,
+            scene_threshold,
+            csv,
+            scene_cuts,
+            frame_rate,
             profile,
             long_play_mode,
         } = args;
 
+        let raw_yuv_format = match (frame_width, frame_height) {
+            (Some(width), Some(height)) => Some(FrameFormat {
+                width,
+                height,
+                bit_depth,
+                chroma: ChromaSubsampling::Cs420,
+                full_range,
+            }),
+            _ => None,
+        };
+
+        ensure!(
+            (0.0..=1.0).contains(&scene_threshold),
+            "Invalid scene threshold {scene_threshold}, must be between 0.0 and 1.0"
+        );
+
         let out_path = if let Some(out_path) = rpu_out {
             out_path
         } else {
@@ -70,8 +113,18 @@ impl Generator {
             xml_path: xml,
             canvas_width,
             canvas_height,
+            xml_output,
+            xml_track,
+            list_xml_outputs,
             madvr_path: madvr_file,
             use_custom_targets,
+            y4m_path: y4m,
+            raw_yuv_path: raw_yuv,
+            raw_yuv_format,
+            scene_threshold,
+            csv_path: csv,
+            scene_cuts_path: scene_cuts,
+            frame_rate,
             profile,
             config: None,
             long_play_mode,
@@ -86,15 +139,16 @@ impl Generator {
     }
 
     pub fn execute(&mut self) -> Result<()> {
+        if let Some(xml_path) = self.xml_path.as_ref().filter(|_| self.list_xml_outputs) {
+            return list_xml_outputs(xml_path);
+        }
+
         let mut config = if let Some(json_path) = &self.json_path {
             let json_file = File::open(json_path)?;
 
             println!("Reading generate config file...");
             let mut config: GenerateConfig = serde_json::from_reader(&json_file)?;
 
-            // Set default to the config's CM version if it wasn't specified
-            config.l1_avg_pq_cm_version.get_or_insert(config.cm_version);
-
             if let Some(hdr10plus_path) = &self.hdr10plus_path {
                 let peak_source = self
                     .hdr10plus_peak_source
@@ -103,25 +157,54 @@ impl Generator {
                 parse_hdr10plus_for_l1(hdr10plus_path, *peak_source, &mut config)?;
             } else if let Some(madvr_path) = &self.madvr_path {
                 generate_metadata_from_madvr(madvr_path, self.use_custom_targets, &mut config)?;
-            } else if config.length == 0 && !config.shots.is_empty() {
-                // Set length from sum of shot durations
-                config.length = config.shots.iter().map(|s| s.duration).sum();
-            }
+            } else if let Some(y4m_path) = &self.y4m_path {
+                let reader = FrameReader::open_y4m(y4m_path)?;
+                generate_metadata_from_video(reader, self.scene_threshold, &mut config)?;
+            } else if let Some(raw_yuv_path) = &self.raw_yuv_path {
+                let Some(format) = self.raw_yuv_format else {
+                    bail!("Raw YUV input requires the frame width and height");
+                };
 
-            ensure!(
-                config.length > 0 || !config.shots.is_empty(),
-                "Missing number of RPUs to generate, and no shots to derive it from"
-            );
-
-            // Create a single shot by default
-            if config.shots.is_empty() {
-                config.shots.push(VideoShot {
-                    start: 0,
-                    duration: config.length,
-                    ..Default::default()
-                })
+                let reader = FrameReader::open_raw(raw_yuv_path, format)?;
+                generate_metadata_from_video(reader, self.scene_threshold, &mut config)?;
+            } else if let Some(csv_path) = &self.csv_path {
+                println!("Parsing shot list...");
+
+                let table = ShotListTable::from_path(csv_path)?;
+                config.shots = table.to_shots(
+                    self.frame_rate.as_ref(),
+                    config.l1_cm_version(),
+                    config.length,
+                )?;
+
+                let shots_end = config.shots.last().map_or(0, |s| s.start + s.duration);
+                ensure!(
+                    config.shots.first().is_some_and(|s| s.start == 0),
+                    "The shot list must start at frame 0"
+                );
+                ensure!(
+                    config.length == 0 || config.length == shots_end,
+                    "Config length {} does not match the shot list end frame {shots_end}",
+                    config.length
+                );
+
+                config.length = shots_end;
+            } else if let Some(scene_cuts_path) = &self.scene_cuts_path {
+                println!("Parsing scene cuts list...");
+
+                let scene_cuts = parse_scene_cuts_file(scene_cuts_path)?;
+                let mut shots = shots_from_scene_cuts(scene_cuts, config.length)?;
+
+                for (shot, override_shot) in shots.iter_mut().zip(config.shots.iter()) {
+                    shot.copy_metadata_from_shot(override_shot, None);
+                }
+
+                config.shots = shots;
             }
 
+            // Length and single shot defaults, L1 correction
+            config.fill_defaults()?;
+
             config
         } else if let Some(xml_path) = &self.xml_path {
             self.config_from_xml(xml_path)?
@@ -143,11 +226,6 @@ impl Generator {
         if let Some(config) = self.config.as_mut() {
             println!("Generating metadata: {}...", &config.profile);
 
-            // Correct L1 for sources other than XML
-            if self.xml_path.is_none() {
-                config.fixup_l1();
-            }
-
             config.write_rpus(&self.rpu_out)?;
 
             println!("Generated metadata for {} frames", config.length);
@@ -166,6 +244,8 @@ impl Generator {
         let parser_opts = XmlParserOpts {
             canvas_width: self.canvas_width,
             canvas_height: self.canvas_height,
+            output: self.xml_output.clone(),
+            track: self.xml_track.clone(),
         };
 
         let parser = CmXmlParser::parse_file(xml_path, parser_opts)?;
@@ -174,6 +254,31 @@ impl Generator {
     }
 }
 
+fn list_xml_outputs<P: AsRef<Path>>(xml_path: P) -> Result<()> {
+    let outputs = CmXmlParser::list_outputs_from_file(xml_path)?;
+    ensure!(!outputs.is_empty(), "Could not find Output node");
+
+    for (i, output) in outputs.iter().enumerate() {
+        let aspect_ratios = match (output.canvas_aspect_ratio, output.image_aspect_ratio) {
+            (Some(canvas_ar), Some(image_ar)) => {
+                format!(", canvas AR {canvas_ar}, image AR {image_ar}")
+            }
+            _ => String::new(),
+        };
+
+        println!(
+            "Output {i}: {}{aspect_ratios}",
+            output.name.as_deref().unwrap_or("(unnamed)")
+        );
+
+        for (j, track) in output.tracks.iter().enumerate() {
+            println!("  Track {j}: {}", track.as_deref().unwrap_or("(unnamed)"));
+        }
+    }
+
+    Ok(())
+}
+
 fn parse_hdr10plus_for_l1<P: AsRef<Path>>(
     hdr10plus_path: P,
     peak_source: PeakBrightnessSource,
@@ -223,7 +328,7 @@ fn parse_hdr10plus_for_l1<P: AsRef<Path>>(
                     min_pq,
                     max_pq,
                     avg_pq,
-                    config.l1_avg_pq_cm_version.unwrap(),
+                    config.l1_cm_version(),
                 ),
             )],
             ..Default::default()
@@ -267,9 +372,14 @@ pub fn generate_metadata_from_madvr<P: AsRef<Path>>(
     let mut madvr_shots = Vec::with_capacity(madvr_info.scenes.len());
 
     for (i, scene) in madvr_info.scenes.iter().enumerate() {
+        let frames = scene.get_frames(frame_count, &madvr_info.frames)?;
+
+        // Max of the frame averages, computed with the histogram bin centres
+        let scene_avg_pq = frames.iter().map(madvr::frame_avg_pq).fold(0.0, f64::max);
+
         let min_pq = 0;
         let max_pq = (scene.max_pq * 4095.0).round() as u16;
-        let avg_pq = (scene.avg_pq * 4095.0).round() as u16;
+        let avg_pq = (scene_avg_pq * 4095.0).round() as u16;
 
         let mut shot = VideoShot {
             start: scene.start as usize,
@@ -279,7 +389,7 @@ pub fn generate_metadata_from_madvr<P: AsRef<Path>>(
                     min_pq,
                     max_pq,
                     avg_pq,
-                    config.l1_avg_pq_cm_version.unwrap(),
+                    config.l1_cm_version(),
                 ),
             )],
             ..Default::default()
@@ -289,12 +399,9 @@ pub fn generate_metadata_from_madvr<P: AsRef<Path>>(
 
         if use_custom_targets && madvr_info.header.flags == 3 {
             // Use peak per frame, average from scene
-            let frames = scene.get_frames(frame_count, &madvr_info.frames)?;
-
             frames.iter().enumerate().for_each(|(i, f)| {
                 let min_pq = 0;
                 let max_pq = (f.target_pq * 4095.0).round() as u16;
-                let avg_pq = (scene.avg_pq * 4095.0).round() as u16;
 
                 let frame_edit = ShotFrameEdit {
                     edit_offset: i,
@@ -303,7 +410,7 @@ pub fn generate_metadata_from_madvr<P: AsRef<Path>>(
                             min_pq,
                             max_pq,
                             avg_pq,
-                            config.l1_avg_pq_cm_version.unwrap(),
+                            config.l1_cm_version(),
                         ),
                     )],
                 };
@@ -339,6 +446,87 @@ pub fn generate_metadata_from_madvr<P: AsRef<Path>>(
     Ok(())
 }
 
+pub fn generate_metadata_from_video(
+    mut reader: FrameReader,
+    scene_threshold: f64,
+    config: &mut GenerateConfig,
+) -> Result<()> {
+    println!("Measuring video frames...");
+    stdout().flush().ok();
+
+    let format = *reader.format();
+    let mut frame_stats = Vec::new();
+
+    while let Some(frame) = reader.next_frame()? {
+        frame_stats.push(frame.stats(&format));
+    }
+
+    ensure!(!frame_stats.is_empty(), "No frames to measure in the video");
+
+    let frame_count = frame_stats.len();
+    let scene_cuts = detect_scene_cuts(&frame_stats, scene_threshold);
+
+    println!(
+        "Measured {frame_count} frames, detected {} scenes",
+        scene_cuts.len()
+    );
+
+    let scene_ends = scene_cuts.iter().skip(1).copied().chain([frame_count]);
+    let mut video_shots = Vec::with_capacity(scene_cuts.len());
+
+    for (i, (start, end)) in scene_cuts.iter().copied().zip(scene_ends).enumerate() {
+        let scene_stats = &frame_stats[start..end];
+
+        let min_pq = scene_stats
+            .iter()
+            .map(|e| e.min_pq)
+            .fold(f64::MAX, f64::min);
+        let max_pq = scene_stats.iter().map(|e| e.max_pq).fold(0.0, f64::max);
+        let avg_pq = scene_stats.iter().map(|e| e.avg_pq).sum::<f64>() / scene_stats.len() as f64;
+
+        let mut shot = VideoShot {
+            start,
+            duration: end - start,
+            metadata_blocks: vec![ExtMetadataBlock::Level1(
+                ExtMetadataBlockLevel1::from_stats_cm_version(
+                    (min_pq * 4095.0).round() as u16,
+                    (max_pq * 4095.0).round() as u16,
+                    (avg_pq * 4095.0).round() as u16,
+                    config.l1_cm_version(),
+                ),
+            )],
+            ..Default::default()
+        };
+
+        if let Some(override_shot) = config.shots.get(i) {
+            shot.copy_metadata_from_shot(override_shot, Some(&[1]))
+        }
+
+        video_shots.push(shot);
+    }
+
+    // Now that the metadata was copied, we can replace the shots
+    config.shots.clear();
+    config.shots.extend(video_shots);
+
+    // Set MaxCLL and MaxFALL if not set in config
+    if let Some(config_l6) = config.level6.as_mut() {
+        if config_l6.max_content_light_level == 0 {
+            let max_pq = frame_stats.iter().map(|e| e.max_pq).fold(0.0, f64::max);
+            config_l6.max_content_light_level = pq_to_nits(max_pq).round() as u16;
+        }
+
+        if config_l6.max_frame_average_light_level == 0 {
+            let max_fall = frame_stats.iter().map(|e| e.avg_nits).fold(0.0, f64::max);
+            config_l6.max_frame_average_light_level = max_fall.round() as u16;
+        }
+    }
+
+    config.length = frame_count;
+
+    Ok(())
+}
+
 impl From<GeneratorProfile> for GenerateProfile {
     fn from(p: GeneratorProfile) -> Self {
         match p {
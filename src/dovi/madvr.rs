@@ -0,0 +1,75 @@
+use dolby_vision::utils::nits_to_pq;
+use madvr_parse::MadVRFrame;
+
+/// Black bars are ignored by madVR when the first bin holds between 2% and 30% of the frame
+const BLACK_BARS_PERCENT: std::ops::Range<f64> = 2.0..30.0;
+
+/// Luminance at the centre of a madVR v5 histogram bin.
+/// The first 64 bins cover 0-100 nits, the other 192 bins cover 100-10000 nits, in PQ steps.
+pub fn histogram_bin_pq(bin: usize) -> f64 {
+    let sdr_peak_pq = nits_to_pq(100.0);
+
+    if bin < 64 {
+        (bin as f64 + 0.5) * sdr_peak_pq / 64.0
+    } else {
+        sdr_peak_pq + (bin as f64 - 64.0 + 0.5) * (1.0 - sdr_peak_pq) / 192.0
+    }
+}
+
+/// Average PQ of a frame computed from its 256 bins luminance histogram, using the bin centres.
+///
+/// `madvr_parse` does not use the bin centres, so its average is only used for older versions.
+pub fn frame_avg_pq(frame: &MadVRFrame) -> f64 {
+    let histogram = &frame.lum_histogram;
+
+    if histogram.len() != 256 {
+        return frame.avg_pq;
+    }
+
+    let percent_sum: f64 = histogram.iter().sum();
+    if percent_sum <= 0.0 {
+        return 0.0;
+    }
+
+    let avg_pq: f64 = histogram
+        .iter()
+        .enumerate()
+        .filter(|(i, percent)| !(*i == 0 && BLACK_BARS_PERCENT.contains(*percent)))
+        .map(|(i, percent)| histogram_bin_pq(i) * percent / 100.0)
+        .sum();
+
+    (avg_pq * (100.0 / percent_sum)).min(1.0)
+}
+
+/// Synthesizes a 256 bins luminance histogram (in percent) with the requested average PQ,
+/// splitting the frame between the two bins surrounding the average
+pub fn lum_histogram(avg_pq: f64) -> Vec<f64> {
+    let mut histogram = vec![0.0; 256];
+
+    let centres: Vec<f64> = (0..256).map(histogram_bin_pq).collect();
+
+    let upper = centres
+        .iter()
+        .position(|pq| *pq >= avg_pq)
+        .unwrap_or(centres.len() - 1);
+
+    // Bin 0 partially filled is ignored as black bars, use the closest bin instead
+    if upper <= 1 || centres[upper] <= avg_pq {
+        let lower = upper.saturating_sub(1);
+
+        let closest = if (avg_pq - centres[lower]).abs() < (centres[upper] - avg_pq).abs() {
+            lower
+        } else {
+            upper
+        };
+        histogram[closest] = 100.0;
+    } else {
+        let lower = upper - 1;
+
+        let upper_weight = (avg_pq - centres[lower]) / (centres[upper] - centres[lower]);
+        histogram[lower] = (1.0 - upper_weight) * 100.0;
+        histogram[upper] = upper_weight * 100.0;
+    }
+
+    histogram
+}
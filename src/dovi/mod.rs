@@ -6,24 +6,31 @@ use anyhow::{Result, bail};
 use indicatif::{ProgressBar, ProgressStyle};
 
 use dolby_vision::rpu::dovi_rpu::DoviRpu;
+use dolby_vision::rpu::editor::EditConfig;
 
 use hevc_parser::hevc::{NAL_UNSPEC62, NALUnit};
 use hevc_parser::io::{IoFormat, StartCodePreset};
 
-use self::editor::EditConfig;
 use super::commands::ConversionModeCli;
 
+pub mod active_area_detector;
+pub mod concatenator;
 pub mod converter;
 pub mod demuxer;
 pub mod editor;
 pub mod exporter;
+pub mod frame_analyzer;
 pub mod generator;
+pub mod madvr;
 pub mod muxer;
 pub mod plotter;
 pub mod remover;
+pub mod repairer;
 pub mod rpu_extractor;
 pub mod rpu_info;
 pub mod rpu_injector;
+pub mod shot_list;
+pub mod splitter;
 
 mod general_read_write;
 mod hdr10plus_utils;
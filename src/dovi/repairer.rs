@@ -0,0 +1,215 @@
+use std::fs::File;
+use std::io::{BufReader, Write, stdout};
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use dolby_vision::rpu::dovi_rpu::RpuParseOpts;
+use dolby_vision::rpu::generate::GenerateConfig;
+use dolby_vision::rpu::utils::{RpuReader, RpuReaderFormat};
+
+use super::{DoviRpu, input_from_either, write_rpu_file};
+use crate::commands::RepairArgs;
+
+pub struct Repairer {
+    input: PathBuf,
+    rpu_out: PathBuf,
+    replace_invalid: bool,
+    report: Option<PathBuf>,
+}
+
+#[derive(Serialize, Default, Debug)]
+struct RepairReport {
+    frames: usize,
+    repaired: Vec<RepairedFrame>,
+    invalid: Vec<InvalidFrame>,
+}
+
+#[derive(Serialize, Debug)]
+struct RepairedFrame {
+    frame: usize,
+    issues: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct InvalidFrame {
+    frame: usize,
+    error: String,
+
+    /// Frame copied in place of the invalid RPU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replaced_by: Option<usize>,
+}
+
+impl Repairer {
+    pub fn from_args(args: RepairArgs) -> Result<Self> {
+        let RepairArgs {
+            input,
+            input_pos,
+            rpu_out,
+            replace_invalid,
+            report,
+        } = args;
+
+        let input = input_from_either("repair", input, input_pos)?;
+
+        Ok(Self {
+            input,
+            rpu_out: rpu_out.unwrap_or_else(|| PathBuf::from("RPU_repaired.bin")),
+            replace_invalid,
+            report,
+        })
+    }
+
+    pub fn repair(args: RepairArgs) -> Result<()> {
+        let repairer = Repairer::from_args(args)?;
+
+        println!("Parsing RPU file...");
+        stdout().flush().ok();
+
+        let reader = RpuReader::new(
+            BufReader::new(File::open(&repairer.input)?),
+            RpuReaderFormat::Hevc,
+        )
+        .with_opts(RpuParseOpts::default().with_repair(true));
+
+        let results: Vec<Result<DoviRpu>> = reader.collect();
+        if results.is_empty() {
+            bail!("No RPU found");
+        }
+
+        let mut report = RepairReport {
+            frames: results.len(),
+            ..Default::default()
+        };
+
+        let mut rpus = Vec::with_capacity(results.len());
+        for (frame, res) in results.into_iter().enumerate() {
+            match res {
+                Ok(rpu) => {
+                    if !rpu.repairs.is_empty() {
+                        report.repaired.push(RepairedFrame {
+                            frame,
+                            issues: rpu.repairs.iter().map(ToString::to_string).collect(),
+                        });
+                    }
+
+                    rpus.push(Some(rpu));
+                }
+                Err(e) => {
+                    report.invalid.push(InvalidFrame {
+                        frame,
+                        error: format!("{e:#}"),
+                        replaced_by: None,
+                    });
+
+                    rpus.push(None);
+                }
+            }
+        }
+
+        if repairer.replace_invalid {
+            Self::replace_invalid_frames(&mut rpus, &mut report)?;
+        }
+
+        Self::print_report(&report);
+
+        if let Some(path) = &repairer.report {
+            serde_json::to_writer_pretty(File::create(path)?, &report)?;
+        }
+
+        if !report.invalid.is_empty() && !repairer.replace_invalid {
+            bail!(
+                "{} RPUs could not be repaired, use --replace-invalid to replace them",
+                report.invalid.len()
+            );
+        }
+
+        let rpus: Vec<DoviRpu> = rpus.into_iter().flatten().collect();
+        let data = GenerateConfig::encode_rpus(&rpus).collect::<Result<Vec<_>>>()?;
+        write_rpu_file(&repairer.rpu_out, data)?;
+
+        Ok(())
+    }
+
+    /// Replaces the invalid RPUs with a copy of the nearest valid RPU, preferring the previous one.
+    /// The next RPU is not used when it starts a new shot, unless there is no previous RPU.
+    fn replace_invalid_frames(
+        rpus: &mut [Option<DoviRpu>],
+        report: &mut RepairReport,
+    ) -> Result<()> {
+        let valid_frames: Vec<usize> = rpus
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rpu)| rpu.as_ref().map(|_| i))
+            .collect();
+
+        if valid_frames.is_empty() {
+            bail!("No valid RPU to replace the invalid frames with");
+        }
+
+        for invalid in report.invalid.iter_mut() {
+            let frame = invalid.frame;
+            let next_idx = valid_frames.partition_point(|i| *i < frame);
+
+            let prev = next_idx.checked_sub(1).map(|i| valid_frames[i]);
+            let next = valid_frames.get(next_idx).copied();
+
+            let source = match (prev, next) {
+                (Some(prev), Some(next))
+                    if next - frame < frame - prev && !Self::is_scene_cut(&rpus[next]) =>
+                {
+                    next
+                }
+                (Some(prev), _) => prev,
+                (None, Some(next)) => next,
+                (None, None) => unreachable!(),
+            };
+
+            let mut rpu = rpus[source].clone();
+            if let Some(rpu) = rpu.as_mut() {
+                // The copies only extend the neighbouring shot, the first frame always starts one
+                if let Some(vdr_dm_data) = rpu.vdr_dm_data.as_mut() {
+                    vdr_dm_data.set_scene_cut(frame == 0);
+                }
+
+                rpu.modified = true;
+            }
+
+            rpus[frame] = rpu;
+            invalid.replaced_by = Some(source);
+        }
+
+        Ok(())
+    }
+
+    fn is_scene_cut(rpu: &Option<DoviRpu>) -> bool {
+        rpu.as_ref()
+            .and_then(|rpu| rpu.vdr_dm_data.as_ref())
+            .is_some_and(|vdr| vdr.scene_refresh_flag == 1)
+    }
+
+    fn print_report(report: &RepairReport) {
+        for repaired in &report.repaired {
+            println!("Frame {}: {}", repaired.frame, repaired.issues.join(", "));
+        }
+
+        for invalid in &report.invalid {
+            match invalid.replaced_by {
+                Some(source) => println!(
+                    "Frame {}: replaced by frame {source}: {}",
+                    invalid.frame, invalid.error
+                ),
+                None => println!("Frame {}: invalid: {}", invalid.frame, invalid.error),
+            }
+        }
+
+        println!(
+            "{} RPUs, {} repaired, {} invalid",
+            report.frames,
+            report.repaired.len(),
+            report.invalid.len()
+        );
+    }
+}
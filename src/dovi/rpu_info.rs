@@ -1,18 +1,21 @@
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write as IoWrite};
 use std::path::PathBuf;
 
-use anyhow::{Result, bail, ensure};
-use dolby_vision::rpu::dovi_rpu::DoviRpu;
+use anyhow::{Context, Result, bail, ensure};
+use dolby_vision::rpu::dovi_rpu::{DoviRpu, RpuParseOpts};
 use dolby_vision::rpu::extension_metadata::MasteringDisplayPrimaries;
 use dolby_vision::rpu::extension_metadata::blocks::{
-    ExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel2, ExtMetadataBlockLevel5,
-    ExtMetadataBlockLevel6, ExtMetadataBlockLevel8,
+    ExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel2, ExtMetadataBlockLevel3,
+    ExtMetadataBlockLevel5, ExtMetadataBlockLevel6, ExtMetadataBlockLevel8,
 };
-use dolby_vision::rpu::utils::parse_rpu_file;
-use dolby_vision::rpu::vdr_dm_data::CmVersion;
+use dolby_vision::rpu::utils::{RpuReader, RpuReaderFormat, parse_rpu_file};
+use dolby_vision::rpu::vdr_dm_data::{CmVersion, VdrDmData};
 use dolby_vision::utils::{nits_to_pq_12_bit, pq_to_nits};
 use itertools::Itertools;
+use serde::Serialize;
 
 use super::input_from_either;
 use crate::commands::InfoArgs;
@@ -21,6 +24,7 @@ pub struct RpuInfo {
     input: PathBuf,
 }
 
+#[derive(Serialize)]
 pub struct AggregateStats {
     pub min: f64,
     pub max: f64,
@@ -51,6 +55,60 @@ pub struct RpusListSummary {
     pub l8_stats_hue: Option<SummaryL8VectorStats>,
 }
 
+/// Machine readable summary of the RPU list.
+/// Every key is always present, so the schema stays stable across files.
+#[derive(Serialize)]
+pub struct SummaryReport {
+    pub frames: usize,
+    pub profiles: Vec<u8>,
+    pub el_types: Vec<String>,
+    pub dm_version: SummaryDmVersion,
+    pub scene_count: usize,
+    pub mastering_displays: Vec<SummaryMasteringDisplay>,
+    pub l1: SummaryL1Stats,
+    pub l5: Option<SummaryL5Ranges>,
+    pub l6: Vec<ExtMetadataBlockLevel6>,
+    pub l2_targets: Vec<u16>,
+    pub l8_targets: Vec<u16>,
+    pub l9_mdp: Vec<SummaryL9Mdp>,
+}
+
+#[derive(Serialize)]
+pub struct SummaryDmVersion {
+    pub cmv29_count: usize,
+    pub cmv40_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct SummaryMasteringDisplay {
+    pub min_pq: u16,
+    pub max_pq: u16,
+    pub min_nits: f64,
+    pub max_nits: f64,
+}
+
+#[derive(Serialize)]
+pub struct SummaryL5Ranges {
+    pub top: SummaryRange,
+    pub bottom: SummaryRange,
+    pub left: SummaryRange,
+    pub right: SummaryRange,
+}
+
+#[derive(Serialize)]
+pub struct SummaryRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+#[derive(Serialize)]
+pub struct SummaryL9Mdp {
+    pub source_primary_index: u8,
+    pub primaries: String,
+    pub frames: usize,
+}
+
+#[derive(Clone, Copy, Serialize)]
 pub struct SummaryL1Stats {
     pub maxcll: f64,
     pub maxcll_avg: f64,
@@ -81,6 +139,36 @@ pub struct SummaryL8VectorStats {
     pub magenta: AggregateStats,
 }
 
+/// Statistics of a single scene/shot, delimited by `scene_refresh_flag`.
+///
+/// L1 stats are aggregated over every frame of the scene, in nits.
+/// The other levels are taken from the first frame of the scene.
+#[derive(Serialize)]
+pub struct SceneStats {
+    pub start: usize,
+    pub end: usize,
+    pub duration: usize,
+
+    pub l1: AggregateStats,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub l2: Vec<SceneTrim<ExtMetadataBlockLevel2>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l3: Option<ExtMetadataBlockLevel3>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l5: Option<ExtMetadataBlockLevel5>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub l8: Vec<SceneTrim<ExtMetadataBlockLevel8>>,
+}
+
+#[derive(Serialize)]
+pub struct SceneTrim<T> {
+    pub target_nits: u16,
+
+    #[serde(flatten)]
+    pub trim: T,
+}
+
 impl RpuInfo {
     pub fn info(args: InfoArgs) -> Result<()> {
         let InfoArgs {
@@ -88,9 +176,13 @@ impl RpuInfo {
             input_pos,
             frame,
             summary,
+            scenes,
+            trace,
+            json,
+            output,
         } = args;
 
-        if !summary && frame.is_none() {
+        if !summary && !scenes && frame.is_none() {
             bail!("No frame number to look up");
         }
 
@@ -98,10 +190,45 @@ impl RpuInfo {
 
         let info = RpuInfo { input };
 
-        println!("Parsing RPU file...");
+        if trace && let Some(frame) = frame {
+            return info.trace_frame(frame, json, output);
+        }
+
+        // Keep stdout clean for machine readable output
+        let machine_readable = scenes || json;
+        if !machine_readable || output.is_some() {
+            println!("Parsing RPU file...");
+        }
 
         let rpus = parse_rpu_file(info.input)?;
 
+        if scenes {
+            let scene_stats = SceneStats::from_rpus(&rpus);
+            let mut writer = Self::output_writer(output)?;
+
+            if json {
+                serde_json::to_writer_pretty(&mut writer, &scene_stats)?;
+                writeln!(writer)?;
+            } else {
+                SceneStats::write_csv(&scene_stats, &mut writer)?;
+            }
+
+            writer.flush()?;
+
+            return Ok(());
+        }
+
+        if summary && json {
+            let report = SummaryReport::new(&rpus);
+            let mut writer = Self::output_writer(output)?;
+
+            serde_json::to_writer_pretty(&mut writer, &report)?;
+            writeln!(writer)?;
+            writer.flush()?;
+
+            return Ok(());
+        }
+
         if let Some(f) = frame {
             ensure!(
                 f < rpus.len(),
@@ -185,35 +312,63 @@ impl RpuInfo {
 
         Ok(())
     }
+
+    /// Traces the raw RPU of the frame, even if it cannot be parsed
+    fn trace_frame(&self, frame: usize, json: bool, output: Option<PathBuf>) -> Result<()> {
+        let mut reader = RpuReader::new(
+            BufReader::new(File::open(&self.input)?),
+            RpuReaderFormat::Hevc,
+        );
+
+        let mut count = 0;
+        let data = loop {
+            match reader.next_payload() {
+                Some(res) if count == frame => break res?,
+                Some(res) => {
+                    res?;
+                    count += 1;
+                }
+                None => {
+                    bail!("info: invalid frame number (out of range).\nNumber of RPUs: {count}")
+                }
+            }
+        };
+
+        let (res, trace) = DoviRpu::trace_unspec62_nalu(&data, RpuParseOpts::default());
+        let mut writer = Self::output_writer(output)?;
+
+        if json {
+            serde_json::to_writer_pretty(&mut writer, &trace)?;
+            writeln!(writer)?;
+        } else {
+            write!(writer, "{trace}")?;
+        }
+
+        writer.flush()?;
+
+        res.map(|_| ())
+            .with_context(|| format!("info: frame {frame} could not be parsed"))
+    }
+
+    fn output_writer(output: Option<PathBuf>) -> Result<Box<dyn IoWrite>> {
+        Ok(if let Some(output) = output {
+            Box::new(BufWriter::new(File::create(output)?))
+        } else {
+            Box::new(BufWriter::new(io::stdout().lock()))
+        })
+    }
 }
 
 impl RpusListSummary {
     pub fn new(rpus: &[DoviRpu]) -> Result<Self> {
-        let profiles = rpus
-            .iter()
-            .map(|rpu| rpu.dovi_profile)
-            .unique()
-            .sorted()
-            .join(", ");
+        let l1_data = frame_l1_data(rpus);
+        let report = SummaryReport::with_l1_data(rpus, &l1_data);
 
-        let dmv1_count = rpus
-            .iter()
-            .filter(|rpu| {
-                rpu.vdr_dm_data
-                    .as_ref()
-                    .and_then(|vdr| vdr.cmv29_metadata.as_ref())
-                    .is_some()
-            })
-            .count();
-        let dmv2_count = rpus
-            .iter()
-            .filter(|rpu| {
-                rpu.vdr_dm_data
-                    .as_ref()
-                    .and_then(|vdr| vdr.cmv40_metadata.as_ref())
-                    .is_some()
-            })
-            .count();
+        let profiles = report.profiles.iter().join(", ");
+        let SummaryDmVersion {
+            cmv29_count: dmv1_count,
+            cmv40_count: dmv2_count,
+        } = report.dm_version;
 
         let dmv2 = dmv2_count == dmv1_count;
         let (dm_version_counts, dm_version_str) = if dmv2 {
@@ -224,16 +379,6 @@ impl RpusListSummary {
             (Some((dmv1_count, dmv2_count)), "1 + 2 (CM 2.9 and 4.0)")
         };
 
-        let scene_count = rpus
-            .iter()
-            .filter(|rpu| {
-                rpu.vdr_dm_data
-                    .as_ref()
-                    .and_then(|vdr| (vdr.scene_refresh_flag == 1).then_some(1))
-                    .is_some()
-            })
-            .count();
-
         // Profile
         let mut profiles_str = "Profile".to_string();
         if profiles.contains(", ") {
@@ -243,55 +388,21 @@ impl RpusListSummary {
 
         if profiles.contains('7') {
             let idx = profiles_str.find('7').unwrap();
-
-            let subprofiles = rpus
-                .iter()
-                .filter_map(|rpu| rpu.el_type.as_ref().map(|e| e.to_string()))
-                .unique()
-                .sorted()
-                .join(", ");
+            let subprofiles = report.el_types.join(", ");
 
             profiles_str.insert_str(idx + 1, &format!(" ({subprofiles})"));
         }
 
         let mut rpu_mastering_meta_str = String::from("RPU mastering display: ");
-        let rpu_mastering_meta = rpus
+        let rpu_mastering_meta = report
+            .mastering_displays
             .iter()
-            .filter_map(|rpu| {
-                rpu.vdr_dm_data
-                    .as_ref()
-                    .map(|vdr| (vdr.source_min_pq, vdr.source_max_pq))
-            })
-            .unique()
-            .sorted()
-            .map(|meta| {
-                let min = (pq_to_nits(meta.0 as f64 / 4095.0) * 1e6).round() / 1e6;
-                let max = (pq_to_nits(meta.1 as f64 / 4095.0) / 1000.0).round() * 1000.0;
-
-                format!("{min:.4}/{max} nits")
-            })
+            .map(|meta| format!("{:.4}/{} nits", meta.min_nits, meta.max_nits))
             .join(", ");
         rpu_mastering_meta_str.push_str(&rpu_mastering_meta);
 
-        let l6_meta: Vec<ExtMetadataBlockLevel6> = rpus
-            .iter()
-            .filter_map(|rpu| {
-                rpu.vdr_dm_data.as_ref().and_then(|vdr| {
-                    vdr.get_block(6).map(|b| {
-                        if let ExtMetadataBlock::Level6(l6) = b {
-                            l6
-                        } else {
-                            unreachable!()
-                        }
-                    })
-                })
-            })
-            .unique()
-            .cloned()
-            .collect();
-
-        let l6_meta = if !l6_meta.is_empty() {
-            let l6_meta_str: Vec<String> = l6_meta.iter().map(|l6| {
+        let l6_meta = if !report.l6.is_empty() {
+            let l6_meta_str: Vec<String> = report.l6.iter().map(|l6| {
                 let min = l6.min_display_mastering_luminance as f64 / 10000.0;
                 let max = l6.max_display_mastering_luminance;
                 let maxcll = l6.max_content_light_level;
@@ -305,194 +416,56 @@ impl RpusListSummary {
             None
         };
 
-        let cm_version = if dmv2_count > 0 {
-            CmVersion::V40
-        } else {
-            CmVersion::V29
-        };
-        let default_l1_for_missing = ExtMetadataBlock::Level1(
-            ExtMetadataBlockLevel1::from_stats_cm_version(0, 0, 0, cm_version),
-        );
-
-        let l1_data = rpus
+        let l2_trims = report
+            .l2_targets
             .iter()
-            .map(|rpu| {
-                let block = rpu
-                    .vdr_dm_data
-                    .as_ref()
-                    .and_then(|dm| dm.get_block(1))
-                    .unwrap_or(&default_l1_for_missing);
-
-                if let ExtMetadataBlock::Level1(l1) = block {
-                    let min_pq = (l1.min_pq as f64) / 4095.0;
-                    let max_pq = (l1.max_pq as f64) / 4095.0;
-                    let avg_pq = (l1.avg_pq as f64) / 4095.0;
-
-                    AggregateStats {
-                        min: min_pq,
-                        max: max_pq,
-                        avg: avg_pq,
-                    }
-                } else {
-                    unreachable!();
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let min_pq_max_value = l1_data
-            .iter()
-            .map(|e| e.min)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
-
-        let max_pq_value = l1_data
-            .iter()
-            .map(|e| e.max)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
-        let max_pq_mean_value = l1_data.iter().map(|e| e.max).sum::<f64>() / l1_data.len() as f64;
-
-        let max_avg_pq_value = l1_data
-            .iter()
-            .map(|e| e.avg)
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
-        let avg_pq_mean_value = l1_data.iter().map(|e| e.avg).sum::<f64>() / l1_data.len() as f64;
-
-        let l1_stats = SummaryL1Stats {
-            maxcll: pq_to_nits(max_pq_value),
-            maxcll_avg: pq_to_nits(max_pq_mean_value),
-            maxfall: pq_to_nits(max_avg_pq_value),
-            maxfall_avg: pq_to_nits(avg_pq_mean_value),
-            max_min_nits: pq_to_nits(min_pq_max_value),
-        };
-
-        let l2_trims: Vec<String> = rpus
-            .iter()
-            .filter_map(|rpu| {
-                rpu.vdr_dm_data.as_ref().map(|vdr| {
-                    vdr.level_blocks_iter(2)
-                        .map(|b| {
-                            if let ExtMetadataBlock::Level2(l2) = b {
-                                l2.target_max_pq
-                            } else {
-                                unreachable!()
-                            }
-                        })
-                        .unique()
-                })
-            })
-            .flatten()
-            .unique()
-            .sorted()
-            .map(|target_max_pq| {
-                ((pq_to_nits(target_max_pq as f64 / 4095.0) / 100.0).round() * 100.0) as u16
-            })
             .map(|target_nits| format!("{target_nits} nits"))
             .collect();
 
-        let l5_blocks = rpus
-            .iter()
-            .filter_map(|rpu| {
-                rpu.vdr_dm_data.as_ref()?.get_block(5).and_then(|block| {
-                    if let ExtMetadataBlock::Level5(l5) = block {
-                        Some(l5)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .unique()
-            .collect::<Vec<_>>();
-
-        type L5Mapping = (&'static str, fn(&ExtMetadataBlockLevel5) -> u16);
-        let l5_mappings: [L5Mapping; 4] = [
-            ("top", |l5| l5.active_area_top_offset),
-            ("bottom", |l5| l5.active_area_bottom_offset),
-            ("left", |l5| l5.active_area_left_offset),
-            ("right", |l5| l5.active_area_right_offset),
-        ];
-        let l5_str = l5_mappings
+        let l5_str = match &report.l5 {
+            Some(l5) => [
+                ("top", &l5.top),
+                ("bottom", &l5.bottom),
+                ("left", &l5.left),
+                ("right", &l5.right),
+            ]
             .iter()
-            .map(|(area, offset_extractor)| {
-                l5_blocks
-                    .iter()
-                    .map(|l5| offset_extractor(l5))
-                    .minmax()
-                    .into_option()
-                    .map_or(format!("{area}=N/A"), |(min, max)| {
-                        if min == max {
-                            format!("{area}={min}")
-                        } else {
-                            format!("{area}={min}..{max}")
-                        }
-                    })
+            .map(|(area, range)| {
+                if range.min == range.max {
+                    format!("{area}={}", range.min)
+                } else {
+                    format!("{area}={}..{}", range.min, range.max)
+                }
             })
-            .join(", ");
+            .join(", "),
+            None => String::from("top=N/A, bottom=N/A, left=N/A, right=N/A"),
+        };
 
-        let l8_trims = if dmv2_count > 0 {
-            let l8_trims_str: Vec<String> = rpus
+        let l8_trims = (dmv2_count > 0).then(|| {
+            report
+                .l8_targets
                 .iter()
-                .filter_map(|rpu| {
-                    rpu.vdr_dm_data.as_ref().map(|vdr| {
-                        vdr.level_blocks_iter(8)
-                            .map(|b| {
-                                if let ExtMetadataBlock::Level8(l8) = b {
-                                    l8.trim_target_nits()
-                                } else {
-                                    unreachable!()
-                                }
-                            })
-                            .unique()
-                    })
-                })
-                .flatten()
-                .unique()
-                .sorted()
                 .map(|target_nits| format!("{target_nits} nits"))
-                .collect();
-
-            Some(l8_trims_str)
-        } else {
-            None
-        };
+                .collect()
+        });
 
-        let l9_mdp = if dmv2_count > 0 {
-            let l9_mdp_str: Vec<String> = rpus
+        let l9_mdp = (dmv2_count > 0).then(|| {
+            report
+                .l9_mdp
                 .iter()
-                .filter_map(|rpu| {
-                    rpu.vdr_dm_data.as_ref()?.get_block(9).and_then(|block| {
-                        if let ExtMetadataBlock::Level9(l9) = block {
-                            Some(l9.source_primary_index)
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .fold(HashMap::new(), |mut frames, idx| {
-                    *frames.entry(idx).or_insert(0) += 1;
-                    frames
-                })
-                .into_iter()
-                .sorted_by_key(|e| e.0)
-                .map(|(idx, frames)| {
-                    let alias = MasteringDisplayPrimaries::from(idx).to_string();
-                    if frames < dmv2_count {
-                        format!("{alias} ({frames})")
+                .map(|mdp| {
+                    if mdp.frames < dmv2_count {
+                        format!("{} ({})", mdp.primaries, mdp.frames)
                     } else {
-                        alias
+                        mdp.primaries.clone()
                     }
                 })
-                .collect();
-
-            Some(l9_mdp_str)
-        } else {
-            None
-        };
+                .collect()
+        });
 
         Ok(Self {
-            count: rpus.len(),
-            scene_count,
+            count: report.frames,
+            scene_count: report.scene_count,
             rpu_mastering_meta_str,
             profiles_str,
             dm_version_str,
@@ -503,7 +476,7 @@ impl RpusListSummary {
             l8_trims,
             l9_mdp,
             l1_data,
-            l1_stats,
+            l1_stats: report.l1,
             l2_trims,
             l2_data: None,
             l2_stats: None,
@@ -631,7 +604,7 @@ impl RpusListSummary {
         Ok(summary)
     }
 
-    fn min_max_avg<T, F>(data: &[T], field_extractor: F) -> AggregateStats
+    pub(crate) fn min_max_avg<T, F>(data: &[T], field_extractor: F) -> AggregateStats
     where
         F: Fn(&T) -> f64,
     {
@@ -656,3 +629,460 @@ impl RpusListSummary {
         }
     }
 }
+
+impl SummaryReport {
+    pub fn new(rpus: &[DoviRpu]) -> Self {
+        Self::with_l1_data(rpus, &frame_l1_data(rpus))
+    }
+
+    fn with_l1_data(rpus: &[DoviRpu], l1_data: &[AggregateStats]) -> Self {
+        let profiles = rpus
+            .iter()
+            .map(|rpu| rpu.dovi_profile)
+            .unique()
+            .sorted()
+            .collect();
+
+        let el_types = rpus
+            .iter()
+            .filter_map(|rpu| rpu.el_type.as_ref().map(|e| e.to_string()))
+            .unique()
+            .sorted()
+            .collect();
+
+        let cmv29_count = rpus
+            .iter()
+            .filter(|rpu| {
+                rpu.vdr_dm_data
+                    .as_ref()
+                    .and_then(|vdr| vdr.cmv29_metadata.as_ref())
+                    .is_some()
+            })
+            .count();
+        let cmv40_count = rpus
+            .iter()
+            .filter(|rpu| {
+                rpu.vdr_dm_data
+                    .as_ref()
+                    .and_then(|vdr| vdr.cmv40_metadata.as_ref())
+                    .is_some()
+            })
+            .count();
+
+        let scene_count = rpus
+            .iter()
+            .filter(|rpu| {
+                rpu.vdr_dm_data
+                    .as_ref()
+                    .is_some_and(|vdr| vdr.scene_refresh_flag == 1)
+            })
+            .count();
+
+        let mastering_displays = rpus
+            .iter()
+            .filter_map(|rpu| {
+                rpu.vdr_dm_data
+                    .as_ref()
+                    .map(|vdr| (vdr.source_min_pq, vdr.source_max_pq))
+            })
+            .unique()
+            .sorted()
+            .map(|(min_pq, max_pq)| SummaryMasteringDisplay::from_source_pq(min_pq, max_pq))
+            .collect();
+
+        let l6 = rpus
+            .iter()
+            .filter_map(|rpu| match rpu.vdr_dm_data.as_ref()?.get_block(6) {
+                Some(ExtMetadataBlock::Level6(l6)) => Some(l6),
+                _ => None,
+            })
+            .unique()
+            .cloned()
+            .collect();
+
+        let min_pq = RpusListSummary::min_max_avg(l1_data, |e| e.min);
+        let max_pq = RpusListSummary::min_max_avg(l1_data, |e| e.max);
+        let avg_pq = RpusListSummary::min_max_avg(l1_data, |e| e.avg);
+
+        let l1 = SummaryL1Stats {
+            maxcll: pq_to_nits(max_pq.max),
+            maxcll_avg: pq_to_nits(max_pq.avg),
+            maxfall: pq_to_nits(avg_pq.max),
+            maxfall_avg: pq_to_nits(avg_pq.avg),
+            max_min_nits: pq_to_nits(min_pq.max),
+        };
+
+        let l2_targets = rpus
+            .iter()
+            .filter_map(|rpu| rpu.vdr_dm_data.as_ref())
+            .flat_map(|vdr| vdr.level_blocks_iter(2))
+            .filter_map(|block| match block {
+                ExtMetadataBlock::Level2(l2) => Some(l2.target_max_pq),
+                _ => None,
+            })
+            .unique()
+            .sorted()
+            .map(l2_target_nits)
+            .collect();
+
+        let l5_blocks = rpus
+            .iter()
+            .filter_map(|rpu| match rpu.vdr_dm_data.as_ref()?.get_block(5) {
+                Some(ExtMetadataBlock::Level5(l5)) => Some(l5),
+                _ => None,
+            })
+            .unique()
+            .collect::<Vec<_>>();
+
+        let l5_range = |offset_extractor: fn(&ExtMetadataBlockLevel5) -> u16| {
+            l5_blocks
+                .iter()
+                .map(|l5| offset_extractor(l5))
+                .minmax()
+                .into_option()
+                .map(SummaryRange::from)
+        };
+        let l5 = (!l5_blocks.is_empty()).then(|| SummaryL5Ranges {
+            top: l5_range(|l5| l5.active_area_top_offset).unwrap(),
+            bottom: l5_range(|l5| l5.active_area_bottom_offset).unwrap(),
+            left: l5_range(|l5| l5.active_area_left_offset).unwrap(),
+            right: l5_range(|l5| l5.active_area_right_offset).unwrap(),
+        });
+
+        let l8_targets = rpus
+            .iter()
+            .filter_map(|rpu| rpu.vdr_dm_data.as_ref())
+            .flat_map(|vdr| vdr.level_blocks_iter(8))
+            .filter_map(|block| match block {
+                ExtMetadataBlock::Level8(l8) => Some(l8.trim_target_nits()),
+                _ => None,
+            })
+            .unique()
+            .sorted()
+            .collect();
+
+        let l9_mdp = rpus
+            .iter()
+            .filter_map(|rpu| match rpu.vdr_dm_data.as_ref()?.get_block(9) {
+                Some(ExtMetadataBlock::Level9(l9)) => Some(l9.source_primary_index),
+                _ => None,
+            })
+            .fold(HashMap::new(), |mut frames, idx| {
+                *frames.entry(idx).or_insert(0) += 1;
+                frames
+            })
+            .into_iter()
+            .sorted_by_key(|e| e.0)
+            .map(|(source_primary_index, frames)| SummaryL9Mdp {
+                source_primary_index,
+                primaries: MasteringDisplayPrimaries::from(source_primary_index).to_string(),
+                frames,
+            })
+            .collect();
+
+        Self {
+            frames: rpus.len(),
+            profiles,
+            el_types,
+            dm_version: SummaryDmVersion {
+                cmv29_count,
+                cmv40_count,
+            },
+            scene_count,
+            mastering_displays,
+            l1,
+            l5,
+            l6,
+            l2_targets,
+            l8_targets,
+            l9_mdp,
+        }
+    }
+}
+
+impl SceneStats {
+    pub fn from_rpus(rpus: &[DoviRpu]) -> Vec<Self> {
+        let mut scene_starts = rpus
+            .iter()
+            .enumerate()
+            .filter(|(i, rpu)| {
+                *i == 0
+                    || rpu
+                        .vdr_dm_data
+                        .as_ref()
+                        .is_some_and(|vdr| vdr.scene_refresh_flag == 1)
+            })
+            .map(|(i, _)| i)
+            .peekable();
+
+        let mut scenes = Vec::new();
+
+        while let Some(start) = scene_starts.next() {
+            let end = scene_starts.peek().map_or(rpus.len(), |next| *next) - 1;
+            scenes.push(Self::from_scene_rpus(start, &rpus[start..=end]));
+        }
+
+        scenes
+    }
+
+    fn from_scene_rpus(start: usize, rpus: &[DoviRpu]) -> Self {
+        let l1_data = rpus
+            .iter()
+            .map(|rpu| {
+                rpu.vdr_dm_data
+                    .as_ref()
+                    .and_then(|vdr| match vdr.get_block(1) {
+                        Some(ExtMetadataBlock::Level1(l1)) => Some(l1.clone()),
+                        _ => None,
+                    })
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let min_pq = l1_data.iter().map(|l1| l1.min_pq).min().unwrap_or(0);
+        let max_pq = l1_data.iter().map(|l1| l1.max_pq).max().unwrap_or(0);
+        let avg_pq = RpusListSummary::min_max_avg(&l1_data, |l1| l1.avg_pq as f64).avg;
+
+        let l1 = AggregateStats {
+            min: pq_to_nits(min_pq as f64 / 4095.0),
+            max: pq_to_nits(max_pq as f64 / 4095.0),
+            avg: pq_to_nits(avg_pq / 4095.0),
+        };
+
+        let first_vdr = rpus.first().and_then(|rpu| rpu.vdr_dm_data.as_ref());
+        let blocks_iter = |level: u8| {
+            first_vdr
+                .into_iter()
+                .flat_map(move |vdr: &VdrDmData| vdr.level_blocks_iter(level))
+        };
+
+        let l2 = blocks_iter(2)
+            .filter_map(|block| match block {
+                ExtMetadataBlock::Level2(l2) => Some(SceneTrim {
+                    target_nits: l2_target_nits(l2.target_max_pq),
+                    trim: l2.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        let l3 = blocks_iter(3).find_map(|block| match block {
+            ExtMetadataBlock::Level3(l3) => Some(l3.clone()),
+            _ => None,
+        });
+        let l5 = blocks_iter(5).find_map(|block| match block {
+            ExtMetadataBlock::Level5(l5) => Some(l5.clone()),
+            _ => None,
+        });
+        let l8 = blocks_iter(8)
+            .filter_map(|block| match block {
+                ExtMetadataBlock::Level8(l8) => Some(SceneTrim {
+                    target_nits: l8.trim_target_nits(),
+                    trim: l8.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            start,
+            end: start + rpus.len() - 1,
+            duration: rpus.len(),
+            l1,
+            l2,
+            l3,
+            l5,
+            l8,
+        }
+    }
+
+    /// Writes the scenes as a CSV table.
+    /// The trim columns are generated for every target display found in the list.
+    pub fn write_csv<W: IoWrite>(scenes: &[Self], writer: &mut W) -> Result<()> {
+        const L2_FIELDS: &[&str] = &[
+            "slope",
+            "offset",
+            "power",
+            "chroma_weight",
+            "saturation_gain",
+            "ms_weight",
+        ];
+        const L8_FIELDS: &[&str] = &[
+            "slope",
+            "offset",
+            "power",
+            "chroma_weight",
+            "saturation_gain",
+            "ms_weight",
+            "target_mid_contrast",
+            "clip_trim",
+        ];
+
+        let l2_targets = scenes
+            .iter()
+            .flat_map(|s| s.l2.iter().map(|t| t.target_nits))
+            .unique()
+            .sorted()
+            .collect::<Vec<_>>();
+        let l8_targets = scenes
+            .iter()
+            .flat_map(|s| s.l8.iter().map(|t| t.target_nits))
+            .unique()
+            .sorted()
+            .collect::<Vec<_>>();
+        let has_l3 = scenes.iter().any(|s| s.l3.is_some());
+
+        let mut header = vec![
+            "start".to_string(),
+            "end".to_string(),
+            "duration".to_string(),
+            "l1_min_nits".to_string(),
+            "l1_max_nits".to_string(),
+            "l1_avg_nits".to_string(),
+        ];
+
+        for target in &l2_targets {
+            header.extend(L2_FIELDS.iter().map(|f| format!("l2_{target}_{f}")));
+        }
+
+        if has_l3 {
+            header.extend(
+                ["l3_min_pq_offset", "l3_max_pq_offset", "l3_avg_pq_offset"].map(String::from),
+            );
+        }
+
+        header.extend(["l5_left", "l5_right", "l5_top", "l5_bottom"].map(String::from));
+
+        for target in &l8_targets {
+            header.extend(L8_FIELDS.iter().map(|f| format!("l8_{target}_{f}")));
+        }
+
+        writeln!(writer, "{}", header.join(","))?;
+
+        for scene in scenes {
+            let mut row = vec![
+                scene.start.to_string(),
+                scene.end.to_string(),
+                scene.duration.to_string(),
+                format!("{:.4}", scene.l1.min),
+                format!("{:.4}", scene.l1.max),
+                format!("{:.4}", scene.l1.avg),
+            ];
+
+            for target in &l2_targets {
+                if let Some(l2) = scene.l2.iter().find(|t| t.target_nits == *target) {
+                    let l2 = &l2.trim;
+                    row.extend(
+                        [
+                            l2.trim_slope as i32,
+                            l2.trim_offset as i32,
+                            l2.trim_power as i32,
+                            l2.trim_chroma_weight as i32,
+                            l2.trim_saturation_gain as i32,
+                            l2.ms_weight as i32,
+                        ]
+                        .map(|v| v.to_string()),
+                    );
+                } else {
+                    row.extend(std::iter::repeat_n(String::new(), L2_FIELDS.len()));
+                }
+            }
+
+            if has_l3 {
+                if let Some(l3) = &scene.l3 {
+                    row.extend(
+                        [l3.min_pq_offset, l3.max_pq_offset, l3.avg_pq_offset]
+                            .map(|v| v.to_string()),
+                    );
+                } else {
+                    row.extend(std::iter::repeat_n(String::new(), 3));
+                }
+            }
+
+            if let Some(l5) = &scene.l5 {
+                row.extend(l5.get_offsets_vec().iter().map(|v| v.to_string()));
+            } else {
+                row.extend(std::iter::repeat_n(String::new(), 4));
+            }
+
+            for target in &l8_targets {
+                if let Some(l8) = scene.l8.iter().find(|t| t.target_nits == *target) {
+                    let l8 = &l8.trim;
+                    row.extend(
+                        [
+                            l8.trim_slope,
+                            l8.trim_offset,
+                            l8.trim_power,
+                            l8.trim_chroma_weight,
+                            l8.trim_saturation_gain,
+                            l8.ms_weight,
+                            l8.target_mid_contrast,
+                            l8.clip_trim,
+                        ]
+                        .map(|v| v.to_string()),
+                    );
+                } else {
+                    row.extend(std::iter::repeat_n(String::new(), L8_FIELDS.len()));
+                }
+            }
+
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SummaryMasteringDisplay {
+    fn from_source_pq(min_pq: u16, max_pq: u16) -> Self {
+        let min_nits = (pq_to_nits(min_pq as f64 / 4095.0) * 1e6).round() / 1e6;
+        let max_nits = (pq_to_nits(max_pq as f64 / 4095.0) / 1000.0).round() * 1000.0;
+
+        Self {
+            min_pq,
+            max_pq,
+            min_nits,
+            max_nits,
+        }
+    }
+}
+
+impl From<(u16, u16)> for SummaryRange {
+    fn from((min, max): (u16, u16)) -> Self {
+        Self { min, max }
+    }
+}
+
+/// L1 PQ values of every frame, with the default L1 of the CM version for frames without L1
+fn frame_l1_data(rpus: &[DoviRpu]) -> Vec<AggregateStats> {
+    let has_cmv40 = rpus.iter().any(|rpu| {
+        rpu.vdr_dm_data
+            .as_ref()
+            .is_some_and(|vdr| vdr.cmv40_metadata.is_some())
+    });
+    let cm_version = if has_cmv40 {
+        CmVersion::V40
+    } else {
+        CmVersion::V29
+    };
+    let default_l1 = ExtMetadataBlockLevel1::from_stats_cm_version(0, 0, 0, cm_version);
+
+    rpus.iter()
+        .map(|rpu| {
+            let l1 = match rpu.vdr_dm_data.as_ref().and_then(|dm| dm.get_block(1)) {
+                Some(ExtMetadataBlock::Level1(l1)) => l1,
+                _ => &default_l1,
+            };
+
+            AggregateStats {
+                min: (l1.min_pq as f64) / 4095.0,
+                max: (l1.max_pq as f64) / 4095.0,
+                avg: (l1.avg_pq as f64) / 4095.0,
+            }
+        })
+        .collect()
+}
+
+/// L2 target display brightness, rounded to the nearest 100 nits
+fn l2_target_nits(target_max_pq: u16) -> u16 {
+    ((pq_to_nits(target_max_pq as f64 / 4095.0) / 100.0).round() * 100.0) as u16
+}
@@ -0,0 +1,492 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail, ensure};
+
+use dolby_vision::rpu::extension_metadata::blocks::{
+    ExtMetadataBlock, ExtMetadataBlockLevel1, ExtMetadataBlockLevel2, ExtMetadataBlockLevel5,
+    ExtMetadataBlockLevel8,
+};
+use dolby_vision::rpu::generate::VideoShot;
+use dolby_vision::rpu::vdr_dm_data::CmVersion;
+use dolby_vision::timecode::FrameRate;
+use dolby_vision::utils::nits_to_pq_12_bit;
+
+/// Shot list table, from a CSV or plain text file.
+///
+/// The first line is the header, and every following line is a shot.
+/// Values are separated by commas, tabs, semicolons or whitespace.
+pub struct ShotListTable {
+    columns: Vec<ShotColumn>,
+    rows: Vec<(usize, Vec<String>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShotColumn {
+    Start,
+    Duration,
+    L1(L1Field, L1Unit),
+    L2 {
+        target_nits: u16,
+        field: TrimField,
+    },
+    L5(L5Field),
+    L8 {
+        target_display_index: u8,
+        field: TrimField,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum L1Field {
+    Min,
+    Max,
+    Avg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum L1Unit {
+    Nits,
+    Pq,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum L5Field {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrimField {
+    TrimSlope,
+    TrimOffset,
+    TrimPower,
+    TrimChromaWeight,
+    TrimSaturationGain,
+    MsWeight,
+    TargetMidContrast,
+    ClipTrim,
+}
+
+impl ShotListTable {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed reading shot list {}", path.display()))?;
+
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let Some((_, header)) = lines.next() else {
+            bail!("Empty shot list");
+        };
+
+        let delimiter = [',', '\t', ';'].into_iter().find(|e| header.contains(*e));
+        let split_line = |line: &str| -> Vec<String> {
+            match delimiter {
+                Some(delimiter) => line.split(delimiter).map(|e| e.trim().to_owned()).collect(),
+                None => line.split_whitespace().map(str::to_owned).collect(),
+            }
+        };
+
+        let columns = split_line(header)
+            .iter()
+            .map(|name| ShotColumn::from_name(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        ensure!(
+            columns.contains(&ShotColumn::Start),
+            "Shot list is missing the `start` column"
+        );
+
+        for (i, column) in columns.iter().enumerate() {
+            ensure!(
+                !columns[i + 1..].contains(column),
+                "Duplicate shot list column {column:?}"
+            );
+        }
+
+        let rows = lines
+            .map(|(line_no, line)| {
+                let values = split_line(line);
+
+                ensure!(
+                    values.len() <= columns.len(),
+                    "Shot list line {line_no}: {} values for {} columns",
+                    values.len(),
+                    columns.len()
+                );
+
+                Ok((line_no, values))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { columns, rows })
+    }
+
+    /// Builds the shots, sorted by start frame.
+    /// The shots without a duration last until the next shot, or the end of the video
+    pub fn to_shots(
+        &self,
+        frame_rate: Option<&FrameRate>,
+        cm_version: CmVersion,
+        length: usize,
+    ) -> Result<Vec<VideoShot>> {
+        let mut shots = Vec::with_capacity(self.rows.len());
+
+        for (line_no, values) in &self.rows {
+            let shot = self
+                .parse_shot(values, frame_rate, cm_version)
+                .with_context(|| format!("Invalid shot list line {line_no}"))?;
+
+            shots.push(shot);
+        }
+
+        shots.sort_by_key(|(shot, _)| shot.start);
+
+        let next_starts: Vec<Option<usize>> = shots
+            .iter()
+            .skip(1)
+            .map(|(shot, _)| Some(shot.start))
+            .chain([None])
+            .collect();
+
+        shots
+            .into_iter()
+            .zip(next_starts)
+            .map(|((mut shot, duration), next_start)| {
+                shot.duration = match (duration, next_start) {
+                    (Some(duration), _) => duration,
+                    (None, Some(next_start)) => next_start - shot.start,
+                    (None, None) => {
+                        ensure!(
+                            length > shot.start,
+                            "Missing duration for the last shot at frame {}, and no config length to derive it from",
+                            shot.start
+                        );
+
+                        length - shot.start
+                    }
+                };
+
+                ensure!(
+                    shot.duration > 0,
+                    "Invalid shot at frame {}: duration is 0",
+                    shot.start
+                );
+
+                if let Some(next_start) = next_start {
+                    ensure!(
+                        shot.start + shot.duration <= next_start,
+                        "Shot at frame {} overlaps the next shot at frame {next_start}",
+                        shot.start
+                    );
+                }
+
+                Ok(shot)
+            })
+            .collect()
+    }
+
+    fn parse_shot(
+        &self,
+        values: &[String],
+        frame_rate: Option<&FrameRate>,
+        cm_version: CmVersion,
+    ) -> Result<(VideoShot, Option<usize>)> {
+        let mut shot = VideoShot::default();
+        let mut duration = None;
+        let mut start = None;
+
+        let mut level1: [Option<u16>; 3] = [None; 3];
+        let mut level5: Option<ExtMetadataBlockLevel5> = None;
+        let mut level2_blocks: Vec<ExtMetadataBlockLevel2> = Vec::new();
+        let mut level8_blocks: Vec<ExtMetadataBlockLevel8> = Vec::new();
+
+        let cells = self
+            .columns
+            .iter()
+            .zip(values.iter())
+            .filter(|(_, value)| !value.is_empty());
+
+        for (column, value) in cells {
+            match *column {
+                ShotColumn::Start => {
+                    start = Some(FrameRate::parse_position(frame_rate, value)?);
+                }
+                ShotColumn::Duration => {
+                    duration = Some(parse_number::<usize>(value)?);
+                }
+                ShotColumn::L1(field, unit) => {
+                    let pq = match unit {
+                        L1Unit::Nits => nits_to_pq_12_bit(parse_number::<f64>(value)?),
+                        L1Unit::Pq => parse_number::<u16>(value)?,
+                    };
+
+                    level1[field as usize] = Some(pq);
+                }
+                ShotColumn::L2 { target_nits, field } => {
+                    let target_max_pq = nits_to_pq_12_bit(target_nits);
+
+                    let block = match level2_blocks
+                        .iter_mut()
+                        .position(|e| e.target_max_pq == target_max_pq)
+                    {
+                        Some(idx) => &mut level2_blocks[idx],
+                        None => {
+                            level2_blocks.push(ExtMetadataBlockLevel2::from_nits(target_nits));
+                            level2_blocks.last_mut().unwrap()
+                        }
+                    };
+
+                    field.set_level2(block, value)?;
+                }
+                ShotColumn::L5(field) => {
+                    let block = level5.get_or_insert_with(Default::default);
+                    let value = parse_number::<u16>(value)?;
+
+                    match field {
+                        L5Field::Left => block.active_area_left_offset = value,
+                        L5Field::Right => block.active_area_right_offset = value,
+                        L5Field::Top => block.active_area_top_offset = value,
+                        L5Field::Bottom => block.active_area_bottom_offset = value,
+                    }
+                }
+                ShotColumn::L8 {
+                    target_display_index,
+                    field,
+                } => {
+                    ensure!(
+                        cm_version == CmVersion::V40,
+                        "L8 trims require CM v4.0 metadata"
+                    );
+
+                    let block = match level8_blocks
+                        .iter_mut()
+                        .position(|e| e.target_display_index == target_display_index)
+                    {
+                        Some(idx) => &mut level8_blocks[idx],
+                        None => {
+                            level8_blocks.push(ExtMetadataBlockLevel8 {
+                                target_display_index,
+                                ..Default::default()
+                            });
+                            level8_blocks.last_mut().unwrap()
+                        }
+                    };
+
+                    field.set_level8(block, value)?;
+                }
+            }
+        }
+
+        let Some(start) = start else {
+            bail!("Missing shot start");
+        };
+        shot.start = start;
+
+        match level1 {
+            [None, None, None] => {}
+            [min_pq, Some(max_pq), Some(avg_pq)] => {
+                shot.metadata_blocks.push(ExtMetadataBlock::Level1(
+                    ExtMetadataBlockLevel1::from_stats_cm_version(
+                        min_pq.unwrap_or(0),
+                        max_pq,
+                        avg_pq,
+                        cm_version,
+                    ),
+                ));
+            }
+            _ => bail!("L1 metadata requires both max and avg values"),
+        }
+
+        shot.metadata_blocks
+            .extend(level2_blocks.into_iter().map(ExtMetadataBlock::Level2));
+
+        if let Some(level5) = level5 {
+            shot.metadata_blocks.push(ExtMetadataBlock::Level5(level5));
+        }
+
+        shot.metadata_blocks
+            .extend(level8_blocks.into_iter().map(ExtMetadataBlock::Level8));
+
+        Ok((shot, duration))
+    }
+}
+
+impl ShotColumn {
+    fn from_name(name: &str) -> Result<Self> {
+        let name = name.trim().to_lowercase();
+
+        let column = match name.as_str() {
+            "start" => Self::Start,
+            "duration" => Self::Duration,
+            "min_nits" => Self::L1(L1Field::Min, L1Unit::Nits),
+            "max_nits" => Self::L1(L1Field::Max, L1Unit::Nits),
+            "avg_nits" => Self::L1(L1Field::Avg, L1Unit::Nits),
+            "min_pq" => Self::L1(L1Field::Min, L1Unit::Pq),
+            "max_pq" => Self::L1(L1Field::Max, L1Unit::Pq),
+            "avg_pq" => Self::L1(L1Field::Avg, L1Unit::Pq),
+            "l5_left" => Self::L5(L5Field::Left),
+            "l5_right" => Self::L5(L5Field::Right),
+            "l5_top" => Self::L5(L5Field::Top),
+            "l5_bottom" => Self::L5(L5Field::Bottom),
+            _ => {
+                // l2_<target nits>_<field> or l8_<target display index>_<field>
+                let parts = name
+                    .strip_prefix("l2_")
+                    .map(|rest| (2, rest))
+                    .or_else(|| name.strip_prefix("l8_").map(|rest| (8, rest)))
+                    .and_then(|(level, rest)| {
+                        rest.split_once('_')
+                            .map(|(target, field)| (level, target, field))
+                    });
+
+                let Some((level, target, field)) = parts else {
+                    bail!("Unknown shot list column `{name}`");
+                };
+
+                let field = TrimField::from_name(field)
+                    .with_context(|| format!("Unknown shot list column `{name}`"))?;
+
+                if level == 2 {
+                    ensure!(
+                        !matches!(field, TrimField::TargetMidContrast | TrimField::ClipTrim),
+                        "Unknown shot list column `{name}`: L2 has no {field:?}"
+                    );
+
+                    Self::L2 {
+                        target_nits: target
+                            .parse()
+                            .with_context(|| format!("Invalid L2 target nits in `{name}`"))?,
+                        field,
+                    }
+                } else {
+                    Self::L8 {
+                        target_display_index: target.parse().with_context(|| {
+                            format!("Invalid L8 target display index in `{name}`")
+                        })?,
+                        field,
+                    }
+                }
+            }
+        };
+
+        Ok(column)
+    }
+}
+
+impl TrimField {
+    fn from_name(name: &str) -> Result<Self> {
+        Ok(match name {
+            "trim_slope" => Self::TrimSlope,
+            "trim_offset" => Self::TrimOffset,
+            "trim_power" => Self::TrimPower,
+            "trim_chroma_weight" => Self::TrimChromaWeight,
+            "trim_saturation_gain" => Self::TrimSaturationGain,
+            "ms_weight" => Self::MsWeight,
+            "target_mid_contrast" => Self::TargetMidContrast,
+            "clip_trim" => Self::ClipTrim,
+            _ => bail!("Unknown trim field {name}"),
+        })
+    }
+
+    fn set_level2(&self, block: &mut ExtMetadataBlockLevel2, value: &str) -> Result<()> {
+        match self {
+            Self::TrimSlope => block.trim_slope = parse_number(value)?,
+            Self::TrimOffset => block.trim_offset = parse_number(value)?,
+            Self::TrimPower => block.trim_power = parse_number(value)?,
+            Self::TrimChromaWeight => block.trim_chroma_weight = parse_number(value)?,
+            Self::TrimSaturationGain => block.trim_saturation_gain = parse_number(value)?,
+            Self::MsWeight => block.ms_weight = parse_number(value)?,
+            Self::TargetMidContrast | Self::ClipTrim => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn set_level8(&self, block: &mut ExtMetadataBlockLevel8, value: &str) -> Result<()> {
+        match self {
+            Self::TrimSlope => block.trim_slope = parse_number(value)?,
+            Self::TrimOffset => block.trim_offset = parse_number(value)?,
+            Self::TrimPower => block.trim_power = parse_number(value)?,
+            Self::TrimChromaWeight => block.trim_chroma_weight = parse_number(value)?,
+            Self::TrimSaturationGain => block.trim_saturation_gain = parse_number(value)?,
+            Self::MsWeight => block.ms_weight = parse_number(value)?,
+            Self::TargetMidContrast => {
+                block.target_mid_contrast = parse_number(value)?;
+                block.length = block.length.max(12);
+            }
+            Self::ClipTrim => {
+                block.clip_trim = parse_number(value)?;
+                block.length = block.length.max(13);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T> {
+    value
+        .parse::<T>()
+        .map_err(|_| anyhow::anyhow!("Invalid value `{value}`"))
+}
+
+/// Parses a scene cuts list, with one frame index per line.
+/// This is the format written by `export --data scenes`
+pub fn parse_scene_cuts_file(path: &Path) -> Result<Vec<usize>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut frames = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let frame = line
+            .parse::<usize>()
+            .with_context(|| format!("Invalid frame index in scene cuts list: {line}"))?;
+        frames.push(frame);
+    }
+
+    Ok(frames)
+}
+
+/// Builds shots starting at every scene cut, until the end of the video
+pub fn shots_from_scene_cuts(mut scene_cuts: Vec<usize>, length: usize) -> Result<Vec<VideoShot>> {
+    ensure!(length > 0, "Missing number of RPUs to generate");
+
+    if let Some(frame) = scene_cuts.iter().find(|frame| **frame >= length) {
+        bail!("Invalid scene cut: {frame} >= {length} frames");
+    }
+
+    scene_cuts.push(0);
+    scene_cuts.sort_unstable();
+    scene_cuts.dedup();
+
+    let ends = scene_cuts.iter().skip(1).copied().chain([length]);
+
+    Ok(scene_cuts
+        .iter()
+        .copied()
+        .zip(ends)
+        .map(|(start, end)| VideoShot {
+            start,
+            duration: end - start,
+            ..Default::default()
+        })
+        .collect())
+}
@@ -0,0 +1,238 @@
+use std::fs;
+use std::io::{Write, stdout};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail, ensure};
+use roxmltree::Document;
+
+use dolby_vision::rpu::generate::GenerateConfig;
+use dolby_vision::rpu::prev_vdr_rpu::PrevVdrRpuResolver;
+use dolby_vision::rpu::utils::parse_rpu_file;
+use dolby_vision::timecode::FrameRate;
+
+use super::shot_list::parse_scene_cuts_file;
+use super::{DoviRpu, input_from_either, write_rpu_file};
+use crate::commands::SplitArgs;
+
+pub struct Splitter {
+    input: PathBuf,
+    output_dir: PathBuf,
+    frame_rate: Option<FrameRate>,
+    mode: SplitMode,
+}
+
+enum SplitMode {
+    Ranges(Vec<String>),
+    SceneCuts(PathBuf),
+    Chapters(PathBuf),
+    Scenes(usize),
+}
+
+impl Splitter {
+    pub fn from_args(args: SplitArgs) -> Result<Self> {
+        let SplitArgs {
+            input,
+            input_pos,
+            ranges,
+            scene_cuts,
+            chapters,
+            scenes,
+            frame_rate,
+            output_dir,
+        } = args;
+
+        let input = input_from_either("split", input, input_pos)?;
+
+        let mode = if let Some(path) = scene_cuts {
+            SplitMode::SceneCuts(path)
+        } else if let Some(path) = chapters {
+            SplitMode::Chapters(path)
+        } else if let Some(count) = scenes {
+            SplitMode::Scenes(count as usize)
+        } else {
+            SplitMode::Ranges(ranges)
+        };
+
+        Ok(Self {
+            input,
+            output_dir: output_dir.unwrap_or_else(|| PathBuf::from(".")),
+            frame_rate,
+            mode,
+        })
+    }
+
+    pub fn split(args: SplitArgs) -> Result<()> {
+        let splitter = Splitter::from_args(args)?;
+
+        println!("Parsing RPU file...");
+        stdout().flush().ok();
+
+        let rpus = parse_rpu_file(&splitter.input)?;
+        ensure!(!rpus.is_empty(), "No RPUs to split");
+
+        let segments = splitter.segment_ranges(&rpus)?;
+
+        fs::create_dir_all(&splitter.output_dir)?;
+
+        let file_stem = splitter
+            .input
+            .file_stem()
+            .map(|e| e.to_string_lossy())
+            .unwrap_or_default();
+
+        for (i, (start, end)) in segments.into_iter().enumerate() {
+            let out_path = splitter
+                .output_dir
+                .join(format!("{file_stem}_{:03}.bin", i + 1));
+
+            println!(
+                "Segment {}: frames {start}-{end} to {}",
+                i + 1,
+                out_path.display()
+            );
+
+            let mut segment_rpus = rpus[start..=end].to_vec();
+
+            // The mapping referenced by the first RPU is in the previous segment
+            let first_rpu = &mut segment_rpus[0];
+            PrevVdrRpuResolver::detach(&rpus[..start], first_rpu).with_context(|| {
+                format!(
+                    "Segment {}: failed resolving the mapping of frame {start}",
+                    i + 1
+                )
+            })?;
+
+            // The segments always start with a scene cut
+            if let Some(vdr_dm_data) = first_rpu.vdr_dm_data.as_mut()
+                && vdr_dm_data.scene_refresh_flag != 1
+            {
+                first_rpu.modified = true;
+                vdr_dm_data.set_scene_cut(true);
+            }
+
+            let data = GenerateConfig::encode_rpus(&segment_rpus).collect::<Result<Vec<_>>>()?;
+            write_rpu_file(&out_path, data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Inclusive frame ranges of every segment
+    fn segment_ranges(&self, rpus: &[DoviRpu]) -> Result<Vec<(usize, usize)>> {
+        let count = rpus.len();
+
+        let split_frames = match &self.mode {
+            SplitMode::Ranges(ranges) => {
+                let ranges = ranges
+                    .iter()
+                    .map(|range| FrameRate::parse_range(self.frame_rate.as_ref(), range))
+                    .collect::<Result<Vec<_>>>()?;
+
+                if let Some((_, end)) = ranges.iter().find(|(_, end)| *end >= count) {
+                    bail!("Invalid range: {} >= {} available RPUs", end, count);
+                }
+
+                return Ok(ranges);
+            }
+            SplitMode::SceneCuts(path) => parse_scene_cuts_file(path)?,
+            SplitMode::Chapters(path) => {
+                let Some(frame_rate) = &self.frame_rate else {
+                    bail!("Splitting by chapters requires a frame rate");
+                };
+
+                Self::parse_chapters(path)?
+                    .into_iter()
+                    .map(|seconds| frame_rate.seconds_to_frame(seconds))
+                    .collect::<Result<Vec<_>>>()?
+            }
+            SplitMode::Scenes(scenes_count) => rpus
+                .iter()
+                .enumerate()
+                .filter(|(i, rpu)| {
+                    *i == 0
+                        || rpu
+                            .vdr_dm_data
+                            .as_ref()
+                            .is_some_and(|vdr| vdr.scene_refresh_flag == 1)
+                })
+                .map(|(i, _)| i)
+                .step_by(*scenes_count)
+                .collect(),
+        };
+
+        Self::ranges_from_split_frames(split_frames, count)
+    }
+
+    fn ranges_from_split_frames(
+        mut split_frames: Vec<usize>,
+        count: usize,
+    ) -> Result<Vec<(usize, usize)>> {
+        if let Some(frame) = split_frames.iter().find(|frame| **frame >= count) {
+            bail!("Invalid split frame: {} >= {} available RPUs", frame, count);
+        }
+
+        split_frames.push(0);
+        split_frames.sort_unstable();
+        split_frames.dedup();
+
+        let ends = split_frames.iter().skip(1).map(|next| next - 1);
+
+        Ok(split_frames
+            .iter()
+            .copied()
+            .zip(ends.chain(std::iter::once(count - 1)))
+            .collect())
+    }
+
+    /// Chapter start times in seconds, from Matroska XML or OGM chapters
+    fn parse_chapters(path: &Path) -> Result<Vec<f64>> {
+        let contents = fs::read_to_string(path)?;
+
+        let timestamps: Vec<String> = if contents.trim_start().starts_with('<') {
+            let doc = Document::parse(&contents)?;
+
+            doc.descendants()
+                .filter(|node| node.has_tag_name("ChapterTimeStart"))
+                .filter_map(|node| node.text())
+                .map(str::to_owned)
+                .collect()
+        } else {
+            // CHAPTER01=00:00:00.000
+            contents
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .filter(|(key, _)| {
+                    key.trim().starts_with("CHAPTER") && !key.trim().ends_with("NAME")
+                })
+                .map(|(_, value)| value.to_owned())
+                .collect()
+        };
+
+        ensure!(
+            !timestamps.is_empty(),
+            "No chapters found in {}",
+            path.display()
+        );
+
+        timestamps
+            .into_iter()
+            .map(|timestamp| Self::parse_chapter_timestamp(timestamp.trim()))
+            .collect()
+    }
+
+    /// Parses a `HH:MM:SS.nnnnnnnnn` timestamp to seconds
+    fn parse_chapter_timestamp(timestamp: &str) -> Result<f64> {
+        let parts: Vec<&str> = timestamp.split(':').collect();
+
+        let [hours, minutes, seconds] = parts[..] else {
+            bail!("Invalid chapter timestamp {timestamp}");
+        };
+
+        let parse_err = || format!("Invalid chapter timestamp {timestamp}");
+        let hours = hours.parse::<u64>().with_context(parse_err)?;
+        let minutes = minutes.parse::<u64>().with_context(parse_err)?;
+        let seconds = seconds.parse::<f64>().with_context(parse_err)?;
+
+        Ok((hours * 3600 + minutes * 60) as f64 + seconds)
+    }
+}
@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use anyhow::Result;
 use clap::{Parser, ValueHint};
 
+use dolby_vision::rpu::editor::EditConfig;
+
 #[cfg(test)]
 mod tests;
 
@@ -11,18 +13,11 @@ use commands::{Commands, ConversionModeCli};
 
 mod dovi;
 use dovi::{
-    CliOptions, WriteStartCodePreset,
-    converter::Converter,
-    demuxer::Demuxer,
-    editor::{EditConfig, Editor},
-    exporter::Exporter,
-    generator::Generator,
-    muxer::Muxer,
-    plotter::Plotter,
-    remover::Remover,
-    rpu_extractor::RpuExtractor,
-    rpu_info::RpuInfo,
-    rpu_injector::RpuInjector,
+    CliOptions, WriteStartCodePreset, active_area_detector::ActiveAreaDetector,
+    concatenator::Concatenator, converter::Converter, demuxer::Demuxer, editor::Editor,
+    exporter::Exporter, generator::Generator, muxer::Muxer, plotter::Plotter, remover::Remover,
+    repairer::Repairer, rpu_extractor::RpuExtractor, rpu_info::RpuInfo, rpu_injector::RpuInjector,
+    splitter::Splitter,
 };
 
 #[derive(Parser, Debug)]
@@ -102,7 +97,9 @@ fn main() -> Result<()> {
     }
 
     match opt.cmd {
+        Commands::Concat(args) => Concatenator::concat(args),
         Commands::Demux(args) => Demuxer::demux(args, cli_options),
+        Commands::DetectActiveArea(args) => ActiveAreaDetector::detect(args),
         Commands::Editor(args) => Editor::edit(args),
         Commands::Convert(args) => Converter::convert(args, cli_options),
         Commands::ExtractRpu(args) => RpuExtractor::extract_rpu(args, cli_options),
@@ -113,5 +110,7 @@ fn main() -> Result<()> {
         Commands::Mux(args) => Muxer::mux_el(args, cli_options),
         Commands::Plot(args) => Plotter::plot(args),
         Commands::Remove(args) => Remover::remove(args, cli_options),
+        Commands::Repair(args) => Repairer::repair(args),
+        Commands::Split(args) => Splitter::split(args),
     }
 }
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use assert_cmd::cargo;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use dolby_vision::rpu::extension_metadata::blocks::ExtMetadataBlock;
+use dolby_vision::rpu::prev_vdr_rpu::PrevVdrRpuResolver;
+
+const SUBCOMMAND: &str = "concat";
+
+#[test]
+fn help() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let assert = cmd.arg(SUBCOMMAND).arg("--help").assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains(
+            "dovi_tool concat [OPTIONS] [inputs]...",
+        ));
+    Ok(())
+}
+
+#[test]
+fn whole_files() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/tests/cmv40_full_rpu.bin")
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("-o")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 260);
+
+    Ok(())
+}
+
+#[test]
+fn segments_json() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let config = temp.child("segments.json");
+    let cfg_file = std::fs::File::create(&config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "segments": [
+                { "rpu_file": "assets/tests/cmv40_full_rpu.bin" },
+                { "rpu_file": "assets/hevc_tests/regular_rpu.bin", "start": 10, "end": 19 },
+                { "rpu_file": "assets/hevc_tests/regular_rpu.bin", "start": 220 }
+            ],
+            "normalize_levels": [6]
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(config.as_ref())
+        .arg("-o")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 50);
+
+    let scene_cuts: Vec<usize> = rpus
+        .iter()
+        .enumerate()
+        .filter(|(_, rpu)| rpu.vdr_dm_data.as_ref().unwrap().scene_refresh_flag == 1)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(scene_cuts, vec![0, 1, 11]);
+
+    // L6 from the first segment
+    let vdr_dm_data = rpus[20].vdr_dm_data.as_ref().unwrap();
+    if let Some(ExtMetadataBlock::Level6(l6)) = vdr_dm_data.get_block(6) {
+        assert_eq!(l6.max_content_light_level, 1000);
+        assert_eq!(l6.max_frame_average_light_level, 400);
+    } else {
+        panic!("No L6 block");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_segment_range() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let config = temp.child("segments.json");
+    let cfg_file = std::fs::File::create(&config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "segments": [
+                { "rpu_file": "assets/hevc_tests/regular_rpu.bin", "start": 10, "end": 259 }
+            ]
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(config.as_ref())
+        .arg("-o")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "Invalid segment end 259 for assets/hevc_tests/regular_rpu.bin: 259 available RPUs",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn segments_with_shared_mappings() -> Result<()> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let edit_config = temp.child("edit.json");
+    edit_config.write_str("{}")?;
+
+    let shared_rpu = temp.child("shared.bin");
+    cargo::cargo_bin_cmd!()
+        .arg("editor")
+        .arg("assets/hevc_tests/regular_rpu_mel.bin")
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(shared_rpu.as_ref())
+        .arg("--reuse-mappings")
+        .assert()
+        .success();
+
+    let config = temp.child("segments.json");
+    let cfg_file = std::fs::File::create(&config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "segments": [
+                { "rpu_file": shared_rpu.path(), "start": 100, "end": 109 },
+                { "rpu_file": shared_rpu.path(), "start": 10, "end": 19 }
+            ]
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cargo::cargo_bin_cmd!()
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(config.as_ref())
+        .arg("-o")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let mut rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 20);
+
+    // Every segment writes the mapping of its first RPU
+    let explicit: Vec<usize> = rpus
+        .iter()
+        .enumerate()
+        .filter(|(_, rpu)| !rpu.header.use_prev_vdr_rpu_flag)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(explicit, vec![0, 10]);
+    assert!(rpus[10].rpu_data_mapping.is_some());
+
+    let original_rpus =
+        dolby_vision::rpu::utils::parse_rpu_file("assets/hevc_tests/regular_rpu_mel.bin")?;
+    assert!(PrevVdrRpuResolver::resolve_all(&mut rpus).is_empty());
+    assert!(
+        rpus.iter()
+            .all(|rpu| rpu.rpu_data_mapping == original_rpus[0].rpu_data_mapping)
+    );
+
+    Ok(())
+}
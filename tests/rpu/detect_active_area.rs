@@ -0,0 +1,210 @@
+use anyhow::Result;
+use assert_cmd::cargo;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use dolby_vision::rpu::extension_metadata::blocks::ExtMetadataBlock;
+
+const SUBCOMMAND: &str = "detect-active-area";
+
+#[derive(Clone, Copy)]
+enum Picture {
+    Letterbox,
+    Pillarbox,
+    Full,
+    Black,
+}
+
+/// 10 bit 4:2:0 Y4M with a 32x18 canvas
+fn y4m_video(frames: &[(Picture, usize)]) -> Vec<u8> {
+    let (width, height) = (32, 18);
+
+    let mut data = b"YUV4MPEG2 W32 H18 F24:1 Ip A1:1 C420p10\n".to_vec();
+
+    for (picture, count) in frames.iter().copied() {
+        for _ in 0..count {
+            data.extend_from_slice(b"FRAME\n");
+
+            let luma = (0..height).flat_map(|row| {
+                (0..width).map(move |col| {
+                    let active = match picture {
+                        Picture::Letterbox => (3..15).contains(&row),
+                        Picture::Pillarbox => (4..28).contains(&col),
+                        Picture::Full => true,
+                        Picture::Black => false,
+                    };
+
+                    if active { 502_u16 } else { 64 }
+                })
+            });
+            let chroma = std::iter::repeat_n(512_u16, width * height / 2);
+
+            data.extend(luma.chain(chroma).flat_map(u16::to_le_bytes));
+        }
+    }
+
+    data
+}
+
+#[test]
+fn help() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let assert = cmd.arg(SUBCOMMAND).arg("--help").assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains(
+            "dovi_tool detect-active-area [OPTIONS] <--y4m <y4m>|--raw-yuv <raw-yuv>>",
+        ));
+    Ok(())
+}
+
+#[test]
+fn editor_config() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let video = temp.child("video.y4m");
+    video.write_binary(&y4m_video(&[
+        (Picture::Letterbox, 10),
+        (Picture::Black, 2),
+        (Picture::Letterbox, 18),
+        (Picture::Pillarbox, 5),
+        (Picture::Full, 30),
+    ]))?;
+
+    let config = temp.child("L5.json");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--y4m")
+        .arg(video.as_ref())
+        .arg("-o")
+        .arg(config.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let config: serde_json::Value = serde_json::from_reader(std::fs::File::open(config)?)?;
+
+    // Short pillarbox region is merged into the letterbox region
+    assert_eq!(
+        config,
+        serde_json::json!({
+            "active_area": {
+                "presets": [
+                    { "id": 0, "left": 0, "right": 0, "top": 3, "bottom": 3 },
+                    { "id": 1, "left": 0, "right": 0, "top": 0, "bottom": 0 }
+                ],
+                "edits": {
+                    "0-34": 0,
+                    "35-64": 1
+                }
+            }
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn inject_l5() -> Result<()> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = temp.child("RPU.bin");
+    cargo::cargo_bin_cmd!()
+        .arg("generate")
+        .arg("-j")
+        .arg("assets/generator_examples/default_cmv40.json")
+        .arg("-o")
+        .arg(input_rpu.as_ref())
+        .assert()
+        .success();
+
+    let video = y4m_video(&[(Picture::Full, 4), (Picture::Pillarbox, 6)]);
+    let output_rpu = temp.child("RPU_L5.bin");
+
+    let assert = cargo::cargo_bin_cmd!()
+        .arg(SUBCOMMAND)
+        .arg("--y4m")
+        .arg("-")
+        .arg("--min-duration")
+        .arg("2")
+        .arg("--rpu-in")
+        .arg(input_rpu.as_ref())
+        .arg("-o")
+        .arg(output_rpu.as_ref())
+        .write_stdin(video)
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 10);
+
+    let offsets: Vec<_> = rpus
+        .iter()
+        .map(|rpu| match rpu.vdr_dm_data.as_ref().unwrap().get_block(5) {
+            Some(ExtMetadataBlock::Level5(level5)) => level5.get_offsets(),
+            _ => panic!("No L5 block"),
+        })
+        .collect();
+
+    assert_eq!(offsets[0], (0, 0, 0, 0));
+    assert_eq!(offsets[9], (4, 4, 0, 0));
+
+    Ok(())
+}
+
+#[test]
+fn rpu_length_mismatch() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let output_rpu = temp.child("RPU_L5.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--y4m")
+        .arg("-")
+        .arg("--rpu-in")
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("-o")
+        .arg(output_rpu.as_ref())
+        .write_stdin(y4m_video(&[(Picture::Full, 10)]))
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "RPU length mismatch: 259 RPUs for 10 video frames",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn raw_yuv_options_require_raw_yuv() -> Result<()> {
+    let raw_yuv_options: [&[&str]; 4] = [
+        &["--bit-depth", "12"],
+        &["--frame-width", "1920"],
+        &["--frame-height", "1080"],
+        &["--full-range"],
+    ];
+
+    for args in raw_yuv_options {
+        let mut cmd = cargo::cargo_bin_cmd!();
+
+        let assert = cmd
+            .arg(SUBCOMMAND)
+            .arg("--y4m")
+            .arg("video.y4m")
+            .args(args)
+            .assert();
+
+        assert
+            .failure()
+            .stderr(predicate::str::contains("--raw-yuv <raw-yuv>"));
+    }
+
+    Ok(())
+}
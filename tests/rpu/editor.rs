@@ -5,7 +5,9 @@ use assert_cmd::cargo;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
+use dolby_vision::rpu::ConversionMode;
 use dolby_vision::rpu::extension_metadata::{MasteringDisplayPrimaries, blocks::ExtMetadataBlock};
+use dolby_vision::rpu::prev_vdr_rpu::PrevVdrRpuResolver;
 
 const SUBCOMMAND: &str = "editor";
 
@@ -344,3 +346,601 @@ fn duplicate() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn level_edits() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+    let edit_config = Path::new("assets/editor_examples/level_edits.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 259);
+
+    let l2_100_nits_slope = |frame: usize| {
+        let vdr_dm_data = rpus[frame].vdr_dm_data.as_ref().unwrap();
+        vdr_dm_data
+            .level_blocks_iter(2)
+            .find_map(|b| match b {
+                ExtMetadataBlock::Level2(l2) if l2.target_max_pq == 2081 => Some(l2.trim_slope),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    assert!(rpus.iter().all(|rpu| {
+        rpu.vdr_dm_data
+            .as_ref()
+            .is_some_and(|vdr_dm_data| vdr_dm_data.get_block(11).is_none())
+    }));
+
+    assert_eq!(l2_100_nits_slope(0), 2000);
+    assert_eq!(l2_100_nits_slope(119), 2000);
+    assert_eq!(l2_100_nits_slope(120), 2013);
+
+    // Inserted when missing, other targets are untouched
+    let count_l2 = |frame: usize| {
+        let vdr_dm_data = rpus[frame].vdr_dm_data.as_ref().unwrap();
+        vdr_dm_data.level_blocks_iter(2).count()
+    };
+    assert_eq!(count_l2(0), 1);
+    assert_eq!(count_l2(120), 3);
+
+    Ok(())
+}
+
+#[test]
+fn level_adjustments() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+    let edit_config = Path::new("assets/editor_examples/level_adjustments.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 259);
+
+    let vdr_dm_data = rpus[120].vdr_dm_data.as_ref().unwrap();
+    if let Some(ExtMetadataBlock::Level1(l1)) = vdr_dm_data.get_block(1) {
+        assert_eq!(l1.avg_pq, 1249);
+    } else {
+        panic!("No L1 block");
+    }
+
+    // Only the 100 nits target is scaled
+    let l2_power: Vec<u16> = vdr_dm_data
+        .level_blocks_iter(2)
+        .filter_map(|b| match b {
+            ExtMetadataBlock::Level2(l2) => Some(l2.trim_power),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(l2_power, vec![1272, 1955, 2047]);
+
+    // Clamped to 12-bit values
+    assert!(vdr_dm_data.level_blocks_iter(8).all(|b| match b {
+        ExtMetadataBlock::Level8(l8) => l8.trim_slope == 4095,
+        _ => false,
+    }));
+
+    // Outside of the range
+    let vdr_dm_data = rpus[219].vdr_dm_data.as_ref().unwrap();
+    if let Some(ExtMetadataBlock::Level1(l1)) = vdr_dm_data.get_block(1) {
+        assert_eq!(l1.avg_pq, 819);
+    } else {
+        panic!("No L1 block");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn level_adjustments_invalid_field() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+
+    let edit_config = temp.child("adjustments.json");
+    let cfg_file = std::fs::File::create(&edit_config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "level_adjustments": {
+                "all": [{ "level": 1, "field": "trim_slope", "offset": 10 }]
+            }
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("L1 has no TrimSlope field"));
+
+    Ok(())
+}
+
+#[test]
+fn downgrade_cmv4() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/tests/cmv4_0_2_custom_displays_xml_rpu.bin");
+    let edit_config = Path::new("assets/editor_examples/downgrade_cmv4.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 24);
+
+    let vdr_dm_data = rpus[0].vdr_dm_data.as_ref().unwrap();
+    assert!(vdr_dm_data.cmv40_metadata.is_none());
+
+    // Custom target display L8, from the L10 target
+    if let Some(ExtMetadataBlock::Level2(l2)) = vdr_dm_data.get_block(2) {
+        assert_eq!(l2.target_max_pq, 2081);
+        assert_eq!(l2.trim_slope, 2068);
+        assert_eq!(l2.trim_power, 1987);
+    } else {
+        panic!("No L2 block");
+    }
+
+    // L3 offsets applied, clamped to CM v2.9 values
+    if let Some(ExtMetadataBlock::Level1(l1)) = vdr_dm_data.get_block(1) {
+        assert_eq!(l1.max_pq, 2081);
+        assert_eq!(l1.avg_pq, 819);
+    } else {
+        panic!("No L1 block");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_cmv29() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/tests/cmv2_9_xml_with_l5_rpu.bin");
+    let edit_config = Path::new("assets/editor_examples/upgrade_cmv29.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 108);
+
+    let vdr_dm_data = rpus[0].vdr_dm_data.as_ref().unwrap();
+    assert!(vdr_dm_data.cmv40_metadata.is_some());
+
+    let l2_trims: Vec<_> = vdr_dm_data
+        .level_blocks_iter(2)
+        .filter_map(|b| match b {
+            ExtMetadataBlock::Level2(l2) => Some((l2.trim_slope, l2.trim_offset, l2.trim_power)),
+            _ => None,
+        })
+        .collect();
+    let l8_trims: Vec<_> = vdr_dm_data
+        .level_blocks_iter(8)
+        .filter_map(|b| match b {
+            ExtMetadataBlock::Level8(l8) => Some((
+                l8.target_display_index,
+                (l8.trim_slope, l8.trim_offset, l8.trim_power),
+            )),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        l8_trims.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+        vec![1, 27, 48]
+    );
+    assert!(
+        l8_trims
+            .iter()
+            .map(|(_, trims)| *trims)
+            .eq(l2_trims.into_iter())
+    );
+
+    assert!(vdr_dm_data.get_block(10).is_none());
+    assert!(vdr_dm_data.get_block(254).is_some());
+
+    if let Some(ExtMetadataBlock::Level9(l9)) = vdr_dm_data.get_block(9) {
+        assert_eq!(
+            l9.source_primary_index,
+            MasteringDisplayPrimaries::BT2020 as u8
+        );
+    } else {
+        panic!("No L9 block");
+    }
+
+    // Overriden by `level11`
+    if let Some(ExtMetadataBlock::Level11(l11)) = vdr_dm_data.get_block(11) {
+        assert!(!l11.reference_mode_flag);
+    } else {
+        panic!("No L11 block");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_cmv29_requires_level9() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/tests/cmv2_9_xml_with_l5_rpu.bin");
+    let edit_config = temp.child("upgrade.json");
+    edit_config.write_str(r#"{ "upgrade_cmv29": true }"#)?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(temp.child("RPU.bin").as_ref())
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "level9 is required to upgrade CM v2.9 metadata",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn timecode_ranges() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+
+    let edit_config = temp.child("timecodes.json");
+    let cfg_file = std::fs::File::create(&edit_config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "frame_rate": "24000/1001",
+            "remove": ["00:00:05:00-00:00:09:23"],
+            "scene_cuts": {
+                "0.5s-1s": true
+            },
+            "duplicate": [
+                {
+                    "source": "00:00:01:00",
+                    "offset": 0,
+                    "length": 1
+                }
+            ]
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+
+    // 120 frames removed, one duplicated
+    assert_eq!(rpus.len(), 140);
+
+    let scene_refresh_flag =
+        |frame: usize| rpus[frame].vdr_dm_data.as_ref().unwrap().scene_refresh_flag;
+
+    // Offset by the duplicated frame, 12 to 24 are scene cuts
+    assert_eq!(scene_refresh_flag(12), 0);
+    assert_eq!(scene_refresh_flag(13), 1);
+    assert_eq!(scene_refresh_flag(25), 1);
+    assert_eq!(scene_refresh_flag(26), 0);
+
+    // Frame 24 was duplicated at the start
+    assert_eq!(rpus[0].rpu_data_crc32, rpus[25].rpu_data_crc32);
+
+    Ok(())
+}
+
+#[test]
+fn timecode_ranges_require_frame_rate() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+
+    let edit_config = temp.child("timecodes.json");
+    let cfg_file = std::fs::File::create(&edit_config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "remove": ["00:00:05:00-00:00:09:23"],
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("require a frame_rate"));
+
+    Ok(())
+}
+
+fn scene_cut_frames(rpus: &[dolby_vision::rpu::dovi_rpu::DoviRpu]) -> Vec<usize> {
+    rpus.iter()
+        .enumerate()
+        .filter(|(_, rpu)| rpu.vdr_dm_data.as_ref().unwrap().scene_refresh_flag == 1)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[test]
+fn frame_rate_conversion_pulldown() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+    let edit_config = Path::new("assets/editor_examples/pulldown.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 324);
+
+    // Source scene cuts at 0, 120 and 219
+    assert_eq!(scene_cut_frames(&rpus), vec![0, 150, 274]);
+
+    Ok(())
+}
+
+#[test]
+fn frame_rate_conversion_resample() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+
+    let edit_config = temp.child("resample.json");
+    let cfg_file = std::fs::File::create(&edit_config)?;
+    serde_json::to_writer(
+        cfg_file,
+        &serde_json::json!({
+            "frame_rate": "24",
+            "frame_rate_conversion": {
+                "mode": "resample",
+                "target_frame_rate": "12"
+            }
+        }),
+    )?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 130);
+
+    // Source frame 219 was dropped, the cut is moved to the next frame
+    assert_eq!(scene_cut_frames(&rpus), vec![0, 60, 110]);
+
+    Ok(())
+}
+
+#[test]
+fn reuse_mappings() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu_mel.bin");
+    let edit_config = Path::new("assets/editor_examples/remove.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .arg("--reuse-mappings")
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    output_rpu.assert(predicate::path::is_file());
+
+    let original_rpus = dolby_vision::rpu::utils::parse_rpu_file(input_rpu)?;
+    let mut rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 219);
+
+    // The first RPU after the removed frames has the mapping
+    assert!(!rpus[0].header.use_prev_vdr_rpu_flag);
+    assert!(rpus[1..].iter().all(|e| e.header.use_prev_vdr_rpu_flag));
+    assert!(rpus[1].rpu_data_mapping.is_none());
+
+    // The mappings are identical, so they can be resolved in any order
+    assert!(PrevVdrRpuResolver::resolve_all(&mut rpus).is_empty());
+    assert!(
+        rpus.iter()
+            .zip(&original_rpus[40..])
+            .all(|(rpu, orig)| rpu.rpu_data_mapping == orig.rpu_data_mapping
+                && rpu.el_type == orig.el_type)
+    );
+
+    assert!(std::fs::metadata(output_rpu)?.len() < std::fs::metadata(input_rpu)?.len() * 219 / 259);
+
+    Ok(())
+}
+
+#[test]
+fn reuse_mappings_different_mappings_error() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = temp.child("RPU_mixed.bin");
+    let edit_config = Path::new("assets/editor_examples/remove.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    // The second half of the frames has a different mapping
+    let mut rpus =
+        dolby_vision::rpu::utils::parse_rpu_file("assets/hevc_tests/regular_rpu_mel.bin")?;
+    let half = rpus.len() / 2;
+    for rpu in &mut rpus[half..] {
+        rpu.convert_with_mode(ConversionMode::To81MappingPreserved)?;
+    }
+
+    let mut data = Vec::new();
+    for rpu in &rpus {
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(&rpu.write_hevc_unspec62_nalu()?[2..]);
+    }
+    std::fs::write(input_rpu.path(), data)?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu.as_ref())
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .arg("--reuse-mappings")
+        .assert();
+
+    // Frame index in the output, after removing the first 40 frames
+    assert.failure().stderr(predicate::str::contains(format!(
+        "The mapping of RPU {} differs from the first RPU's, mappings can only be shared when they are all identical",
+        half - 40
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn reuse_mappings_duplicate_error() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu_mel.bin");
+    let edit_config = Path::new("assets/editor_examples/duplicate.json");
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--json")
+        .arg(edit_config)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .arg("--reuse-mappings")
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "--reuse-mappings cannot be used with duplicate edits",
+    ));
+
+    Ok(())
+}
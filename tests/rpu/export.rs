@@ -5,6 +5,12 @@ use assert_cmd::cargo;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
+use itertools::Itertools;
+
+use dolby_vision::rpu::dovi_rpu::DoviRpu;
+use dolby_vision::rpu::extension_metadata::blocks::{ExtMetadataBlock, ExtMetadataBlockLevel1};
+use dolby_vision::rpu::vdr_dm_data::CmVersion;
+
 const SUBCOMMAND: &str = "export";
 
 #[test]
@@ -74,3 +80,119 @@ fn export_all_and_scenes() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn export_madvr_measurements() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+    let madvr_file = temp.child("RPU_madVR.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--data")
+        .arg(format!("madvr={}", madvr_file.to_str().unwrap()))
+        .assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains(
+            "Exporting madVR measurement file...",
+        ));
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(input_rpu)?;
+    let measurements = madvr_parse::MadVRMeasurements::parse_file(madvr_file.path())?;
+
+    assert_eq!(measurements.frames.len(), rpus.len());
+
+    let scene_starts: Vec<u32> = measurements.scenes.iter().map(|s| s.start).collect();
+    assert_eq!(scene_starts, vec![0, 120, 219]);
+    assert_eq!(measurements.scenes[2].end, 258);
+
+    for (rpu, frame) in rpus.iter().zip(measurements.frames.iter()) {
+        let level1 = match rpu.vdr_dm_data.as_ref().and_then(|vdr| vdr.get_block(1)) {
+            Some(ExtMetadataBlock::Level1(b)) => b,
+            _ => panic!("missing L1"),
+        };
+
+        assert_eq!((frame.peak_pq_2020 * 4095.0).round() as u16, level1.max_pq);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn export_madvr_generate_round_trip() -> Result<()> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    // Two scenes, with averages below and above the CM v4.0 minimum
+    let input_rpu = Path::new("assets/tests/cmv2_9_xml_with_l5_rpu.bin");
+    let madvr_file = temp.child("RPU_madVR.bin");
+    let generate_config = temp.child("generate.json");
+    let output_rpu = temp.child("RPU_generated.bin");
+
+    cargo::cargo_bin_cmd!()
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--data")
+        .arg(format!("madvr={}", madvr_file.to_str().unwrap()))
+        .assert()
+        .success();
+
+    generate_config.write_str(r#"{ "cm_version": "V40", "length": 0 }"#)?;
+
+    cargo::cargo_bin_cmd!()
+        .arg("generate")
+        .arg("--json")
+        .arg(generate_config.as_ref())
+        .arg("--madvr-file")
+        .arg(madvr_file.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+
+    let level1 = |rpu: &DoviRpu| match rpu.vdr_dm_data.as_ref().and_then(|vdr| vdr.get_block(1)) {
+        Some(ExtMetadataBlock::Level1(b)) => b.clone(),
+        _ => panic!("missing L1"),
+    };
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(input_rpu)?;
+    let generated = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(generated.len(), rpus.len());
+
+    let mut scene_starts: Vec<usize> = rpus
+        .iter()
+        .enumerate()
+        .filter(|(i, rpu)| *i == 0 || rpu.vdr_dm_data.as_ref().unwrap().scene_refresh_flag == 1)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(scene_starts.len(), 2);
+    scene_starts.push(rpus.len());
+
+    // The generated L1 is per shot: the max of the frame peaks and averages
+    for (start, end) in scene_starts.iter().copied().tuple_windows() {
+        let (max_pq, avg_pq) = rpus[start..end]
+            .iter()
+            .map(level1)
+            .fold((0, 0), |(max, avg), l1| {
+                (max.max(l1.max_pq), avg.max(l1.avg_pq))
+            });
+        let expected =
+            ExtMetadataBlockLevel1::from_stats_cm_version(0, max_pq, avg_pq, CmVersion::V40);
+
+        for rpu in &generated[start..end] {
+            let generated_l1 = level1(rpu);
+
+            // The scene peak is stored in integer nits
+            assert!(generated_l1.max_pq.abs_diff(expected.max_pq) <= 1);
+            assert_eq!(generated_l1.avg_pq, expected.avg_pq);
+        }
+    }
+
+    Ok(())
+}
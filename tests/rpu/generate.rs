@@ -416,6 +416,81 @@ fn xml_cmv4_0_2() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn xml_list_outputs() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let xml = Path::new("assets/tests/cmv4_0_2_multiple_outputs.xml");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--xml")
+        .arg(xml)
+        .arg("--list-xml-outputs")
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty()).stdout(
+        predicate::str::contains("Output 0: Timeline 1, canvas AR 1.77778, image AR 1.33333")
+            .and(predicate::str::contains("Output 1: Scope"))
+            .and(predicate::str::contains("  Track 1: V2")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn xml_select_output_and_track() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let xml = Path::new("assets/tests/cmv4_0_2_multiple_outputs.xml");
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--xml")
+        .arg(xml)
+        .arg("--xml-output")
+        .arg("Scope")
+        .arg("--xml-track")
+        .arg("1")
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.path())?;
+    assert_eq!(rpus.len(), 120);
+
+    Ok(())
+}
+
+#[test]
+fn xml_unknown_output() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let xml = Path::new("assets/tests/cmv4_0_2_multiple_outputs.xml");
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--xml")
+        .arg(xml)
+        .arg("--xml-output")
+        .arg("Flat")
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "Could not find Output `Flat`. Available: [0: Timeline 1, 1: Scope]",
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn xml_cmv4_0_2_with_l5() -> Result<()> {
     let mut cmd = cargo::cargo_bin_cmd!();
@@ -686,3 +761,330 @@ fn l1_cmv29_override_avg_cmv40() -> Result<()> {
 
     Ok(())
 }
+
+/// 10 bit 4:2:0 Y4M with 4 frames of a first scene, and 6 frames of a second scene.
+/// The top half of the frames is darker than the bottom half.
+fn two_scenes_y4m(header: bool) -> Vec<u8> {
+    let (width, height) = (16, 8);
+
+    let mut data = Vec::new();
+    if header {
+        data.extend_from_slice(b"YUV4MPEG2 W16 H8 F24000:1001 Ip A1:1 C420p10 XYSCSS=420P10\n");
+    }
+
+    for (dark, bright) in [(64_u16, 502_u16); 4].into_iter().chain([(300, 940); 6]) {
+        if header {
+            data.extend_from_slice(b"FRAME\n");
+        }
+
+        let luma = (0..height).flat_map(|row| {
+            let value = if row < height / 2 { dark } else { bright };
+            std::iter::repeat_n(value, width)
+        });
+        let chroma = std::iter::repeat_n(512_u16, width * height / 2);
+
+        data.extend(luma.chain(chroma).flat_map(u16::to_le_bytes));
+    }
+
+    data
+}
+
+#[test]
+fn y4m_measurement() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let generate_config = Path::new("assets/generator_examples/default_cmv40.json");
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(generate_config)
+        .arg("--y4m")
+        .arg("-")
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .write_stdin(two_scenes_y4m(true))
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 10);
+
+    let scene_cuts: Vec<usize> = rpus
+        .iter()
+        .enumerate()
+        .filter(|(_, rpu)| rpu.vdr_dm_data.as_ref().unwrap().scene_refresh_flag == 1)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(scene_cuts, vec![0, 4]);
+
+    let vdr_dm_data = rpus[0].vdr_dm_data.as_ref().unwrap();
+    if let ExtMetadataBlock::Level1(level1) = vdr_dm_data.get_block(1).unwrap() {
+        assert_eq!(level1.min_pq, 0);
+        // Clamped to 2081
+        assert_eq!(level1.max_pq, 2081);
+        // Clamped to 1229
+        assert_eq!(level1.avg_pq, 1229);
+    }
+
+    let vdr_dm_data = rpus[9].vdr_dm_data.as_ref().unwrap();
+    if let ExtMetadataBlock::Level1(level1) = vdr_dm_data.get_block(1).unwrap() {
+        // Clamped to 12
+        assert_eq!(level1.min_pq, 12);
+        assert_eq!(level1.max_pq, 4095);
+        assert_eq!(level1.avg_pq, 2599);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn raw_yuv_measurement() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let generate_config = Path::new("assets/generator_examples/default_cmv40.json");
+    let raw_yuv = temp.child("video.yuv");
+    raw_yuv.write_binary(&two_scenes_y4m(false))?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(generate_config)
+        .arg("--raw-yuv")
+        .arg(raw_yuv.as_ref())
+        .arg("--frame-width")
+        .arg("16")
+        .arg("--frame-height")
+        .arg("8")
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 10);
+    assert_eq!(rpus[4].vdr_dm_data.as_ref().unwrap().scene_refresh_flag, 1);
+
+    Ok(())
+}
+
+#[test]
+fn raw_yuv_requires_dimensions() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg("assets/generator_examples/default_cmv40.json")
+        .arg("--raw-yuv")
+        .arg("video.yuv")
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("--frame-width <frame-width>"));
+
+    Ok(())
+}
+
+#[test]
+fn raw_yuv_options_require_raw_yuv() -> Result<()> {
+    let raw_yuv_options: [&[&str]; 4] = [
+        &["--bit-depth", "12"],
+        &["--frame-width", "1920"],
+        &["--frame-height", "1080"],
+        &["--full-range"],
+    ];
+
+    for args in raw_yuv_options {
+        let mut cmd = cargo::cargo_bin_cmd!();
+
+        let assert = cmd
+            .arg(SUBCOMMAND)
+            .arg("--json")
+            .arg("assets/generator_examples/default_cmv40.json")
+            .arg("--y4m")
+            .arg("video.y4m")
+            .args(args)
+            .assert();
+
+        assert
+            .failure()
+            .stderr(predicate::str::contains("--raw-yuv <raw-yuv>"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn csv_shot_list() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let generate_config = Path::new("assets/generator_examples/default_cmv40.json");
+    let shot_list = Path::new("assets/generator_examples/shot_list.csv");
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(generate_config)
+        .arg("--csv")
+        .arg(shot_list)
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 10);
+
+    let shot1_vdr_dm_data = rpus[0].vdr_dm_data.as_ref().unwrap();
+    assert_eq!(shot1_vdr_dm_data.scene_refresh_flag, 1);
+
+    if let ExtMetadataBlock::Level1(level1) = shot1_vdr_dm_data.get_block(1).unwrap() {
+        assert_eq!(level1.min_pq, 0);
+        assert_eq!(level1.max_pq, 3079);
+        assert_eq!(level1.avg_pq, 2081);
+    }
+
+    if let ExtMetadataBlock::Level2(level2) = shot1_vdr_dm_data.get_block(2).unwrap() {
+        assert_eq!(level2.target_max_pq, 2081);
+        assert_eq!(level2.trim_slope, 2100);
+        assert_eq!(level2.trim_offset, 2000);
+        assert_eq!(level2.trim_power, 1900);
+    }
+
+    if let ExtMetadataBlock::Level8(level8) = shot1_vdr_dm_data.get_block(8).unwrap() {
+        assert_eq!(level8.target_display_index, 1);
+        assert_eq!(level8.trim_slope, 2100);
+        assert_eq!(level8.trim_power, 1900);
+    }
+
+    if let ExtMetadataBlock::Level5(level5) = shot1_vdr_dm_data.get_block(5).unwrap() {
+        assert_eq!(level5.get_offsets(), (0, 0, 276, 276));
+    }
+
+    let shot2_vdr_dm_data = rpus[4].vdr_dm_data.as_ref().unwrap();
+    assert_eq!(shot2_vdr_dm_data.scene_refresh_flag, 1);
+    assert!(shot2_vdr_dm_data.get_block(2).is_none());
+    assert!(shot2_vdr_dm_data.get_block(8).is_none());
+
+    if let ExtMetadataBlock::Level1(level1) = shot2_vdr_dm_data.get_block(1).unwrap() {
+        // Clamped to 12
+        assert_eq!(level1.min_pq, 12);
+        assert_eq!(level1.max_pq, 3696);
+        assert_eq!(level1.avg_pq, 1803);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn plain_text_shot_list_timecodes() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let generate_config = Path::new("assets/generator_examples/default_cmv40.json");
+    let shot_list = temp.child("shots.txt");
+    shot_list.write_str("start max_pq avg_pq\n00:00:00:00 3079 2081\n00:00:00:06 2800 1500\n")?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(generate_config)
+        .arg("--csv")
+        .arg(shot_list.as_ref())
+        .arg("--frame-rate")
+        .arg("24")
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 10);
+
+    let vdr_dm_data = rpus[6].vdr_dm_data.as_ref().unwrap();
+    assert_eq!(vdr_dm_data.scene_refresh_flag, 1);
+
+    if let ExtMetadataBlock::Level1(level1) = vdr_dm_data.get_block(1).unwrap() {
+        assert_eq!(level1.max_pq, 2800);
+        assert_eq!(level1.avg_pq, 1500);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn shot_list_unknown_column() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let shot_list = temp.child("shots.csv");
+    shot_list.write_str("start,duration,l2_100_clip_trim\n0,10,2048\n")?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg("assets/generator_examples/default_cmv40.json")
+        .arg("--csv")
+        .arg(shot_list.as_ref())
+        .arg("--rpu-out")
+        .arg(temp.child("RPU.bin").as_ref())
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "Unknown shot list column `l2_100_clip_trim`: L2 has no ClipTrim",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn scene_cuts_list() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let generate_config = Path::new("assets/generator_examples/default_cmv40.json");
+    let scene_cuts = temp.child("scenes.txt");
+    scene_cuts.write_str("0\n3\n7\n")?;
+
+    let output_rpu = temp.child("RPU.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("--json")
+        .arg(generate_config)
+        .arg("--scene-cuts")
+        .arg(scene_cuts.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu.as_ref())?;
+    assert_eq!(rpus.len(), 10);
+
+    let scene_cuts: Vec<usize> = rpus
+        .iter()
+        .enumerate()
+        .filter(|(_, rpu)| rpu.vdr_dm_data.as_ref().unwrap().scene_refresh_flag == 1)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(scene_cuts, vec![0, 3, 7]);
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use std::path::Path;
 
 use anyhow::Result;
 use assert_cmd::cargo;
+use assert_fs::prelude::*;
 use predicates::prelude::*;
 
 const SUBCOMMAND: &str = "info";
@@ -101,3 +102,207 @@ fn invalid_l3_error() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn scenes_csv() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+
+    let assert = cmd.arg(SUBCOMMAND).arg(input_rpu).arg("--scenes").assert();
+
+    assert.success().stderr(predicate::str::is_empty()).stdout(
+        predicate::str::starts_with(
+            "start,end,duration,l1_min_nits,l1_max_nits,l1_avg_nits,l2_100_slope",
+        )
+        .and(predicate::str::contains(
+            "\n0,119,120,0.0000,569.2454,7.1126,",
+        ))
+        .and(predicate::str::contains(
+            "\n120,218,99,0.0000,100.1020,10.0538,2013,2016,1339,",
+        ))
+        .and(predicate::str::contains(
+            "\n219,258,40,0.0000,632.8840,2.5292,",
+        ))
+        .and(predicate::str::contains("Parsing RPU file...").not()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn scenes_json() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+    let output_json = temp.child("scenes.json");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--scenes")
+        .arg("--json")
+        .arg("-o")
+        .arg(output_json.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let scenes: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(output_json.path())?)?;
+    let scenes = scenes.as_array().unwrap();
+
+    assert_eq!(scenes.len(), 3);
+    assert_eq!(scenes[1]["start"], 120);
+    assert_eq!(scenes[1]["duration"], 99);
+    assert_eq!(scenes[1]["l2"][0]["target_nits"], 100);
+    assert_eq!(scenes[1]["l2"][0]["trim_power"], 1339);
+    assert_eq!(scenes[1]["l8"][0]["clip_trim"], 2011);
+    assert_eq!(scenes[2]["l3"]["avg_pq_offset"], 1871);
+
+    Ok(())
+}
+
+#[test]
+fn summary_json() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu_mel.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--summary")
+        .arg("--json")
+        .assert();
+
+    let assert = assert.success().stderr(predicate::str::is_empty());
+    let summary: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+
+    assert_eq!(summary["frames"], 259);
+    assert_eq!(summary["profiles"], serde_json::json!([7]));
+    assert_eq!(summary["el_types"], serde_json::json!(["MEL"]));
+    assert_eq!(summary["dm_version"]["cmv40_count"], 259);
+    assert_eq!(summary["scene_count"], 3);
+    assert_eq!(summary["l2_targets"], serde_json::json!([100, 600, 1000]));
+    assert_eq!(summary["l6"][0]["max_content_light_level"], 3948);
+    assert_eq!(summary["l9_mdp"][0]["primaries"], "DCI-P3 D65");
+
+    Ok(())
+}
+
+#[test]
+fn json_requires_mode() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--frame")
+        .arg("0")
+        .arg("--json")
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("<--summary|--scenes|--trace>"));
+
+    Ok(())
+}
+
+#[test]
+fn summary_output_requires_json() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let input_rpu = Path::new("assets/hevc_tests/regular_rpu.bin");
+    let output_json = temp.child("summary.json");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--summary")
+        .arg("-o")
+        .arg(output_json.as_ref())
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("<--scenes|--trace|--json>"));
+    output_json.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn trace_tree() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let input_rpu = Path::new("assets/tests/fel_orig.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--trace")
+        .arg("--frame")
+        .arg("0")
+        .assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains("rpu_data_header"))
+        .stdout(predicate::str::contains("cmp[0] pivot_idx[0]"))
+        .stdout(predicate::str::contains("rpu_data_crc32"));
+
+    Ok(())
+}
+
+#[test]
+fn trace_json() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let input_rpu = Path::new("assets/tests/fel_orig.bin");
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(input_rpu)
+        .arg("--trace")
+        .arg("--frame")
+        .arg("0")
+        .arg("--json")
+        .assert();
+
+    let assert = assert.success().stderr(predicate::str::is_empty());
+    let trace: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+
+    let prefix = &trace["nodes"][0];
+    assert_eq!(prefix["name"], "rpu_nal_prefix");
+    assert_eq!(prefix["offset"], 0);
+    assert_eq!(prefix["width"], 8);
+    assert_eq!(prefix["value"], 25);
+
+    let header = &trace["nodes"][1];
+    assert_eq!(header["name"], "rpu_data_header");
+    assert_eq!(header["offset"], 8);
+
+    Ok(())
+}
+
+#[test]
+fn trace_requires_frame() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let input_rpu = Path::new("assets/tests/fel_orig.bin");
+
+    let assert = cmd.arg(SUBCOMMAND).arg(input_rpu).arg("--trace").assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("--frame <frame>"));
+
+    Ok(())
+}
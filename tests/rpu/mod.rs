@@ -1,5 +1,9 @@
+mod concat;
+mod detect_active_area;
 mod editor;
 mod export;
 mod generate;
 mod info;
 mod plot;
+mod repair;
+mod split;
@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use anyhow::Result;
+use assert_cmd::cargo;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+const SUBCOMMAND: &str = "repair";
+const INPUT: &str = "assets/hevc_tests/regular_rpu.bin";
+
+/// Splits the RPU file into NALUs, without start codes
+fn split_nalus(data: &[u8]) -> Vec<Vec<u8>> {
+    let starts: Vec<usize> = data
+        .windows(4)
+        .enumerate()
+        .filter(|(_, w)| *w == [0, 0, 0, 1])
+        .map(|(i, _)| i)
+        .collect();
+
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain(std::iter::once(&data.len())))
+        .map(|(start, end)| data[start + 4..*end].to_vec())
+        .collect()
+}
+
+/// Writes the RPU file with damaged frames, returns the original data
+fn write_damaged(path: &Path, damage: impl Fn(usize, &mut Vec<u8>)) -> Result<Vec<u8>> {
+    let data = std::fs::read(INPUT)?;
+
+    let damaged: Vec<u8> = split_nalus(&data)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, mut nalu)| {
+            damage(i, &mut nalu);
+            [0, 0, 0, 1].into_iter().chain(nalu)
+        })
+        .collect();
+    std::fs::write(path, damaged)?;
+
+    Ok(data)
+}
+
+/// Damages the CRC32 of frame 5 and appends garbage to frame 20
+fn repairable_damage(i: usize, nalu: &mut Vec<u8>) {
+    let len = nalu.len();
+
+    match i {
+        5 => nalu[len - 2] ^= 0x10,
+        20 => nalu.extend_from_slice(&[0x12, 0x34]),
+        _ => (),
+    }
+}
+
+#[test]
+fn help() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let assert = cmd.arg(SUBCOMMAND).arg("--help").assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains(
+            "dovi_tool repair [OPTIONS] [input_pos]",
+        ));
+    Ok(())
+}
+
+#[test]
+fn repaired() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let damaged = temp.child("damaged.bin");
+    let output_rpu = temp.child("RPU_repaired.bin");
+    let report = temp.child("report.json");
+
+    let original = write_damaged(damaged.path(), repairable_damage)?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(damaged.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .arg("--report")
+        .arg(report.as_ref())
+        .assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains("259 RPUs, 2 repaired, 0 invalid"));
+
+    assert_eq!(std::fs::read(output_rpu.path())?, original);
+
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(report)?)?;
+    let frames: Vec<u64> = report["repaired"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["frame"].as_u64().unwrap())
+        .collect();
+    assert_eq!(frames, vec![5, 20]);
+    assert!(report["invalid"].as_array().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn invalid_frames() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let damaged = temp.child("damaged.bin");
+    let output_rpu = temp.child("RPU_repaired.bin");
+
+    write_damaged(damaged.path(), |i, nalu| {
+        if i == 10 {
+            nalu.truncate(nalu.len() / 2);
+            nalu.push(0x80);
+        }
+    })?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(damaged.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "Error: 1 RPUs could not be repaired, use --replace-invalid to replace them",
+    ));
+    output_rpu.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn replace_invalid() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let damaged = temp.child("damaged.bin");
+    let output_rpu = temp.child("RPU_repaired.bin");
+    let report = temp.child("report.json");
+
+    write_damaged(damaged.path(), |i, nalu| {
+        repairable_damage(i, nalu);
+
+        // Unrecoverable: the first frame, two consecutive frames,
+        // and the end of a shot, right before the scene cut at frame 120
+        if matches!(i, 0 | 10 | 11 | 118 | 119) {
+            nalu.truncate(nalu.len() / 2);
+            nalu.push(0x80);
+        }
+    })?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg(damaged.as_ref())
+        .arg("--rpu-out")
+        .arg(output_rpu.as_ref())
+        .arg("--replace-invalid")
+        .arg("--report")
+        .arg(report.as_ref())
+        .assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains("Frame 11: replaced by frame 12"))
+        .stdout(predicate::str::contains("Frame 119: replaced by frame 117"))
+        .stdout(predicate::str::contains("259 RPUs, 2 repaired, 5 invalid"));
+
+    let original = dolby_vision::rpu::utils::parse_rpu_file(INPUT)?;
+    let rpus = dolby_vision::rpu::utils::parse_rpu_file(output_rpu)?;
+    assert_eq!(rpus.len(), 259);
+
+    let expected_replaced: Vec<(u64, u64)> =
+        vec![(0, 1), (10, 9), (11, 12), (118, 117), (119, 117)];
+
+    for &(frame, source) in &expected_replaced {
+        let (frame, source) = (frame as usize, source as usize);
+        let mut expected = original[source].clone();
+        expected
+            .vdr_dm_data
+            .as_mut()
+            .unwrap()
+            .set_scene_cut(frame == 0);
+
+        assert_eq!(rpus[frame].write_rpu()?, {
+            expected.modified = true;
+            expected.write_rpu()?
+        });
+    }
+
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(report)?)?;
+    let replaced: Vec<(u64, u64)> = report["invalid"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["frame"].as_u64().unwrap(),
+                r["replaced_by"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(replaced, expected_replaced);
+
+    Ok(())
+}
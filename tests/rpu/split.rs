@@ -0,0 +1,237 @@
+use anyhow::Result;
+use assert_cmd::cargo;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use dolby_vision::rpu::prev_vdr_rpu::PrevVdrRpuResolver;
+
+const SUBCOMMAND: &str = "split";
+
+fn segment_lengths(dir: &assert_fs::TempDir, count: usize) -> Result<Vec<usize>> {
+    (1..=count)
+        .map(|i| {
+            let segment = dir.child(format!("regular_rpu_{i:03}.bin"));
+            let rpus = dolby_vision::rpu::utils::parse_rpu_file(segment)?;
+
+            assert_eq!(rpus[0].vdr_dm_data.as_ref().unwrap().scene_refresh_flag, 1);
+
+            Ok(rpus.len())
+        })
+        .collect()
+}
+
+#[test]
+fn help() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let assert = cmd.arg(SUBCOMMAND).arg("--help").assert();
+
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains(
+            "dovi_tool split [OPTIONS] <--ranges <RANGES>|--scene-cuts <SCENE_CUTS>|--chapters <CHAPTERS>|--scenes <SCENES>> [input_pos]",
+        ));
+    Ok(())
+}
+
+#[test]
+fn every_scene() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("--scenes")
+        .arg("1")
+        .arg("-o")
+        .arg(temp.path())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+    temp.child("regular_rpu_004.bin")
+        .assert(predicate::path::missing());
+
+    assert_eq!(segment_lengths(&temp, 3)?, vec![120, 99, 40]);
+
+    Ok(())
+}
+
+#[test]
+fn ranges() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("--ranges")
+        .arg("10-19,200-258")
+        .arg("-o")
+        .arg(temp.path())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    assert_eq!(segment_lengths(&temp, 2)?, vec![10, 59]);
+
+    Ok(())
+}
+
+#[test]
+fn scene_cuts_list() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let scene_cuts = temp.child("scenes.txt");
+    scene_cuts.write_str("50\n150\n")?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("--scene-cuts")
+        .arg(scene_cuts.as_ref())
+        .arg("-o")
+        .arg(temp.path())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    assert_eq!(segment_lengths(&temp, 3)?, vec![50, 100, 109]);
+
+    Ok(())
+}
+
+#[test]
+fn chapters_xml() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let chapters = temp.child("chapters.xml");
+    chapters.write_str(
+        r#"<?xml version="1.0"?>
+<Chapters>
+  <EditionEntry>
+    <ChapterAtom>
+      <ChapterTimeStart>00:00:00.000000000</ChapterTimeStart>
+    </ChapterAtom>
+    <ChapterAtom>
+      <ChapterTimeStart>00:00:05.000000000</ChapterTimeStart>
+    </ChapterAtom>
+  </EditionEntry>
+</Chapters>
+"#,
+    )?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("--chapters")
+        .arg(chapters.as_ref())
+        .arg("--frame-rate")
+        .arg("24")
+        .arg("-o")
+        .arg(temp.path())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    assert_eq!(segment_lengths(&temp, 2)?, vec![120, 139]);
+
+    Ok(())
+}
+
+#[test]
+fn chapters_require_frame_rate() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("--chapters")
+        .arg("chapters.xml")
+        .assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("--frame-rate <FRAME_RATE>"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_split_frame() -> Result<()> {
+    let mut cmd = cargo::cargo_bin_cmd!();
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let scene_cuts = temp.child("scenes.txt");
+    scene_cuts.write_str("50\n259\n")?;
+
+    let assert = cmd
+        .arg(SUBCOMMAND)
+        .arg("assets/hevc_tests/regular_rpu.bin")
+        .arg("--scene-cuts")
+        .arg(scene_cuts.as_ref())
+        .arg("-o")
+        .arg(temp.path())
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "Invalid split frame: 259 >= 259 available RPUs",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn shared_mappings() -> Result<()> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let edit_config = temp.child("edit.json");
+    edit_config.write_str("{}")?;
+
+    let shared_rpu = temp.child("shared.bin");
+    cargo::cargo_bin_cmd!()
+        .arg("editor")
+        .arg("assets/hevc_tests/regular_rpu_mel.bin")
+        .arg("--json")
+        .arg(edit_config.as_ref())
+        .arg("--rpu-out")
+        .arg(shared_rpu.as_ref())
+        .arg("--reuse-mappings")
+        .assert()
+        .success();
+
+    let output_dir = temp.child("segments");
+
+    let assert = cargo::cargo_bin_cmd!()
+        .arg(SUBCOMMAND)
+        .arg(shared_rpu.as_ref())
+        .arg("--ranges")
+        .arg("0-99,100-258")
+        .arg("-o")
+        .arg(output_dir.as_ref())
+        .assert();
+
+    assert.success().stderr(predicate::str::is_empty());
+
+    let original_rpus =
+        dolby_vision::rpu::utils::parse_rpu_file("assets/hevc_tests/regular_rpu_mel.bin")?;
+
+    for i in 1..=2 {
+        let segment = output_dir.child(format!("shared_{i:03}.bin"));
+        let mut rpus = dolby_vision::rpu::utils::parse_rpu_file(segment)?;
+
+        // Each segment can be decoded on its own
+        assert!(!rpus[0].header.use_prev_vdr_rpu_flag);
+        assert!(rpus[1..].iter().all(|e| e.header.use_prev_vdr_rpu_flag));
+
+        assert!(PrevVdrRpuResolver::resolve_all(&mut rpus).is_empty());
+        assert!(
+            rpus.iter()
+                .all(|rpu| rpu.rpu_data_mapping == original_rpus[0].rpu_data_mapping)
+        );
+    }
+
+    Ok(())
+}